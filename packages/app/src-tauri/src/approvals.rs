@@ -0,0 +1,390 @@
+use crate::config;
+use crate::project::AppStateMutex;
+use crate::state::{ApprovalSettings, Decision};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{sync_channel, RecvTimeoutError, SyncSender};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Hook events the app can answer with a permission decision.
+pub const APPROVAL_EVENTS: &[&str] = &["PreToolUse", "PermissionRequest"];
+
+/// Longest a request can be held. The hook script gives up after 58s and
+/// Claude Code kills the hook at 60s, so the default decision has to be sent
+/// before either.
+pub const MAX_TIMEOUT_SECS: u64 = 55;
+
+/// Requests held open at once. Each one occupies a hook server worker, so
+/// later ones fall back to the tool's own prompt to keep workers free for
+/// daemon events and the other hooks.
+pub const MAX_WAITING: usize = 12;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    pub session_id: String,
+    pub hook_event_name: String,
+    pub tool_name: Option<String>,
+    pub tool_input: Option<serde_json::Value>,
+    pub timeout_secs: u64,
+    /// Unix epoch milliseconds
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalOutcome {
+    pub decision: Decision,
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct ApprovalResolved {
+    id: String,
+    session_id: String,
+    decision: Decision,
+    reason: Option<String>,
+    /// True when nobody answered and the default decision was applied
+    timed_out: bool,
+}
+
+struct Pending {
+    request: ApprovalRequest,
+    reply: SyncSender<ApprovalOutcome>,
+}
+
+/// Hook requests that are being held open until the user answers them in the
+/// UI (or the timeout applies the default decision).
+pub struct ApprovalManager {
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl ApprovalManager {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Emit `approval-request` and block until it is answered or times out.
+    /// Answers `Ask` right away if `MAX_WAITING` requests are already held.
+    pub fn wait_for_decision(
+        &self,
+        app: &AppHandle,
+        settings: &ApprovalSettings,
+        session_id: &str,
+        hook_event_name: &str,
+        tool_name: Option<String>,
+        tool_input: Option<serde_json::Value>,
+    ) -> ApprovalOutcome {
+        let request = ApprovalRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            hook_event_name: hook_event_name.to_string(),
+            tool_name,
+            tool_input,
+            timeout_secs: settings.timeout_secs.min(MAX_TIMEOUT_SECS),
+            created_at: now_ms(),
+        };
+        self.hold(request, settings.default_decision, |event, payload| {
+            let _ = app.emit(event, payload);
+        })
+    }
+
+    /// Hold `request` until it is answered or its timeout applies
+    /// `default_decision`, reporting both ends through `emit`.
+    fn hold(
+        &self,
+        request: ApprovalRequest,
+        default_decision: Decision,
+        emit: impl Fn(&str, serde_json::Value),
+    ) -> ApprovalOutcome {
+        let (tx, rx) = sync_channel(1);
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.len() >= MAX_WAITING {
+                log::warn!("{MAX_WAITING} approvals already waiting; leaving this one to the tool");
+                return ApprovalOutcome {
+                    decision: Decision::Ask,
+                    reason: Some("Too many approvals waiting in touchgrass app".into()),
+                };
+            }
+            pending.insert(
+                request.id.clone(),
+                Pending {
+                    request: request.clone(),
+                    reply: tx,
+                },
+            );
+        }
+        emit("approval-request", to_json(&request));
+
+        let timeout = Duration::from_secs(request.timeout_secs);
+        let (outcome, timed_out) = match rx.recv_timeout(timeout) {
+            Ok(outcome) => (outcome, false),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => (
+                ApprovalOutcome {
+                    decision: default_decision,
+                    reason: Some("No response from touchgrass app in time".into()),
+                },
+                true,
+            ),
+        };
+        self.pending.lock().unwrap().remove(&request.id);
+
+        emit(
+            "approval-resolved",
+            to_json(&ApprovalResolved {
+                id: request.id,
+                session_id: request.session_id,
+                decision: outcome.decision,
+                reason: outcome.reason.clone(),
+                timed_out,
+            }),
+        );
+        outcome
+    }
+
+    fn respond(&self, id: &str, outcome: ApprovalOutcome) -> Result<(), String> {
+        let pending = self.pending.lock().unwrap();
+        let entry = pending
+            .get(id)
+            .ok_or("Approval request not found or expired")?;
+        entry
+            .reply
+            .try_send(outcome)
+            .map_err(|_| "Approval request already answered".to_string())
+    }
+
+    fn list(&self) -> Vec<ApprovalRequest> {
+        let pending = self.pending.lock().unwrap();
+        let mut list: Vec<_> = pending.values().map(|p| p.request.clone()).collect();
+        list.sort_by_key(|r| r.created_at);
+        list
+    }
+}
+
+/// Claude Code hook output for a decision on `hook_event_name`.
+pub fn hook_decision_json(hook_event_name: &str, outcome: &ApprovalOutcome) -> serde_json::Value {
+    if hook_event_name == "PermissionRequest" {
+        // PermissionRequest has no "ask": leave the decision to Claude's own prompt
+        let decision = match outcome.decision {
+            Decision::Allow => serde_json::json!({ "behavior": "allow" }),
+            Decision::Deny => serde_json::json!({
+                "behavior": "deny",
+                "message": outcome.reason.clone().unwrap_or_else(|| "Denied in touchgrass app".into()),
+            }),
+            Decision::Ask => return serde_json::json!({}),
+        };
+        return serde_json::json!({
+            "hookSpecificOutput": {
+                "hookEventName": "PermissionRequest",
+                "decision": decision,
+            }
+        });
+    }
+
+    let mut output = serde_json::json!({
+        "hookEventName": hook_event_name,
+        "permissionDecision": outcome.decision,
+    });
+    if let Some(reason) = &outcome.reason {
+        output["permissionDecisionReason"] = serde_json::Value::String(reason.clone());
+    }
+    serde_json::json!({ "hookSpecificOutput": output })
+}
+
+fn to_json(value: &impl Serialize) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Approval settings, read fresh for each hook request.
+pub fn current_settings(app: &AppHandle) -> ApprovalSettings {
+    let state = app.state::<AppStateMutex>();
+    let s = state.lock().unwrap();
+    s.approvals.clone()
+}
+
+#[tauri::command]
+pub fn get_approval_settings(state: State<'_, AppStateMutex>) -> ApprovalSettings {
+    let s = state.lock().unwrap();
+    s.approvals.clone()
+}
+
+#[tauri::command]
+pub fn set_approval_settings(
+    state: State<'_, AppStateMutex>,
+    mut settings: ApprovalSettings,
+) -> Result<(), String> {
+    if settings.timeout_secs == 0 {
+        return Err("Timeout must be at least 1 second.".into());
+    }
+    settings.timeout_secs = settings.timeout_secs.min(MAX_TIMEOUT_SECS);
+    let mut s = state.lock().unwrap();
+    s.approvals = settings;
+    config::save_state(&s);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_pending_approvals(approvals: State<'_, ApprovalManager>) -> Vec<ApprovalRequest> {
+    approvals.list()
+}
+
+#[tauri::command]
+pub fn respond_to_approval(
+    approvals: State<'_, ApprovalManager>,
+    request_id: String,
+    decision: Decision,
+    reason: Option<String>,
+) -> Result<(), String> {
+    approvals.respond(&request_id, ApprovalOutcome { decision, reason })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn request(id: &str, timeout_secs: u64) -> ApprovalRequest {
+        ApprovalRequest {
+            id: id.to_string(),
+            session_id: "s-1".into(),
+            hook_event_name: "PreToolUse".into(),
+            tool_name: Some("Bash".into()),
+            tool_input: Some(serde_json::json!({ "command": "ls" })),
+            timeout_secs,
+            created_at: 0,
+        }
+    }
+
+    fn outcome(decision: Decision, reason: Option<&str>) -> ApprovalOutcome {
+        ApprovalOutcome {
+            decision,
+            reason: reason.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn pre_tool_use_decisions_carry_the_reason() {
+        assert_eq!(
+            hook_decision_json("PreToolUse", &outcome(Decision::Deny, Some("no"))),
+            serde_json::json!({ "hookSpecificOutput": {
+                "hookEventName": "PreToolUse",
+                "permissionDecision": "deny",
+                "permissionDecisionReason": "no",
+            } })
+        );
+        assert_eq!(
+            hook_decision_json("PreToolUse", &outcome(Decision::Ask, None)),
+            serde_json::json!({ "hookSpecificOutput": {
+                "hookEventName": "PreToolUse",
+                "permissionDecision": "ask",
+            } })
+        );
+    }
+
+    #[test]
+    fn permission_request_has_no_ask() {
+        assert_eq!(
+            hook_decision_json("PermissionRequest", &outcome(Decision::Allow, Some("ok"))),
+            serde_json::json!({ "hookSpecificOutput": {
+                "hookEventName": "PermissionRequest",
+                "decision": { "behavior": "allow" },
+            } })
+        );
+        assert_eq!(
+            hook_decision_json("PermissionRequest", &outcome(Decision::Deny, None)),
+            serde_json::json!({ "hookSpecificOutput": {
+                "hookEventName": "PermissionRequest",
+                "decision": { "behavior": "deny", "message": "Denied in touchgrass app" },
+            } })
+        );
+        // Left to Claude's own prompt
+        assert_eq!(
+            hook_decision_json("PermissionRequest", &outcome(Decision::Ask, None)),
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn answered_request_resolves_once() {
+        let approvals = ApprovalManager::new();
+        let events = RefCell::new(Vec::new());
+        let result = approvals.hold(request("a-1", 5), Decision::Deny, |event, payload| {
+            if event == "approval-request" {
+                assert_eq!(approvals.list().len(), 1);
+                assert!(approvals
+                    .respond("a-1", outcome(Decision::Allow, Some("fine")))
+                    .is_ok());
+                assert_eq!(
+                    approvals.respond("a-1", outcome(Decision::Deny, None)),
+                    Err("Approval request already answered".to_string())
+                );
+            }
+            events.borrow_mut().push((event.to_string(), payload));
+        });
+
+        assert_eq!(result.decision, Decision::Allow);
+        assert_eq!(result.reason.as_deref(), Some("fine"));
+        let events = events.into_inner();
+        assert_eq!(events[0].0, "approval-request");
+        assert_eq!(events[1].0, "approval-resolved");
+        assert_eq!(events[1].1["decision"], "allow");
+        assert_eq!(events[1].1["timed_out"], false);
+
+        assert!(approvals.list().is_empty());
+        assert_eq!(
+            approvals.respond("a-1", outcome(Decision::Allow, None)),
+            Err("Approval request not found or expired".to_string())
+        );
+    }
+
+    #[test]
+    fn timeout_applies_the_default_decision() {
+        let approvals = ApprovalManager::new();
+        let events = RefCell::new(Vec::new());
+        let result = approvals.hold(request("a-1", 0), Decision::Deny, |event, payload| {
+            events.borrow_mut().push((event.to_string(), payload));
+        });
+
+        assert_eq!(result.decision, Decision::Deny);
+        let events = events.into_inner();
+        assert_eq!(events[1].1["decision"], "deny");
+        assert_eq!(events[1].1["timed_out"], true);
+        assert!(approvals.list().is_empty());
+    }
+
+    #[test]
+    fn too_many_waiting_falls_back_to_ask() {
+        let approvals = ApprovalManager::new();
+        let mut replies = Vec::new();
+        for i in 0..MAX_WAITING {
+            let (reply, rx) = sync_channel(1);
+            replies.push(rx);
+            approvals.pending.lock().unwrap().insert(
+                i.to_string(),
+                Pending {
+                    request: request(&i.to_string(), 5),
+                    reply,
+                },
+            );
+        }
+
+        let events = RefCell::new(Vec::new());
+        let result = approvals.hold(request("extra", 5), Decision::Allow, |event, _| {
+            events.borrow_mut().push(event.to_string());
+        });
+        assert_eq!(result.decision, Decision::Ask);
+        assert!(events.into_inner().is_empty());
+        assert_eq!(approvals.list().len(), MAX_WAITING);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::config;
+use crate::daemon;
+use crate::daemon_client::DaemonHandle;
+use crate::policy;
+use crate::project::AppStateMutex;
+use crate::pty_manager;
+use crate::state::AwaySettings;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often sessions are checked for having crossed the idle threshold.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Serialize)]
+struct ForwardingChanged {
+    session_id: String,
+    /// Chat prompts are forwarded to, or None when forwarding stopped
+    chat_id: Option<String>,
+}
+
+struct Activity {
+    last_input: Instant,
+    /// (daemon session, chat) while prompts are being forwarded
+    forwarding: Option<(String, String)>,
+}
+
+impl Activity {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_input: now,
+            forwarding: None,
+        }
+    }
+
+    /// The user typed. Returns the forwarding to stop, if it was on.
+    fn input(&mut self, now: Instant) -> Option<(String, String)> {
+        self.last_input = now;
+        self.forwarding.take()
+    }
+
+    fn is_idle(&self, threshold: Duration, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_input) >= threshold
+    }
+}
+
+/// What one check does to the tracked sessions.
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    /// Sessions idle long enough to start forwarding
+    start: Vec<String>,
+    /// (session, daemon session) to stop forwarding because away mode is off
+    stop: Vec<(String, String)>,
+}
+
+/// Decide which sessions start or stop forwarding. Stopped ones are marked
+/// as no longer forwarding here; started ones only once the daemon agrees.
+fn decide(
+    sessions: &mut HashMap<String, Activity>,
+    settings: &AwaySettings,
+    now: Instant,
+) -> Changes {
+    let threshold = idle_threshold(settings);
+    let mut changes = Changes::default();
+    for (id, activity) in sessions.iter_mut() {
+        if !settings.enabled {
+            if let Some((remote_id, _)) = activity.forwarding.take() {
+                changes.stop.push((id.clone(), remote_id));
+            }
+        } else if activity.forwarding.is_none() && activity.is_idle(threshold, now) {
+            changes.start.push(id.clone());
+        }
+    }
+    changes
+}
+
+fn idle_threshold(settings: &AwaySettings) -> Duration {
+    Duration::from_secs(u64::from(settings.idle_minutes) * 60)
+}
+
+/// Local keyboard activity per session, and which sessions currently have
+/// their prompts forwarded to a channel because the user is away.
+pub struct AwayTracker {
+    sessions: Mutex<HashMap<String, Activity>>,
+}
+
+impl AwayTracker {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn register(&self, session_id: &str) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), Activity::new(Instant::now()));
+    }
+
+    pub fn remove(&self, session_id: &str) {
+        // The daemon drops forwarding along with the session
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// The user typed into a session. Stops forwarding if it was on.
+    pub fn note_input(&self, app: &AppHandle, session_id: &str) {
+        let forwarding = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(activity) = sessions.get_mut(session_id) else {
+                return;
+            };
+            activity.input(Instant::now())
+        };
+        if let Some((remote_id, _)) = forwarding {
+            // Keystrokes shouldn't wait on the daemon
+            let app = app.clone();
+            let session_id = session_id.to_string();
+            std::thread::spawn(move || stop_forwarding(&app, &session_id, &remote_id));
+        }
+    }
+
+    fn check(&self, app: &AppHandle) {
+        let settings = {
+            let state = app.state::<AppStateMutex>();
+            let s = state.lock().unwrap();
+            s.away.clone()
+        };
+        let threshold = idle_threshold(&settings);
+        let changes = decide(
+            &mut self.sessions.lock().unwrap(),
+            &settings,
+            Instant::now(),
+        );
+
+        for (session_id, remote_id) in changes.stop {
+            stop_forwarding(app, &session_id, &remote_id);
+        }
+        for session_id in changes.start {
+            let Some((remote_id, chat_id)) = forward_target(app, &session_id) else {
+                continue;
+            };
+            match daemon::set_forwarding(
+                app.state::<DaemonHandle>().passive(),
+                &remote_id,
+                Some(&chat_id),
+            ) {
+                Ok(_) => {
+                    let mut sessions = self.sessions.lock().unwrap();
+                    // Skip if the user came back while the request was in flight
+                    let Some(activity) = sessions.get_mut(&session_id) else {
+                        continue;
+                    };
+                    if !activity.is_idle(threshold, Instant::now()) {
+                        drop(sessions);
+                        stop_forwarding(app, &session_id, &remote_id);
+                        continue;
+                    }
+                    activity.forwarding = Some((remote_id, chat_id.clone()));
+                    drop(sessions);
+                    log::info!("Away: forwarding {session_id} to {chat_id}");
+                    emit_changed(app, &session_id, Some(chat_id));
+                }
+                Err(e) => log::debug!("Away: could not forward {session_id}: {e}"),
+            }
+        }
+    }
+
+    fn forwarded(&self) -> HashMap<String, String> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .iter()
+            .filter_map(|(id, a)| {
+                a.forwarding
+                    .as_ref()
+                    .map(|(_, chat)| (id.clone(), chat.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Daemon session and chat ID to forward to: the project's default channel,
+/// resolved against the daemon's runtime channels.
+fn forward_target(app: &AppHandle, session_id: &str) -> Option<(String, String)> {
+    let (project_id, _) = policy::project_for_session(app, session_id)?;
+    let default_channel = {
+        let state = app.state::<AppStateMutex>();
+        let s = state.lock().unwrap();
+        s.projects
+            .iter()
+            .find(|p| p.id == project_id)?
+            .default_channel
+            .clone()?
+    };
+    // Stored as "type:Title"; the title identifies the chat
+    let title = match default_channel.find(':') {
+        Some(idx) => &default_channel[idx + 1..],
+        None => default_channel.as_str(),
+    };
+    let chat_id = app
+        .state::<DaemonHandle>()
+        .passive()
+        .get::<daemon::RuntimeChannelsResponse>("/channels")
+        .ok()?
+        .channels
+        .into_iter()
+        .find(|c| c.title == title)?
+        .chat_id;
+    let remote_id = pty_manager::remote_for_session(app, session_id)?;
+    Some((remote_id, chat_id))
+}
+
+fn stop_forwarding(app: &AppHandle, session_id: &str, remote_id: &str) {
+    if let Err(e) = daemon::set_forwarding(app.state::<DaemonHandle>().passive(), remote_id, None) {
+        log::debug!("Away: could not stop forwarding {session_id}: {e}");
+    }
+    log::info!("Away: stopped forwarding {session_id}");
+    emit_changed(app, session_id, None);
+}
+
+fn emit_changed(app: &AppHandle, session_id: &str, chat_id: Option<String>) {
+    let _ = app.emit(
+        "away-forwarding-changed",
+        ForwardingChanged {
+            session_id: session_id.to_string(),
+            chat_id,
+        },
+    );
+}
+
+/// Start the background thread that turns forwarding on for idle sessions.
+pub fn start_checking(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        app.state::<AwayTracker>().check(&app);
+    });
+}
+
+#[tauri::command]
+pub fn get_away_settings(state: State<'_, AppStateMutex>) -> AwaySettings {
+    let s = state.lock().unwrap();
+    s.away.clone()
+}
+
+#[tauri::command]
+pub fn set_away_settings(
+    state: State<'_, AppStateMutex>,
+    settings: AwaySettings,
+) -> Result<(), String> {
+    if settings.idle_minutes == 0 {
+        return Err("Idle time must be at least 1 minute.".into());
+    }
+    let mut s = state.lock().unwrap();
+    s.away = settings;
+    config::save_state(&s);
+    Ok(())
+}
+
+/// session_id -> chat ID for sessions currently forwarding prompts.
+#[tauri::command]
+pub fn get_away_forwarding(away: State<'_, AwayTracker>) -> HashMap<String, String> {
+    away.forwarded()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn settings(enabled: bool) -> AwaySettings {
+        AwaySettings {
+            enabled,
+            idle_minutes: 5,
+        }
+    }
+
+    fn forwarding() -> Option<(String, String)> {
+        Some(("remote-1".into(), "chat-1".into()))
+    }
+
+    #[test]
+    fn starts_forwarding_once_idle_past_threshold() {
+        let start = Instant::now();
+        let mut sessions = HashMap::new();
+        sessions.insert("idle".to_string(), Activity::new(start));
+        sessions.insert("busy".to_string(), Activity::new(start + 3 * MINUTE));
+
+        let changes = decide(&mut sessions, &settings(true), start + 5 * MINUTE);
+        assert_eq!(changes.start, vec!["idle".to_string()]);
+        assert!(changes.stop.is_empty());
+
+        // Nothing starts before the threshold
+        let changes = decide(&mut sessions, &settings(true), start + 4 * MINUTE);
+        assert_eq!(changes, Changes::default());
+    }
+
+    #[test]
+    fn does_not_restart_a_forwarding_session() {
+        let start = Instant::now();
+        let mut sessions = HashMap::new();
+        let mut activity = Activity::new(start);
+        activity.forwarding = forwarding();
+        sessions.insert("s1".to_string(), activity);
+
+        let changes = decide(&mut sessions, &settings(true), start + 60 * MINUTE);
+        assert_eq!(changes, Changes::default());
+        assert_eq!(sessions["s1"].forwarding, forwarding());
+    }
+
+    #[test]
+    fn turning_away_mode_off_stops_forwarding() {
+        let start = Instant::now();
+        let mut sessions = HashMap::new();
+        let mut activity = Activity::new(start);
+        activity.forwarding = forwarding();
+        sessions.insert("s1".to_string(), activity);
+        sessions.insert("s2".to_string(), Activity::new(start));
+
+        let changes = decide(&mut sessions, &settings(false), start + 60 * MINUTE);
+        assert_eq!(
+            changes.stop,
+            vec![("s1".to_string(), "remote-1".to_string())]
+        );
+        // Idle sessions don't start while it's off
+        assert!(changes.start.is_empty());
+        assert_eq!(sessions["s1"].forwarding, None);
+
+        // Already stopped, so the next check has nothing to do
+        let changes = decide(&mut sessions, &settings(false), start + 61 * MINUTE);
+        assert_eq!(changes, Changes::default());
+    }
+
+    #[test]
+    fn input_stops_forwarding_and_resets_idle_time() {
+        let start = Instant::now();
+        let mut activity = Activity::new(start);
+        activity.forwarding = forwarding();
+
+        assert_eq!(activity.input(start + 10 * MINUTE), forwarding());
+        assert_eq!(activity.forwarding, None);
+        assert_eq!(activity.input(start + 11 * MINUTE), None);
+        assert!(!activity.is_idle(5 * MINUTE, start + 15 * MINUTE));
+        assert!(activity.is_idle(5 * MINUTE, start + 16 * MINUTE));
+    }
+
+    #[test]
+    fn threshold_follows_idle_minutes() {
+        assert_eq!(idle_threshold(&settings(true)), 5 * MINUTE);
+    }
+}
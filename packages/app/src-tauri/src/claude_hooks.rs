@@ -0,0 +1,903 @@
+use crate::project::AppStateMutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// Hook scripts shipped with the CLI, kept in sync with this build.
+const CLAUDE_HOOK_SCRIPT: &str = include_str!("../../../cli/src/hooks/claude-hooks.sh");
+const GEMINI_HOOK_SCRIPT: &str = include_str!("../../../cli/src/hooks/gemini-hooks.sh");
+
+/// Claude: the app holds approval hooks open for up to
+/// `approvals::MAX_TIMEOUT_SECS`, and the script waits 58s for it.
+const CLAUDE_SYNC_TIMEOUT: u64 = 60;
+const CLAUDE_ASYNC_TIMEOUT: u64 = 5;
+/// Gemini: milliseconds. The script forwards in the background and returns.
+const GEMINI_TIMEOUT_MS: u64 = 5000;
+
+/// Lines of unchanged context around each hunk in a preview diff.
+const DIFF_CONTEXT: usize = 3;
+
+struct HookSpec {
+    event: &'static str,
+    /// Claude waits for the script, so the app can answer approvals
+    sync: bool,
+    /// Tool events take a matcher that should match every tool
+    matcher: bool,
+}
+
+const fn spec(event: &'static str, sync: bool, matcher: bool) -> HookSpec {
+    HookSpec {
+        event,
+        sync,
+        matcher,
+    }
+}
+
+const CLAUDE_SPECS: &[HookSpec] = &[
+    spec("SessionStart", false, false),
+    spec("UserPromptSubmit", false, false),
+    spec("PreToolUse", true, true),
+    spec("PermissionRequest", true, true),
+    spec("PostToolUse", false, true),
+    spec("Notification", false, false),
+    spec("Stop", false, false),
+    spec("SubagentStop", false, false),
+    spec("PreCompact", false, false),
+];
+
+/// Mapped onto the Claude event model by `hook_adapters`.
+const GEMINI_SPECS: &[HookSpec] = &[
+    spec("SessionStart", false, false),
+    spec("BeforeAgent", false, false),
+    spec("BeforeTool", false, true),
+    spec("AfterTool", false, true),
+    spec("Notification", false, false),
+    spec("AfterAgent", false, false),
+    spec("PreCompress", false, false),
+];
+
+/// Tools whose settings.json hooks touchgrass can manage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookTool {
+    #[default]
+    Claude,
+    Gemini,
+}
+
+impl HookTool {
+    fn specs(self) -> &'static [HookSpec] {
+        match self {
+            HookTool::Claude => CLAUDE_SPECS,
+            HookTool::Gemini => GEMINI_SPECS,
+        }
+    }
+
+    /// Directory holding settings.json, under home or a project
+    fn settings_dir(self) -> &'static str {
+        match self {
+            HookTool::Claude => ".claude",
+            HookTool::Gemini => ".gemini",
+        }
+    }
+
+    fn script_name(self) -> &'static str {
+        match self {
+            HookTool::Claude => "claude-hooks.sh",
+            HookTool::Gemini => "gemini-hooks.sh",
+        }
+    }
+
+    fn script(self) -> &'static str {
+        match self {
+            HookTool::Claude => CLAUDE_HOOK_SCRIPT,
+            HookTool::Gemini => GEMINI_HOOK_SCRIPT,
+        }
+    }
+
+    /// Claude matchers are tool names or "*"; Gemini's are regexes
+    fn wildcard(self) -> &'static str {
+        match self {
+            HookTool::Claude => "*",
+            HookTool::Gemini => ".*",
+        }
+    }
+}
+
+/// A tool together with the installed path of its hook script.
+struct Target {
+    tool: HookTool,
+    command: String,
+}
+
+impl Target {
+    fn new(tool: HookTool) -> Result<Self, String> {
+        Ok(Self {
+            tool,
+            command: script_path(tool.script_name())?
+                .to_string_lossy()
+                .to_string(),
+        })
+    }
+
+    /// Matches our command however the home directory was written.
+    fn is_ours(&self, hook: &Value) -> bool {
+        let suffix = format!("/.touchgrass/hooks/{}", self.tool.script_name());
+        hook.get("command")
+            .and_then(|c| c.as_str())
+            .is_some_and(|c| c == self.command || c.ends_with(&suffix))
+    }
+
+    fn desired_hook(&self, spec: &HookSpec) -> Value {
+        match self.tool {
+            HookTool::Claude if spec.sync => json!({
+                "type": "command",
+                "command": self.command,
+                "timeout": CLAUDE_SYNC_TIMEOUT,
+            }),
+            HookTool::Claude => json!({
+                "type": "command",
+                "command": self.command,
+                "async": true,
+                "timeout": CLAUDE_ASYNC_TIMEOUT,
+            }),
+            HookTool::Gemini => json!({
+                "name": "touchgrass",
+                "type": "command",
+                "command": self.command,
+                "timeout": GEMINI_TIMEOUT_MS,
+            }),
+        }
+    }
+
+    fn desired_entry(&self, spec: &HookSpec) -> Value {
+        let mut entry = Map::new();
+        if spec.matcher {
+            entry.insert("matcher".into(), json!(self.tool.wildcard()));
+        }
+        entry.insert("hooks".into(), json!([self.desired_hook(spec)]));
+        Value::Object(entry)
+    }
+
+    /// Every field we write has the expected value, and a sync hook hasn't
+    /// been made async.
+    fn hook_up_to_date(&self, hook: &Value, spec: &HookSpec) -> bool {
+        let desired = self.desired_hook(spec);
+        let fields_match = desired
+            .as_object()
+            .is_some_and(|d| d.iter().all(|(k, v)| hook.get(k) == Some(v)));
+        let async_flag = hook.get("async").and_then(|v| v.as_bool()).unwrap_or(false);
+        fields_match && (desired.get("async").is_some() || !async_flag)
+    }
+
+    /// An entry holding only our hook must match every tool; entries shared
+    /// with other hooks keep whatever matcher the user gave them.
+    fn matcher_up_to_date(&self, entry: &Value, spec: &HookSpec) -> bool {
+        let shared = entry
+            .get("hooks")
+            .and_then(|h| h.as_array())
+            .is_some_and(|h| h.len() > 1);
+        if shared || !spec.matcher {
+            return true;
+        }
+        entry.get("matcher").and_then(|m| m.as_str()) == Some(self.tool.wildcard())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookAction {
+    /// Add missing entries, leaving existing ones alone
+    Install,
+    /// Add missing entries and rewrite outdated ones and the script
+    Repair,
+    /// Remove touchgrass entries, keeping everything else
+    Uninstall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Installed,
+    Missing,
+    /// Present but with old options, or registered more than once
+    Outdated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookEntryStatus {
+    pub event: String,
+    pub status: EntryStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeHooksStatus {
+    pub settings_path: String,
+    pub settings_exists: bool,
+    pub script_path: String,
+    pub script_installed: bool,
+    /// The installed script matches the one bundled with the app
+    pub script_current: bool,
+    pub events: Vec<HookEntryStatus>,
+    /// Everything is installed and up to date
+    pub healthy: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeHooksPlan {
+    pub settings_path: String,
+    pub changed: bool,
+    /// Unified diff of settings.json, empty when nothing changes
+    pub diff: String,
+    /// Fingerprint of the settings the diff was made against; pass it to
+    /// `apply_claude_hooks` so it only writes what was previewed
+    pub base: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaudeHooksResult {
+    pub settings_path: String,
+    pub changed: bool,
+    /// Copy of the previous settings.json, if there was one to replace
+    pub backup_path: Option<String>,
+}
+
+fn script_path(name: &str) -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|h| h.join(".touchgrass").join("hooks").join(name))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+/// ~/<dir>/settings.json, or <project>/<dir>/settings.json for a project.
+fn settings_path(
+    state: &AppStateMutex,
+    tool: HookTool,
+    project_id: Option<&str>,
+) -> Result<PathBuf, String> {
+    let base = match project_id {
+        Some(id) => {
+            let s = state.lock().unwrap();
+            let project = s
+                .projects
+                .iter()
+                .find(|p| p.id == id)
+                .ok_or_else(|| format!("Project {id} not found"))?;
+            PathBuf::from(&project.path)
+        }
+        None => dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string())?,
+    };
+    Ok(base.join(tool.settings_dir()).join("settings.json"))
+}
+
+/// Settings file text (empty if it doesn't exist) and its parsed contents.
+fn read_settings(path: &Path) -> Result<(String, Map<String, Value>), String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((String::new(), Map::new()))
+        }
+        Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+    };
+    if text.trim().is_empty() {
+        return Ok((text, Map::new()));
+    }
+    match serde_json::from_str::<Value>(&text) {
+        Ok(Value::Object(map)) => Ok((text, map)),
+        Ok(_) => Err(format!("{} is not a JSON object", path.display())),
+        Err(e) => Err(format!("{} is not valid JSON: {e}", path.display())),
+    }
+}
+
+fn event_entries<'a>(settings: &'a Map<String, Value>, event: &str) -> Result<&'a [Value], String> {
+    match settings.get("hooks") {
+        None => Ok(&[]),
+        Some(Value::Object(hooks)) => match hooks.get(event) {
+            None => Ok(&[]),
+            Some(Value::Array(entries)) => Ok(entries),
+            Some(_) => Err(format!("hooks.{event} is not a list")),
+        },
+        Some(_) => Err("hooks is not an object".into()),
+    }
+}
+
+fn entry_status(
+    settings: &Map<String, Value>,
+    target: &Target,
+    spec: &HookSpec,
+) -> Result<EntryStatus, String> {
+    let mut found = 0;
+    let mut current = true;
+    for entry in event_entries(settings, spec.event)? {
+        let hooks = entry.get("hooks").and_then(|h| h.as_array());
+        for hook in hooks.into_iter().flatten().filter(|h| target.is_ours(h)) {
+            found += 1;
+            current &= target.hook_up_to_date(hook, spec) && target.matcher_up_to_date(entry, spec);
+        }
+    }
+    Ok(match found {
+        0 => EntryStatus::Missing,
+        1 if current => EntryStatus::Installed,
+        _ => EntryStatus::Outdated,
+    })
+}
+
+/// The mutable entry list for an event, creating `hooks` and the list as
+/// needed.
+fn event_entries_mut<'a>(
+    settings: &'a mut Map<String, Value>,
+    event: &str,
+) -> Result<&'a mut Vec<Value>, String> {
+    let hooks = settings
+        .entry("hooks")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or("hooks is not an object")?;
+    hooks
+        .entry(event)
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .ok_or_else(|| format!("hooks.{event} is not a list"))
+}
+
+/// Remove our hooks from an event's entries, dropping entries left empty.
+/// With `keep_first`, the first one is rewritten in place instead.
+fn strip_ours(entries: &mut Vec<Value>, target: &Target, spec: &HookSpec, keep_first: bool) {
+    let mut kept = false;
+    for entry in entries.iter_mut() {
+        let Some(hooks) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) else {
+            continue;
+        };
+        let mut rewritten = false;
+        hooks.retain_mut(|hook| {
+            if !target.is_ours(hook) {
+                return true;
+            }
+            if keep_first && !kept {
+                *hook = target.desired_hook(spec);
+                kept = true;
+                rewritten = true;
+                return true;
+            }
+            false
+        });
+        if rewritten && hooks.len() == 1 && spec.matcher {
+            if let Some(obj) = entry.as_object_mut() {
+                obj.insert("matcher".into(), json!(target.tool.wildcard()));
+            }
+        }
+    }
+    entries.retain(|entry| {
+        entry
+            .get("hooks")
+            .and_then(|h| h.as_array())
+            .map_or(true, |h| !h.is_empty())
+    });
+}
+
+/// Apply `action` to parsed settings. Only touchgrass hooks are added,
+/// changed or removed.
+fn plan(
+    settings: &Map<String, Value>,
+    target: &Target,
+    action: HookAction,
+) -> Result<Map<String, Value>, String> {
+    let mut next = settings.clone();
+    for spec in target.tool.specs() {
+        let status = entry_status(settings, target, spec)?;
+        match (action, status) {
+            (HookAction::Install | HookAction::Repair, EntryStatus::Missing) => {
+                event_entries_mut(&mut next, spec.event)?.push(target.desired_entry(spec));
+            }
+            (HookAction::Repair, EntryStatus::Outdated) => {
+                strip_ours(
+                    event_entries_mut(&mut next, spec.event)?,
+                    target,
+                    spec,
+                    true,
+                );
+            }
+            (HookAction::Uninstall, EntryStatus::Installed | EntryStatus::Outdated) => {
+                let entries = event_entries_mut(&mut next, spec.event)?;
+                strip_ours(entries, target, spec, false);
+                if entries.is_empty() {
+                    if let Some(hooks) = next.get_mut("hooks").and_then(|h| h.as_object_mut()) {
+                        hooks.remove(spec.event);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let hooks_empty = next
+        .get("hooks")
+        .and_then(|h| h.as_object())
+        .is_some_and(|h| h.is_empty());
+    if hooks_empty && action == HookAction::Uninstall {
+        next.remove("hooks");
+    }
+    Ok(next)
+}
+
+/// SHA-256 of a settings file's text, empty text for a missing file.
+fn fingerprint(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+fn render(settings: &Map<String, Value>) -> String {
+    let mut text = serde_json::to_string_pretty(settings).unwrap_or_default();
+    text.push('\n');
+    text
+}
+
+/// Line diff in unified format, from a longest-common-subsequence table.
+/// Settings files are small enough for the quadratic table.
+fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // (tag, old line index, new line index)
+    let mut ops: Vec<(char, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', i, j));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals before additions, as diff(1) shows them
+            ops.push(('-', i, j));
+            i += 1;
+        } else {
+            ops.push(('+', i, j));
+            j += 1;
+        }
+    }
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- {path}\n+++ {path}\n");
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(DIFF_CONTEXT);
+        let mut end = changes[k];
+        // Merge changes whose context would overlap
+        while k + 1 < changes.len() && changes[k + 1] <= end + 2 * DIFF_CONTEXT + 1 {
+            k += 1;
+            end = changes[k];
+        }
+        let end = (end + DIFF_CONTEXT + 1).min(ops.len());
+        k += 1;
+
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_len = hunk.iter().filter(|op| op.0 != '-').count();
+        let (_, old_start, new_start) = hunk[0];
+        out.push_str(&format!(
+            "@@ -{},{old_len} +{},{new_len} @@\n",
+            old_start + usize::from(old_len > 0),
+            new_start + usize::from(new_len > 0)
+        ));
+        for &(tag, ai, bj) in hunk {
+            let line = if tag == '+' { b[bj] } else { a[ai] };
+            out.push_str(&format!("{tag}{line}\n"));
+        }
+    }
+    out
+}
+
+/// Write a bundled script to ~/.touchgrass/hooks/<name> unless it is already
+/// current, returning its path.
+pub(crate) fn ensure_script(name: &str, body: &str) -> Result<PathBuf, String> {
+    let path = script_path(name)?;
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(body) {
+        return Ok(path);
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    std::fs::write(&path, body).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755));
+    }
+    Ok(path)
+}
+
+/// Whether any of `tool`'s touchgrass hooks is registered in the user's
+/// settings or in `project_dir`'s.
+pub(crate) fn hooks_registered(tool: HookTool, project_dir: &Path) -> bool {
+    let Ok(target) = Target::new(tool) else {
+        return false;
+    };
+    let home = dirs::home_dir().map(|h| h.join(tool.settings_dir()).join("settings.json"));
+    let project = project_dir.join(tool.settings_dir()).join("settings.json");
+    home.into_iter().chain([project]).any(|path| {
+        let Ok((_, settings)) = read_settings(&path) else {
+            return false;
+        };
+        tool.specs().iter().any(|spec| {
+            entry_status(&settings, &target, spec).is_ok_and(|s| s != EntryStatus::Missing)
+        })
+    })
+}
+
+/// Copy the current settings aside, then replace them atomically.
+fn write_settings(path: &Path, text: &str) -> Result<Option<PathBuf>, String> {
+    let dir = path.parent().ok_or("Invalid settings path")?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+
+    let backup = if path.exists() {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let backup = dir.join(format!("settings.json.touchgrass-backup-{stamp}"));
+        std::fs::copy(path, &backup).map_err(|e| format!("Failed to back up settings: {e}"))?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    let tmp = dir.join("settings.json.touchgrass-tmp");
+    std::fs::write(&tmp, text).map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .map_err(|e| format!("Failed to replace {}: {e}", path.display()))?;
+    Ok(backup)
+}
+
+#[tauri::command]
+pub fn get_claude_hooks_status(
+    state: State<'_, AppStateMutex>,
+    tool: Option<HookTool>,
+    project_id: Option<String>,
+) -> Result<ClaudeHooksStatus, String> {
+    let tool = tool.unwrap_or_default();
+    let target = Target::new(tool)?;
+    let path = settings_path(&state, tool, project_id.as_deref())?;
+    let (_, settings) = read_settings(&path)?;
+
+    let events = tool
+        .specs()
+        .iter()
+        .map(|spec| {
+            Ok(HookEntryStatus {
+                event: spec.event.to_string(),
+                status: entry_status(&settings, &target, spec)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let installed = std::fs::read_to_string(&target.command).ok();
+    let script_current = installed.as_deref() == Some(tool.script());
+    let healthy = script_current && events.iter().all(|e| e.status == EntryStatus::Installed);
+
+    Ok(ClaudeHooksStatus {
+        settings_path: path.to_string_lossy().to_string(),
+        settings_exists: path.exists(),
+        script_path: target.command,
+        script_installed: installed.is_some(),
+        script_current,
+        events,
+        healthy,
+    })
+}
+
+/// What `apply_claude_hooks` would write, as a diff against the current file.
+#[tauri::command]
+pub fn preview_claude_hooks(
+    state: State<'_, AppStateMutex>,
+    tool: Option<HookTool>,
+    project_id: Option<String>,
+    action: HookAction,
+) -> Result<ClaudeHooksPlan, String> {
+    let tool = tool.unwrap_or_default();
+    let target = Target::new(tool)?;
+    let path = settings_path(&state, tool, project_id.as_deref())?;
+    let (text, settings) = read_settings(&path)?;
+    let next = plan(&settings, &target, action)?;
+    let changed = next != settings;
+    let diff = if changed {
+        unified_diff(&path.to_string_lossy(), &text, &render(&next))
+    } else {
+        String::new()
+    };
+    Ok(ClaudeHooksPlan {
+        settings_path: path.to_string_lossy().to_string(),
+        changed,
+        diff,
+        base: fingerprint(&text),
+    })
+}
+
+#[tauri::command]
+pub fn apply_claude_hooks(
+    state: State<'_, AppStateMutex>,
+    tool: Option<HookTool>,
+    project_id: Option<String>,
+    action: HookAction,
+    base: String,
+) -> Result<ClaudeHooksResult, String> {
+    let tool = tool.unwrap_or_default();
+    let target = Target::new(tool)?;
+    let path = settings_path(&state, tool, project_id.as_deref())?;
+
+    // The plan is recomputed from the file, so an unchanged file means the
+    // previewed diff is exactly what gets written
+    let (text, settings) = read_settings(&path)?;
+    if fingerprint(&text) != base {
+        return Err(format!(
+            "{} changed since it was previewed; preview again",
+            path.display()
+        ));
+    }
+
+    // The script stays on uninstall: other settings files may still use it
+    match action {
+        HookAction::Install if !Path::new(&target.command).exists() => {
+            ensure_script(tool.script_name(), tool.script())?;
+        }
+        HookAction::Repair => {
+            ensure_script(tool.script_name(), tool.script())?;
+        }
+        _ => {}
+    }
+
+    let next = plan(&settings, &target, action)?;
+    if next == settings {
+        return Ok(ClaudeHooksResult {
+            settings_path: path.to_string_lossy().to_string(),
+            changed: false,
+            backup_path: None,
+        });
+    }
+
+    let backup = write_settings(&path, &render(&next))?;
+    log::info!("Updated {tool:?} hooks ({action:?}) in {}", path.display());
+    Ok(ClaudeHooksResult {
+        settings_path: path.to_string_lossy().to_string(),
+        changed: true,
+        backup_path: backup.map(|b| b.to_string_lossy().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMAND: &str = "/home/me/.touchgrass/hooks/claude-hooks.sh";
+
+    fn claude() -> Target {
+        Target {
+            tool: HookTool::Claude,
+            command: COMMAND.into(),
+        }
+    }
+
+    fn settings(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("settings must be an object"),
+        }
+    }
+
+    fn statuses(settings: &Map<String, Value>, target: &Target) -> Vec<EntryStatus> {
+        target
+            .tool
+            .specs()
+            .iter()
+            .map(|spec| entry_status(settings, target, spec).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn install_adds_every_event_with_its_options() {
+        let target = claude();
+        let next = plan(&Map::new(), &target, HookAction::Install).unwrap();
+        assert!(statuses(&next, &target)
+            .iter()
+            .all(|s| *s == EntryStatus::Installed));
+
+        let pre = &next["hooks"]["PreToolUse"][0];
+        assert_eq!(pre["matcher"], "*");
+        assert_eq!(pre["hooks"][0]["timeout"], CLAUDE_SYNC_TIMEOUT);
+        assert!(pre["hooks"][0].get("async").is_none());
+        let stop = &next["hooks"]["Stop"][0];
+        assert!(stop.get("matcher").is_none());
+        assert_eq!(stop["hooks"][0]["async"], true);
+        assert_eq!(stop["hooks"][0]["timeout"], CLAUDE_ASYNC_TIMEOUT);
+
+        // Installing again changes nothing
+        assert_eq!(plan(&next, &target, HookAction::Install).unwrap(), next);
+    }
+
+    #[test]
+    fn install_keeps_other_settings_and_hooks() {
+        let target = claude();
+        let current = settings(json!({
+            "model": "opus",
+            "hooks": {
+                "Stop": [{ "hooks": [{ "type": "command", "command": "say done" }] }],
+            },
+        }));
+        let next = plan(&current, &target, HookAction::Install).unwrap();
+        assert_eq!(next["model"], "opus");
+        let stop = next["hooks"]["Stop"].as_array().unwrap();
+        assert_eq!(stop.len(), 2);
+        assert_eq!(stop[0]["hooks"][0]["command"], "say done");
+        assert_eq!(stop[1]["hooks"][0]["command"], COMMAND);
+    }
+
+    #[test]
+    fn install_leaves_outdated_entries_and_repair_rewrites_them() {
+        let target = claude();
+        let mut current = plan(&Map::new(), &target, HookAction::Install).unwrap();
+        // An old async install of the approval hook, registered twice, from a
+        // home directory written differently
+        current["hooks"]["PreToolUse"] = json!([
+            { "matcher": "Bash", "hooks": [{ "type": "command",
+              "command": "~/.touchgrass/hooks/claude-hooks.sh", "async": true, "timeout": 5 }] },
+            { "matcher": "*", "hooks": [{ "type": "command", "command": COMMAND }] },
+        ]);
+        let pre = CLAUDE_SPECS
+            .iter()
+            .find(|s| s.event == "PreToolUse")
+            .unwrap();
+        assert_eq!(
+            entry_status(&current, &target, pre).unwrap(),
+            EntryStatus::Outdated
+        );
+        assert_eq!(
+            plan(&current, &target, HookAction::Install).unwrap(),
+            current
+        );
+
+        let repaired = plan(&current, &target, HookAction::Repair).unwrap();
+        assert_eq!(
+            repaired["hooks"]["PreToolUse"],
+            json!([{ "matcher": "*", "hooks": [
+                { "type": "command", "command": COMMAND, "timeout": CLAUDE_SYNC_TIMEOUT }
+            ] }])
+        );
+        assert!(statuses(&repaired, &target)
+            .iter()
+            .all(|s| *s == EntryStatus::Installed));
+    }
+
+    #[test]
+    fn repair_keeps_the_matcher_of_a_shared_entry() {
+        let target = claude();
+        let current = settings(json!({
+            "hooks": {
+                "PostToolUse": [{ "matcher": "Edit", "hooks": [
+                    { "type": "command", "command": "fmt" },
+                    { "type": "command", "command": COMMAND },
+                ] }],
+            },
+        }));
+        let repaired = plan(&current, &target, HookAction::Repair).unwrap();
+        let entry = &repaired["hooks"]["PostToolUse"][0];
+        assert_eq!(entry["matcher"], "Edit");
+        assert_eq!(entry["hooks"][0]["command"], "fmt");
+        assert_eq!(entry["hooks"][1]["async"], true);
+    }
+
+    #[test]
+    fn uninstall_removes_only_our_hooks() {
+        let target = claude();
+        let mut current = plan(&Map::new(), &target, HookAction::Install).unwrap();
+        current["hooks"]["Stop"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "hooks": [{ "type": "command", "command": "say done" }] }));
+        current.insert("model".into(), json!("opus"));
+
+        let next = plan(&current, &target, HookAction::Uninstall).unwrap();
+        assert_eq!(
+            next,
+            settings(json!({
+                "model": "opus",
+                "hooks": {
+                    "Stop": [{ "hooks": [{ "type": "command", "command": "say done" }] }],
+                },
+            }))
+        );
+
+        // With nothing else left, the hooks key goes too
+        let only_ours = plan(&Map::new(), &target, HookAction::Install).unwrap();
+        assert!(plan(&only_ours, &target, HookAction::Uninstall)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn gemini_hooks_use_regex_matchers_and_milliseconds() {
+        let target = Target {
+            tool: HookTool::Gemini,
+            command: "/home/me/.touchgrass/hooks/gemini-hooks.sh".into(),
+        };
+        let next = plan(&Map::new(), &target, HookAction::Install).unwrap();
+        let tool = &next["hooks"]["BeforeTool"][0];
+        assert_eq!(tool["matcher"], ".*");
+        assert_eq!(tool["hooks"][0]["timeout"], GEMINI_TIMEOUT_MS);
+        assert_eq!(tool["hooks"][0]["name"], "touchgrass");
+        // Claude's script is not ours in Gemini's settings
+        assert!(!target.is_ours(&json!({ "command": COMMAND })));
+    }
+
+    #[test]
+    fn malformed_hooks_are_reported() {
+        let target = claude();
+        let current = settings(json!({ "hooks": { "Stop": {} } }));
+        assert_eq!(
+            plan(&current, &target, HookAction::Install),
+            Err("hooks.Stop is not a list".to_string())
+        );
+        let current = settings(json!({ "hooks": [] }));
+        assert!(plan(&current, &target, HookAction::Install).is_err());
+    }
+
+    #[test]
+    fn diff_of_identical_text_is_empty() {
+        assert_eq!(unified_diff("s.json", "a\nb\n", "a\nb\n"), "");
+    }
+
+    #[test]
+    fn diff_of_a_new_file_adds_every_line() {
+        assert_eq!(
+            unified_diff("s.json", "", "{\n}\n"),
+            "--- s.json\n+++ s.json\n@@ -0,0 +1,2 @@\n+{\n+}\n"
+        );
+    }
+
+    #[test]
+    fn diff_shows_changes_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n";
+        assert_eq!(
+            unified_diff("s.json", old, new),
+            "--- s.json\n+++ s.json\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn diff_splits_distant_changes_into_hunks() {
+        let old: String = (1..=20).map(|n| format!("{n}\n")).collect();
+        let new = old.replace("2\n3\n", "2\nthree\n").replace("18\n", "");
+        assert_eq!(
+            unified_diff("s.json", &old, &new),
+            "--- s.json\n+++ s.json\n\
+             @@ -1,6 +1,6 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n\
+             @@ -15,6 +15,5 @@\n 15\n 16\n 17\n-18\n 19\n 20\n"
+        );
+    }
+
+    #[test]
+    fn preview_diff_applies_to_the_rendered_plan() {
+        let target = claude();
+        let old = "{\n  \"model\": \"opus\"\n}\n";
+        let current: Map<String, Value> = serde_json::from_str(old).unwrap();
+        let next = plan(&current, &target, HookAction::Install).unwrap();
+        let diff = unified_diff("s.json", old, &render(&next));
+        assert!(diff.contains("+  \"hooks\": {"));
+        assert!(diff.contains(" \"model\": \"opus\""));
+        assert_eq!(fingerprint(old), fingerprint(old));
+        assert_ne!(fingerprint(old), fingerprint(""));
+    }
+}
@@ -1,191 +1,17 @@
+use crate::daemon_client::{DaemonApi, DaemonError, DaemonHandle};
+use crate::pty_manager;
+use crate::remote_tab;
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
-#[cfg(unix)]
-use std::os::unix::net::UnixStream;
+use std::io::{Read, Seek};
+use tauri::{AppHandle, State};
+
+/// Largest file channels accept; the daemon enforces the same limit.
+const MAX_SEND_FILE_BYTES: u64 = 50 * 1024 * 1024;
 
 fn home_dir() -> Option<std::path::PathBuf> {
     dirs::home_dir()
 }
 
-fn read_auth_token() -> Result<String, String> {
-    let path = home_dir()
-        .ok_or("Cannot determine home directory")?
-        .join(".touchgrass")
-        .join("daemon.auth");
-    std::fs::read_to_string(&path)
-        .map(|s| s.trim().to_string())
-        .map_err(|e| format!("Failed to read auth token: {e}"))
-}
-
-fn http_request(method: &str, path: &str, body: Option<&str>) -> Result<String, String> {
-    let token = read_auth_token()?;
-
-    let sock_path = home_dir()
-        .ok_or("Cannot determine home directory")?
-        .join(".touchgrass")
-        .join("daemon.sock");
-
-    #[cfg(unix)]
-    let mut stream =
-        UnixStream::connect(&sock_path).map_err(|e| format!("Cannot connect to daemon: {e}"))?;
-
-    #[cfg(not(unix))]
-    let mut stream = {
-        let port_path = home_dir()
-            .ok_or("Cannot determine home directory")?
-            .join(".touchgrass")
-            .join("daemon.port");
-        let port_str = std::fs::read_to_string(&port_path)
-            .map_err(|e| format!("Failed to read daemon port: {e}"))?;
-        let port: u16 = port_str
-            .trim()
-            .parse()
-            .map_err(|e| format!("Invalid daemon port: {e}"))?;
-        std::net::TcpStream::connect(("127.0.0.1", port))
-            .map_err(|e| format!("Cannot connect to daemon: {e}"))?
-    };
-
-    // Shutdown write side after sending so server sees EOF and responds
-    let body_bytes = body.unwrap_or("");
-    let content_length = body_bytes.len();
-
-    let request = format!(
-        "{method} {path} HTTP/1.1\r\nHost: localhost\r\nx-touchgrass-auth: {token}\r\nContent-Type: application/json\r\nContent-Length: {content_length}\r\nConnection: close\r\n\r\n{body_bytes}"
-    );
-
-    stream
-        .write_all(request.as_bytes())
-        .map_err(|e| format!("Failed to send request: {e}"))?;
-
-    // Read response using Content-Length to know when we're done
-    // (avoids blocking on second read() waiting for server to close socket)
-    let mut response = Vec::new();
-    let mut buf = [0u8; 8192];
-    let mut header_end: Option<usize> = None;
-    let mut content_length: Option<usize> = None;
-
-    loop {
-        match stream.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => {
-                response.extend_from_slice(&buf[..n]);
-
-                // Try to find header end if we haven't yet
-                if header_end.is_none() {
-                    if let Some(pos) = find_header_end(&response) {
-                        header_end = Some(pos);
-                        // Parse Content-Length from headers
-                        let hdr = String::from_utf8_lossy(&response[..pos]);
-                        for line in hdr.split("\r\n") {
-                            if let Some(val) = line.strip_prefix("Content-Length: ") {
-                                content_length = val.trim().parse().ok();
-                            } else if let Some(val) = line.strip_prefix("content-length: ") {
-                                content_length = val.trim().parse().ok();
-                            }
-                        }
-                    }
-                }
-
-                // If we know the full size, check if we have enough
-                if let (Some(hdr_end), Some(cl)) = (header_end, content_length) {
-                    let body_start = hdr_end + 4; // past \r\n\r\n
-                    if response.len() >= body_start + cl {
-                        break;
-                    }
-                }
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
-            Err(e) => {
-                if !response.is_empty() {
-                    break;
-                }
-                return Err(format!("Failed to read response: {e}"));
-            }
-        }
-    }
-    let response = String::from_utf8(response)
-        .map_err(|e| format!("Response is not valid UTF-8: {e}"))?;
-
-    // Parse HTTP response - find body after \r\n\r\n
-    let body_start = response
-        .find("\r\n\r\n")
-        .ok_or("Invalid HTTP response: no header/body separator")?;
-    let headers = &response[..body_start];
-    let body = &response[body_start + 4..];
-
-    // Extract status code from first line
-    let status_line = headers
-        .lines()
-        .next()
-        .ok_or("Invalid HTTP response: no status line")?;
-    let status_code: u16 = status_line
-        .split_whitespace()
-        .nth(1)
-        .and_then(|s| s.parse().ok())
-        .ok_or("Invalid HTTP response: cannot parse status code")?;
-
-    // Handle chunked transfer encoding
-    let response_body = if headers.contains("Transfer-Encoding: chunked") {
-        decode_chunked(body)?
-    } else {
-        body.to_string()
-    };
-
-    if status_code >= 400 {
-        // Try to extract error message from JSON body
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&response_body) {
-            if let Some(err) = json.get("error").and_then(|e| e.as_str()) {
-                return Err(err.to_string());
-            }
-        }
-        return Err(format!("HTTP {status_code}: {response_body}"));
-    }
-
-    Ok(response_body)
-}
-
-fn find_header_end(data: &[u8]) -> Option<usize> {
-    data.windows(4).position(|w| w == b"\r\n\r\n")
-}
-
-fn decode_chunked(body: &str) -> Result<String, String> {
-    let mut result = String::new();
-    let mut remaining = body;
-
-    loop {
-        // Skip leading whitespace/newlines
-        remaining = remaining.trim_start();
-        if remaining.is_empty() {
-            break;
-        }
-
-        // Find chunk size line
-        let size_end = remaining
-            .find("\r\n")
-            .unwrap_or(remaining.len());
-        let size_str = &remaining[..size_end];
-        let chunk_size = usize::from_str_radix(size_str.trim(), 16)
-            .map_err(|_| format!("Invalid chunk size: '{size_str}'"))?;
-
-        if chunk_size == 0 {
-            break;
-        }
-
-        // Move past the size line + \r\n
-        let data_start = size_end + 2;
-        if data_start + chunk_size > remaining.len() {
-            // Grab what we can
-            result.push_str(&remaining[data_start..]);
-            break;
-        }
-
-        result.push_str(&remaining[data_start..data_start + chunk_size]);
-        remaining = &remaining[data_start + chunk_size..];
-    }
-
-    Ok(result)
-}
-
 // --- Response types ---
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -303,88 +129,189 @@ pub struct RuntimeChannelsResponse {
     pub channels: Vec<RuntimeChannel>,
 }
 
+// --- Per-chat bridge preferences (from /chat-preferences) ---
+
+/// How much of a session's activity is sent to a chat (`/output_mode`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    /// Replies and questions only (the default)
+    Simple,
+    /// Simple, plus thinking previews
+    Thinking,
+    /// Everything, including tool calls and results
+    Verbose,
+}
+
+/// Bridge preferences of one `RuntimeChannel`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatPreferences {
+    #[serde(rename = "chatId")]
+    pub chat_id: String,
+    #[serde(rename = "outputMode")]
+    pub output_mode: OutputMode,
+    /// Whether thinking previews are sent; true only in thinking mode
+    pub thinking: bool,
+    /// Bridge output is not sent at all (`/mute`)
+    pub muted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatPreferencesListResponse {
+    pub ok: bool,
+    pub preferences: Vec<ChatPreferences>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatPreferencesResponse {
+    pub ok: bool,
+    pub preferences: ChatPreferences,
+}
+
 // --- Tauri commands ---
 
 #[tauri::command]
-pub fn daemon_health() -> Result<HealthResponse, String> {
-    let body = http_request("GET", "/health", None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+pub fn daemon_health(client: State<'_, DaemonHandle>) -> Result<HealthResponse, DaemonError> {
+    // Polled to show whether the daemon runs; must not start it
+    client.passive().get("/health")
 }
 
-#[tauri::command]
-pub fn daemon_list_channels() -> Result<ChannelListResponse, String> {
-    let body = http_request("GET", "/config/channels", None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+#[tauri::command(async)]
+pub fn daemon_list_channels(
+    client: State<'_, DaemonHandle>,
+) -> Result<ChannelListResponse, DaemonError> {
+    client.api().get("/config/channels")
 }
 
-#[tauri::command]
-pub fn daemon_runtime_channels() -> Result<RuntimeChannelsResponse, String> {
-    let body = http_request("GET", "/channels", None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+#[tauri::command(async)]
+pub fn daemon_runtime_channels(
+    client: State<'_, DaemonHandle>,
+) -> Result<RuntimeChannelsResponse, DaemonError> {
+    client.api().get("/channels")
 }
 
-#[tauri::command]
-pub fn daemon_get_channel(name: String) -> Result<ChannelDetailResponse, String> {
+#[tauri::command(async)]
+pub fn daemon_list_chat_preferences(
+    client: State<'_, DaemonHandle>,
+) -> Result<ChatPreferencesListResponse, DaemonError> {
+    client.api().get("/chat-preferences")
+}
+
+#[tauri::command(async)]
+pub fn daemon_get_chat_preferences(
+    chat_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<ChatPreferences, DaemonError> {
+    chat_preferences(client.api(), &chat_id)
+}
+
+fn chat_preferences(api: &dyn DaemonApi, chat_id: &str) -> Result<ChatPreferences, DaemonError> {
+    let resp: ChatPreferencesListResponse = api.get("/chat-preferences")?;
+    resp.preferences
+        .into_iter()
+        .find(|p| p.chat_id == chat_id)
+        .ok_or_else(|| DaemonError::Invalid(format!("Unknown chat: {chat_id}")))
+}
+
+/// Change a chat's preferences; fields left out are kept. Turning thinking
+/// on or off switches between simple and thinking mode.
+#[tauri::command(async)]
+pub fn daemon_set_chat_preferences(
+    chat_id: String,
+    output_mode: Option<OutputMode>,
+    thinking: Option<bool>,
+    muted: Option<bool>,
+    client: State<'_, DaemonHandle>,
+) -> Result<ChatPreferencesResponse, DaemonError> {
+    let mut body = serde_json::json!({ "chatId": chat_id });
+    if let Some(mode) = output_mode {
+        body["outputMode"] = serde_json::json!(mode);
+    }
+    if let Some(thinking) = thinking {
+        body["thinking"] = serde_json::json!(thinking);
+    }
+    if let Some(muted) = muted {
+        body["muted"] = serde_json::json!(muted);
+    }
+    client.api().post("/chat-preferences", &body)
+}
+
+#[tauri::command(async)]
+pub fn daemon_get_channel(
+    name: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<ChannelDetailResponse, DaemonError> {
     let path = format!("/config/channels/{name}");
-    let body = http_request("GET", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().get(&path)
 }
 
-#[tauri::command]
+#[tauri::command(async)]
 pub fn daemon_add_channel(
     name: String,
     channel_type: String,
     bot_token: String,
-) -> Result<AddChannelResponse, String> {
+    client: State<'_, DaemonHandle>,
+) -> Result<AddChannelResponse, DaemonError> {
     let payload = serde_json::json!({
         "name": name,
         "type": channel_type,
         "botToken": bot_token,
     });
-    let body = http_request("POST", "/config/channels", Some(&payload.to_string()))?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().post("/config/channels", &payload)
 }
 
-#[tauri::command]
-pub fn daemon_remove_channel(name: String) -> Result<RemoveResponse, String> {
+#[tauri::command(async)]
+pub fn daemon_remove_channel(
+    name: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<RemoveResponse, DaemonError> {
     let path = format!("/config/channels/{name}");
-    let body = http_request("DELETE", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().delete(&path)
 }
 
-#[tauri::command]
-pub fn daemon_remove_user(channel_name: String, user_id: String) -> Result<SimpleResponse, String> {
+#[tauri::command(async)]
+pub fn daemon_remove_user(
+    channel_name: String,
+    user_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
     let encoded_user_id = urlencoding(&user_id);
     let path = format!("/config/channels/{channel_name}/users/{encoded_user_id}");
-    let body = http_request("DELETE", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().delete(&path)
 }
 
-#[tauri::command]
+#[tauri::command(async)]
 pub fn daemon_remove_group(
     channel_name: String,
     chat_id: String,
-) -> Result<SimpleResponse, String> {
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
     let encoded_chat_id = urlencoding(&chat_id);
     let path = format!("/config/channels/{channel_name}/groups/{encoded_chat_id}");
-    let body = http_request("DELETE", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().delete(&path)
 }
 
-#[tauri::command]
-pub fn daemon_generate_code() -> Result<GenerateCodeResponse, String> {
-    let body = http_request("POST", "/generate-code", Some("{}"))?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+#[tauri::command(async)]
+pub fn daemon_generate_code(
+    client: State<'_, DaemonHandle>,
+) -> Result<GenerateCodeResponse, DaemonError> {
+    client.api().post("/generate-code", &serde_json::json!({}))
 }
 
-#[tauri::command]
-pub fn daemon_restart() -> Result<SimpleResponse, String> {
-    // Send shutdown to current daemon (ignore errors — it may already be stopped)
-    let _ = http_request("POST", "/shutdown", Some("{}"));
+#[tauri::command(async)]
+pub fn daemon_restart(client: State<'_, DaemonHandle>) -> Result<SimpleResponse, DaemonError> {
+    // The start-on-demand client restarts it, so this can't race its own start
+    client.api().restart()?;
+    Ok(SimpleResponse { ok: true })
+}
+
+/// Ask the daemon to shut down and wait (up to 3 seconds) for it to exit.
+pub(crate) fn stop_daemon(api: &dyn DaemonApi) -> Result<(), DaemonError> {
+    // Ignore errors — it may already be stopped
+    let _ = api.send("POST", "/shutdown", Some("{}"));
 
-    // Wait for daemon to stop (up to 3 seconds)
     let pid_path = home_dir()
-        .ok_or("Cannot determine home directory")?
+        .ok_or_else(|| DaemonError::NotRunning("cannot determine home directory".into()))?
         .join(".touchgrass")
         .join("daemon.pid");
     for _ in 0..30 {
@@ -393,8 +320,12 @@ pub fn daemon_restart() -> Result<SimpleResponse, String> {
         }
         std::thread::sleep(std::time::Duration::from_millis(100));
     }
+    Ok(())
+}
 
-    // Try installed binary first, then dev mode
+/// Launch the daemon in the background: the installed binary first, then
+/// dev mode.
+pub(crate) fn spawn_daemon() -> Result<(), String> {
     if let Some(tg_bin) = find_tg_binary() {
         std::process::Command::new(&tg_bin)
             .arg("channels")
@@ -417,16 +348,19 @@ pub fn daemon_restart() -> Result<SimpleResponse, String> {
     } else {
         return Err("Cannot find touchgrass binary. Install it or ensure 'touchgrass' is in PATH.".to_string());
     }
+    Ok(())
+}
 
-    // Wait for daemon to become healthy (up to 5 seconds)
+/// Wait for a freshly started daemon to answer /health (up to 5 seconds).
+pub(crate) fn wait_until_healthy(api: &dyn DaemonApi) -> bool {
     for _ in 0..50 {
         std::thread::sleep(std::time::Duration::from_millis(100));
-        if http_request("GET", "/health", None).is_ok() {
-            return Ok(SimpleResponse { ok: true });
+        // Single attempts: this loop already retries
+        if api.send("GET", "/health", None).is_ok() {
+            return true;
         }
     }
-
-    Err("Daemon started but health check timed out".to_string())
+    false
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -444,10 +378,67 @@ pub struct InputNeededResponse {
     pub sessions: Vec<InputNeededSession>,
 }
 
+/// Manifest the CLI writes to ~/.touchgrass/sessions/<id>.json for each
+/// session it wraps.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionManifest {
+    /// Daemon session ID ("r-...")
+    pub id: String,
+    /// PID of the touchgrass CLI wrapping the tool
+    pub pid: u32,
+}
+
+fn sessions_dir() -> Option<std::path::PathBuf> {
+    Some(home_dir()?.join(".touchgrass").join("sessions"))
+}
+
+pub fn read_session_manifest(session_id: &str) -> Option<SessionManifest> {
+    if session_id.contains(['/', '\\']) || session_id.contains("..") {
+        return None;
+    }
+    let path = sessions_dir()?.join(format!("{session_id}.json"));
+    let raw = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn list_session_manifests() -> Vec<SessionManifest> {
+    let Some(entries) = sessions_dir().and_then(|d| std::fs::read_dir(d).ok()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("json"))
+        .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect()
+}
+
+#[tauri::command(async)]
+pub fn daemon_set_forwarding(
+    session_id: String,
+    chat_id: Option<String>,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    set_forwarding(client.api(), &session_id, chat_id.as_deref())
+}
+
+/// Away mode: have the daemon send a session's prompts and permission
+/// requests to `chat_id` (None stops forwarding).
+pub(crate) fn set_forwarding(
+    api: &dyn DaemonApi,
+    session_id: &str,
+    chat_id: Option<&str>,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = format!("/remote/{}/forwarding", urlencoding(session_id));
+    let payload = serde_json::json!({ "chatId": chat_id });
+    api.post(&path, &payload)
+}
+
 #[tauri::command]
-pub fn daemon_input_needed() -> Result<InputNeededResponse, String> {
-    let body = http_request("GET", "/input-needed", None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+pub fn daemon_input_needed(
+    client: State<'_, DaemonHandle>,
+) -> Result<InputNeededResponse, DaemonError> {
+    client.passive().get("/input-needed")
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -465,13 +456,455 @@ pub struct RecentSessionsResponse {
     pub sessions: Vec<RecentSession>,
 }
 
-#[tauri::command]
-pub fn daemon_recent_sessions(tool: String, cwd: String) -> Result<RecentSessionsResponse, String> {
+#[tauri::command(async)]
+pub fn daemon_recent_sessions(
+    tool: String,
+    cwd: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<RecentSessionsResponse, DaemonError> {
     let encoded_tool = urlencoding(&tool);
     let encoded_cwd = urlencoding(&cwd);
     let path = format!("/sessions/recent?tool={encoded_tool}&cwd={encoded_cwd}");
-    let body = http_request("GET", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().get(&path)
+}
+
+// --- Daemon sessions (touchgrass ls / send / peek / stop / kill) ---
+
+/// A CLI session registered with the daemon, wherever it was started.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaemonSession {
+    /// Daemon session ID ("r-...")
+    pub id: String,
+    pub command: String,
+    pub name: Option<String>,
+    pub cwd: String,
+    /// Chat receiving the session's output, if any
+    #[serde(rename = "chatId")]
+    pub chat_id: Option<String>,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: Option<f64>,
+    /// App session running it, if it runs in one of the app's terminals
+    #[serde(rename = "appSessionId", default)]
+    pub app_session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaemonSessionsResponse {
+    pub ok: bool,
+    pub sessions: Vec<DaemonSession>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeekEntry {
+    /// "assistant" | "user" | "tool"
+    pub role: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeekResponse {
+    pub ok: bool,
+    pub entries: Vec<PeekEntry>,
+}
+
+#[tauri::command(async)]
+pub fn daemon_list_sessions(
+    app: AppHandle,
+    client: State<'_, DaemonHandle>,
+) -> Result<DaemonSessionsResponse, DaemonError> {
+    let mut resp: DaemonSessionsResponse = client.api().get("/sessions")?;
+    let by_remote = pty_manager::sessions_by_remote(&app);
+    for session in &mut resp.sessions {
+        session.app_session_id = by_remote.get(&session.id).cloned();
+    }
+    Ok(resp)
+}
+
+/// Send text to the session's channel, like `touchgrass send`.
+#[tauri::command(async)]
+pub fn daemon_send_message(
+    session_id: String,
+    text: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = format!("/remote/{}/send-message", urlencoding(&session_id));
+    client
+        .api()
+        .post(&path, &serde_json::json!({ "text": text }))
+}
+
+/// Type text into the session's terminal, as if it came from a channel.
+#[tauri::command(async)]
+pub fn daemon_send_input(
+    session_id: String,
+    text: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    send_input(client.api(), &session_id, &text)
+}
+
+fn send_input(
+    api: &dyn DaemonApi,
+    session_id: &str,
+    text: &str,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = format!("/remote/{}/send-input", urlencoding(session_id));
+    let resp: SimpleResponse = api.post(&path, &serde_json::json!({ "text": text }))?;
+    if !resp.ok {
+        return Err(DaemonError::Invalid(
+            "Session did not accept the input".into(),
+        ));
+    }
+    Ok(resp)
+}
+
+/// Send a file to the session's channel, like `touchgrass send --file`.
+/// The file is streamed to the daemon, so it may live outside the
+/// session's working directory.
+#[tauri::command(async)]
+pub fn daemon_send_file(
+    session_id: String,
+    path: String,
+    caption: Option<String>,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = std::path::PathBuf::from(path);
+    let unreadable =
+        |e: std::io::Error| DaemonError::Invalid(format!("Cannot read {}: {e}", path.display()));
+    let mut file = std::fs::File::open(&path)
+        .map_err(|e| DaemonError::Invalid(format!("Cannot open {}: {e}", path.display())))?;
+    let meta = file.metadata().map_err(unreadable)?;
+    if !meta.is_file() {
+        return Err(DaemonError::Invalid(format!(
+            "Not a file: {}",
+            path.display()
+        )));
+    }
+    check_send_size(meta.len())?;
+
+    let mut head = [0u8; 512];
+    let n = file.read(&mut head).map_err(unreadable)?;
+    file.rewind().map_err(unreadable)?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let mime = detect_mime(&head[..n], &name);
+    upload_file(
+        client.api(),
+        &session_id,
+        &name,
+        mime,
+        caption.as_deref(),
+        &mut file,
+        meta.len(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    /// The terminal rendered as an image
+    Png,
+    /// The terminal buffer as plain text
+    Text,
+}
+
+/// Send a snapshot of an app terminal to its session's channel. The
+/// frontend renders it from the terminal; `data` is the PNG or UTF-8 text.
+#[tauri::command(async)]
+pub fn daemon_send_snapshot(
+    app: AppHandle,
+    app_session_id: String,
+    format: SnapshotFormat,
+    data: Vec<u8>,
+    caption: Option<String>,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    let session_id = pty_manager::remote_for_session(&app, &app_session_id)
+        .or_else(|| remote_tab::remote_id(&app, &app_session_id))
+        .ok_or_else(|| {
+            DaemonError::Invalid("This terminal is not running a touchgrass session".into())
+        })?;
+    check_send_size(data.len() as u64)?;
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (name, mime) = match format {
+        SnapshotFormat::Png => {
+            if detect_mime(&data, "") != "image/png" {
+                return Err(DaemonError::Invalid("Snapshot is not a PNG image".into()));
+            }
+            (format!("terminal-{stamp}.png"), "image/png")
+        }
+        SnapshotFormat::Text => {
+            if std::str::from_utf8(&data).is_err() {
+                return Err(DaemonError::Invalid(
+                    "Snapshot text is not valid UTF-8".into(),
+                ));
+            }
+            (format!("terminal-{stamp}.txt"), "text/plain; charset=utf-8")
+        }
+    };
+    upload_file(
+        client.api(),
+        &session_id,
+        &name,
+        mime,
+        caption.as_deref(),
+        &mut data.as_slice(),
+        data.len() as u64,
+    )
+}
+
+fn check_send_size(len: u64) -> Result<(), DaemonError> {
+    if len == 0 {
+        return Err(DaemonError::Invalid("File is empty".into()));
+    }
+    if len > MAX_SEND_FILE_BYTES {
+        return Err(DaemonError::Invalid(format!(
+            "File is {:.1} MB; channels accept at most {} MB",
+            len as f64 / (1024.0 * 1024.0),
+            MAX_SEND_FILE_BYTES / (1024 * 1024)
+        )));
+    }
+    Ok(())
+}
+
+fn upload_file(
+    api: &dyn DaemonApi,
+    session_id: &str,
+    name: &str,
+    mime: &str,
+    caption: Option<&str>,
+    body: &mut dyn Read,
+    len: u64,
+) -> Result<SimpleResponse, DaemonError> {
+    let mut path = format!(
+        "/remote/{}/upload-file?name={}",
+        urlencoding(session_id),
+        urlencoding(&upload_name(name, mime))
+    );
+    if let Some(caption) = caption.filter(|c| !c.trim().is_empty()) {
+        path.push_str(&format!("&caption={}", urlencoding(caption)));
+    }
+    api.post_raw(&path, mime, body, len)
+}
+
+/// The daemon and channels go by the file name, so a name without an
+/// extension gets the one for its detected MIME type. Dotfiles such as
+/// `.bashrc` keep their name.
+fn upload_name(name: &str, mime: &str) -> String {
+    let has_extension = name.starts_with('.') || std::path::Path::new(name).extension().is_some();
+    match mime_extension(mime) {
+        Some(ext) if !has_extension => format!("{name}.{ext}"),
+        _ => name.to_string(),
+    }
+}
+
+/// Usual extension for a MIME type from `detect_mime`.
+fn mime_extension(mime: &str) -> Option<&'static str> {
+    let ext = match mime.split(';').next().unwrap_or_default().trim() {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" => "gz",
+        "audio/mpeg" => "mp3",
+        "audio/ogg" => "ogg",
+        "video/mp4" => "mp4",
+        "application/json" => "json",
+        "text/csv" => "csv",
+        "text/html" => "html",
+        "text/markdown" => "md",
+        "text/plain" => "txt",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+/// MIME type from the file's first bytes, falling back to its extension.
+fn detect_mime(head: &[u8], name: &str) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| head.starts_with(sig)) {
+        return mime;
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    if head.len() >= 8 && &head[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "svg" => "image/svg+xml",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "md" => "text/markdown",
+        "txt" | "log" => "text/plain",
+        // Text without a known extension: no NULs and valid UTF-8 up to a
+        // character cut off at the end of the sample
+        _ if !head.is_empty()
+            && !head.contains(&0)
+            && std::str::from_utf8(head).map_or_else(|e| e.error_len().is_none(), |_| true) =>
+        {
+            "text/plain"
+        }
+        _ => "application/octet-stream",
+    }
+}
+
+/// Last `count` transcript entries (default 10), like `touchgrass peek`.
+#[tauri::command(async)]
+pub fn daemon_peek_session(
+    session_id: String,
+    count: Option<u32>,
+    client: State<'_, DaemonHandle>,
+) -> Result<PeekResponse, DaemonError> {
+    let path = format!(
+        "/session/{}/peek?count={}",
+        urlencoding(&session_id),
+        count.unwrap_or(10)
+    );
+    client.api().get(&path)
+}
+
+/// Ask the session's tool to exit, like `touchgrass stop`.
+#[tauri::command(async)]
+pub fn daemon_stop_session(
+    session_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = format!("/session/{}/stop", urlencoding(&session_id));
+    client.api().post(&path, &serde_json::json!({}))
+}
+
+/// Kill the session's tool, like `touchgrass kill`.
+#[tauri::command(async)]
+pub fn daemon_kill_session(
+    session_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = format!("/session/{}/kill", urlencoding(&session_id));
+    client.api().post(&path, &serde_json::json!({}))
+}
+
+// --- Chat links ---
+
+/// A chat connected to a running session, like a row of `touchgrass links`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatLink {
+    #[serde(rename = "chatId")]
+    pub chat_id: String,
+    /// Daemon session ID ("r-...")
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub label: String,
+    /// Linked group or topic title; None for DMs
+    pub title: Option<String>,
+    /// Whether the chat is the session owner's DM
+    #[serde(rename = "ownerDm")]
+    pub owner_dm: bool,
+    /// App session running it, if it runs in one of the app's terminals
+    #[serde(rename = "appSessionId", default)]
+    pub app_session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatLinksResponse {
+    pub ok: bool,
+    pub links: Vec<ChatLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnlinkResponse {
+    pub ok: bool,
+    /// Chats the session was disconnected from
+    #[serde(rename = "chatIds", default)]
+    pub chat_ids: Vec<String>,
+}
+
+#[tauri::command(async)]
+pub fn daemon_list_links(
+    app: AppHandle,
+    client: State<'_, DaemonHandle>,
+) -> Result<ChatLinksResponse, DaemonError> {
+    let mut resp: ChatLinksResponse = client.api().get("/links")?;
+    let by_remote = pty_manager::sessions_by_remote(&app);
+    for link in &mut resp.links {
+        link.app_session_id = by_remote.get(&link.session_id).cloned();
+    }
+    Ok(resp)
+}
+
+/// Connect a session to a chat (`RuntimeChannel.chat_id`), like
+/// `/start_remote_control`. The chat's previous session is disconnected.
+#[tauri::command(async)]
+pub fn daemon_link_session(
+    session_id: String,
+    chat_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    let path = format!("/session/{}/link", urlencoding(&session_id));
+    client
+        .api()
+        .post(&path, &serde_json::json!({ "chatId": chat_id }))
+}
+
+/// Disconnect a session from its chats, like `/stop_remote_control`.
+#[tauri::command(async)]
+pub fn daemon_unlink_session(
+    session_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<UnlinkResponse, DaemonError> {
+    let path = format!("/session/{}/unlink", urlencoding(&session_id));
+    client.api().post(&path, &serde_json::json!({}))
+}
+
+/// Move a linked session to another chat, like `/change_session`.
+#[tauri::command(async)]
+pub fn daemon_move_session(
+    session_id: String,
+    chat_id: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
+    move_session(client.api(), &session_id, &chat_id)
+}
+
+fn move_session(
+    api: &dyn DaemonApi,
+    session_id: &str,
+    chat_id: &str,
+) -> Result<SimpleResponse, DaemonError> {
+    let links: ChatLinksResponse = api.get("/links")?;
+    if !links.links.iter().any(|l| l.session_id == session_id) {
+        return Err(DaemonError::Invalid(
+            "Session is not linked to a chat".into(),
+        ));
+    }
+    // Linking detaches the session from the chat it was in
+    let path = format!("/session/{}/link", urlencoding(session_id));
+    api.post(&path, &serde_json::json!({ "chatId": chat_id }))
 }
 
 // --- Skills ---
@@ -490,12 +923,14 @@ pub struct SkillsResponse {
     pub skills: Vec<SkillInfo>,
 }
 
-#[tauri::command]
-pub fn daemon_list_skills(cwd: String) -> Result<SkillsResponse, String> {
+#[tauri::command(async)]
+pub fn daemon_list_skills(
+    cwd: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<SkillsResponse, DaemonError> {
     let encoded_cwd = urlencoding(&cwd);
     let path = format!("/skills?cwd={encoded_cwd}");
-    let body = http_request("GET", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().get(&path)
 }
 
 // --- Background jobs ---
@@ -527,11 +962,13 @@ pub struct BackgroundJobsResponse {
 }
 
 #[tauri::command]
-pub fn daemon_list_background_jobs(cwd: String) -> Result<BackgroundJobsResponse, String> {
+pub fn daemon_list_background_jobs(
+    cwd: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<BackgroundJobsResponse, DaemonError> {
     let encoded_cwd = urlencoding(&cwd);
     let path = format!("/background-jobs?cwd={encoded_cwd}");
-    let body = http_request("GET", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.passive().get(&path)
 }
 
 // --- Agent Soul ---
@@ -551,22 +988,25 @@ pub struct AgentSoulResponse {
     pub soul: Option<AgentSoul>,
 }
 
-#[tauri::command]
-pub fn daemon_get_agent_soul(cwd: String) -> Result<AgentSoulResponse, String> {
+#[tauri::command(async)]
+pub fn daemon_get_agent_soul(
+    cwd: String,
+    client: State<'_, DaemonHandle>,
+) -> Result<AgentSoulResponse, DaemonError> {
     let encoded_cwd = urlencoding(&cwd);
     let path = format!("/agent-soul?cwd={encoded_cwd}");
-    let body = http_request("GET", &path, None)?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().get(&path)
 }
 
-#[tauri::command]
+#[tauri::command(async)]
 pub fn daemon_set_agent_soul(
     cwd: String,
     name: String,
     purpose: String,
     owner: String,
     dna: Option<String>,
-) -> Result<SimpleResponse, String> {
+    client: State<'_, DaemonHandle>,
+) -> Result<SimpleResponse, DaemonError> {
     let encoded_cwd = urlencoding(&cwd);
     let path = format!("/agent-soul?cwd={encoded_cwd}");
     let mut payload = serde_json::json!({
@@ -577,8 +1017,7 @@ pub fn daemon_set_agent_soul(
     if let Some(d) = dna {
         payload["dna"] = serde_json::Value::String(d);
     }
-    let body = http_request("POST", &path, Some(&payload.to_string()))?;
-    serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {e}"))
+    client.api().post(&path, &payload)
 }
 
 pub(crate) fn find_tg_binary() -> Option<std::path::PathBuf> {
@@ -596,7 +1035,10 @@ pub(crate) fn find_tg_binary() -> Option<std::path::PathBuf> {
     }
 
     // PATH lookup for `touchgrass`
-    if let Ok(output) = std::process::Command::new("which").arg("touchgrass").output() {
+    if let Ok(output) = std::process::Command::new("which")
+        .arg("touchgrass")
+        .output()
+    {
         if output.status.success() {
             let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
             if !path.is_empty() {
@@ -632,10 +1074,7 @@ pub(crate) fn find_tg_dev() -> Option<(std::path::PathBuf, std::path::PathBuf)>
                     if output.status.success() {
                         let bun = String::from_utf8_lossy(&output.stdout).trim().to_string();
                         if !bun.is_empty() {
-                            return Some((
-                                std::path::PathBuf::from(bun),
-                                main_ts,
-                            ));
+                            return Some((std::path::PathBuf::from(bun), main_ts));
                         }
                     }
                 }
@@ -646,7 +1085,7 @@ pub(crate) fn find_tg_dev() -> Option<(std::path::PathBuf, std::path::PathBuf)>
 }
 
 /// Simple percent-encoding for URL path segments.
-fn urlencoding(s: &str) -> String {
+pub(crate) fn urlencoding(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     for b in s.bytes() {
         match b {
@@ -660,3 +1099,210 @@ fn urlencoding(s: &str) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon_client::FakeDaemon;
+
+    fn link(session_id: &str, chat_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "chatId": chat_id,
+            "sessionId": session_id,
+            "label": "claude",
+            "title": null,
+            "ownerDm": true,
+        })
+    }
+
+    #[test]
+    fn set_forwarding_passes_daemon_errors_through() {
+        let fake =
+            FakeDaemon::new().with_error("POST", "/remote/r-1/forwarding", DaemonError::Timeout);
+        assert_eq!(
+            set_forwarding(&fake, "r-1", Some("telegram:1")).unwrap_err(),
+            DaemonError::Timeout
+        );
+
+        let offline = FakeDaemon::with_defaults().offline();
+        assert!(matches!(
+            set_forwarding(&offline, "r-1", None),
+            Err(DaemonError::NotRunning(_))
+        ));
+    }
+
+    #[test]
+    fn send_input_fails_when_the_session_refuses_it() {
+        let fake = FakeDaemon::new()
+            .with_response(
+                "POST",
+                "/remote/r-1/send-input",
+                serde_json::json!({ "ok": true }),
+            )
+            .with_response(
+                "POST",
+                "/remote/r-2/send-input",
+                serde_json::json!({ "ok": false }),
+            );
+        assert!(send_input(&fake, "r-1", "y").is_ok());
+        assert_eq!(
+            send_input(&fake, "r-2", "y").unwrap_err(),
+            DaemonError::Invalid("Session did not accept the input".into())
+        );
+    }
+
+    #[test]
+    fn move_session_requires_a_linked_session() {
+        let fake = FakeDaemon::new()
+            .with_response(
+                "GET",
+                "/links",
+                serde_json::json!({ "ok": true, "links": [link("r-1", "telegram:1")] }),
+            )
+            .with_response(
+                "POST",
+                "/session/r-1/link",
+                serde_json::json!({ "ok": true }),
+            )
+            .with_response(
+                "POST",
+                "/session/r-2/link",
+                serde_json::json!({ "ok": true }),
+            );
+        assert!(move_session(&fake, "r-1", "telegram:2").unwrap().ok);
+        assert_eq!(
+            move_session(&fake, "r-2", "telegram:2").unwrap_err(),
+            DaemonError::Invalid("Session is not linked to a chat".into())
+        );
+    }
+
+    #[test]
+    fn move_session_passes_daemon_errors_through() {
+        let refused = DaemonError::Api {
+            status: 403,
+            message: "Chat is not linked".into(),
+        };
+        let fake = FakeDaemon::new()
+            .with_response(
+                "GET",
+                "/links",
+                serde_json::json!({ "ok": true, "links": [link("r-1", "telegram:1")] }),
+            )
+            .with_error("POST", "/session/r-1/link", refused.clone());
+        assert_eq!(
+            move_session(&fake, "r-1", "telegram:2").unwrap_err(),
+            refused
+        );
+    }
+
+    #[test]
+    fn chat_preferences_finds_the_chat() {
+        let fake = FakeDaemon::new().with_response(
+            "GET",
+            "/chat-preferences",
+            serde_json::json!({ "ok": true, "preferences": [{
+                "chatId": "telegram:1",
+                "outputMode": "verbose",
+                "thinking": false,
+                "muted": true,
+            }] }),
+        );
+        let prefs = chat_preferences(&fake, "telegram:1").unwrap();
+        assert!(prefs.muted);
+        assert!(matches!(
+            chat_preferences(&fake, "telegram:2"),
+            Err(DaemonError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn upload_file_names_the_file_and_skips_blank_captions() {
+        let fake = FakeDaemon::new().with_response(
+            "POST",
+            "/remote/r-1/upload-file",
+            serde_json::json!({ "ok": true }),
+        );
+        let resp = upload_file(
+            &fake,
+            "r-1",
+            "a b.txt",
+            "text/plain",
+            Some(" "),
+            &mut &b"hi"[..],
+            2,
+        );
+        assert!(resp.unwrap().ok);
+        assert!(matches!(
+            upload_file(
+                &fake,
+                "r-2",
+                "a.txt",
+                "text/plain",
+                None,
+                &mut &b"hi"[..],
+                2
+            ),
+            Err(DaemonError::Api { status: 404, .. })
+        ));
+    }
+
+    #[test]
+    fn detect_mime_prefers_file_signatures() {
+        assert_eq!(
+            detect_mime(b"\x89PNG\r\n\x1a\n....", "shot.txt"),
+            "image/png"
+        );
+        assert_eq!(detect_mime(b"\xff\xd8\xff\xe0", "photo"), "image/jpeg");
+        assert_eq!(detect_mime(b"GIF89a", ""), "image/gif");
+        assert_eq!(detect_mime(b"%PDF-1.7", "doc"), "application/pdf");
+        assert_eq!(detect_mime(b"RIFF\0\0\0\0WEBPVP8 ", "x"), "image/webp");
+        assert_eq!(detect_mime(b"\0\0\0\x18ftypmp42", "clip"), "video/mp4");
+    }
+
+    #[test]
+    fn detect_mime_falls_back_to_the_extension() {
+        assert_eq!(detect_mime(b"<svg/>", "Logo.SVG"), "image/svg+xml");
+        assert_eq!(detect_mime(b"a,b", "data.csv"), "text/csv");
+        assert_eq!(detect_mime(b"\0\x01", "build.log"), "text/plain");
+    }
+
+    #[test]
+    fn detect_mime_sniffs_text_without_a_known_extension() {
+        assert_eq!(detect_mime(b"hello\n", "notes"), "text/plain");
+        // A character cut off by the sample size is still text
+        assert_eq!(
+            detect_mime(&"caf\u{e9}".as_bytes()[..4], "notes"),
+            "text/plain"
+        );
+        assert_eq!(detect_mime(b"bin\0ary", "blob"), "application/octet-stream");
+        assert_eq!(
+            detect_mime(b"\xff\xfe\xfd", "blob"),
+            "application/octet-stream"
+        );
+        assert_eq!(detect_mime(b"", "empty"), "application/octet-stream");
+    }
+
+    #[test]
+    fn upload_name_adds_a_missing_extension() {
+        assert_eq!(upload_name("Screenshot", "image/png"), "Screenshot.png");
+        assert_eq!(upload_name("notes", "text/plain"), "notes.txt");
+        assert_eq!(upload_name(".bashrc", "text/plain"), ".bashrc");
+        assert_eq!(upload_name(".env.local", "text/plain"), ".env.local");
+        assert_eq!(upload_name("report.docx", "application/zip"), "report.docx");
+        assert_eq!(upload_name("blob", "application/octet-stream"), "blob");
+        assert_eq!(
+            upload_name("terminal-1.txt", "text/plain; charset=utf-8"),
+            "terminal-1.txt"
+        );
+    }
+
+    #[test]
+    fn send_size_limits() {
+        assert!(matches!(check_send_size(0), Err(DaemonError::Invalid(_))));
+        assert!(check_send_size(MAX_SEND_FILE_BYTES).is_ok());
+        assert!(matches!(
+            check_send_size(MAX_SEND_FILE_BYTES + 1),
+            Err(DaemonError::Invalid(_))
+        ));
+    }
+}
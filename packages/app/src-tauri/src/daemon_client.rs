@@ -0,0 +1,920 @@
+use crate::daemon;
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+#[cfg(any(test, debug_assertions))]
+use std::collections::HashMap;
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(not(unix))]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Long enough for calls that reach out to a channel API (e.g. adding a bot)
+const IO_TIMEOUT: Duration = Duration::from_secs(15);
+/// Uploads are answered after the daemon has passed the file on to the
+/// channel, which can take a while for large files
+const UPLOAD_RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+/// Extra attempts for idempotent (GET) calls, with this delay before each
+const GET_RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+/// After a failed start-on-demand, report the daemon as down for this long
+/// instead of trying again on every call
+const START_COOLDOWN: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaemonError {
+    /// No daemon is listening, or it went away mid-request
+    NotRunning(String),
+    /// daemon.auth doesn't match the running daemon
+    Unauthorized,
+    /// Connected, but the daemon didn't answer in time
+    Timeout,
+    /// The response wasn't HTTP or JSON we understand
+    Protocol(String),
+    /// The daemon handled the request and reported an error
+    Api { status: u16, message: String },
+    /// The request wasn't sent or its answer can't be used (e.g. an unknown
+    /// chat or an unreadable file)
+    Invalid(String),
+}
+
+impl DaemonError {
+    /// Whether retrying the same request could succeed. A timed-out request
+    /// may still be running in the daemon, so only connection failures are.
+    fn is_retryable(&self) -> bool {
+        matches!(self, DaemonError::NotRunning(_))
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            DaemonError::NotRunning(_) => "not_running",
+            DaemonError::Unauthorized => "unauthorized",
+            DaemonError::Timeout => "timeout",
+            DaemonError::Protocol(_) => "protocol",
+            DaemonError::Api { .. } => "api",
+            DaemonError::Invalid(_) => "invalid",
+        }
+    }
+}
+
+impl std::fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DaemonError::NotRunning(detail) => write!(f, "Cannot connect to daemon: {detail}"),
+            DaemonError::Unauthorized => write!(f, "Daemon rejected the auth token"),
+            DaemonError::Timeout => write!(f, "Daemon did not respond in time"),
+            DaemonError::Protocol(detail) => write!(f, "Invalid daemon response: {detail}"),
+            DaemonError::Api { message, .. } => write!(f, "{message}"),
+            DaemonError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Sent to the frontend as `{ kind, message }`, plus `status` for API errors.
+impl Serialize for DaemonError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let status = match self {
+            DaemonError::Api { status, .. } => Some(*status),
+            _ => None,
+        };
+        let mut state =
+            serializer.serialize_struct("DaemonError", 2 + status.is_some() as usize)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(status) = status {
+            state.serialize_field("status", &status)?;
+        }
+        state.end()
+    }
+}
+
+impl From<DaemonError> for String {
+    fn from(e: DaemonError) -> Self {
+        e.to_string()
+    }
+}
+
+fn io_error(e: std::io::Error) -> DaemonError {
+    match e.kind() {
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => DaemonError::Timeout,
+        _ => DaemonError::NotRunning(e.to_string()),
+    }
+}
+
+/// The daemon's control API. `DaemonClient` talks to the real daemon;
+/// `FakeDaemon` answers from canned responses.
+pub trait DaemonApi: Send + Sync {
+    /// Send one request and return the body of a successful response.
+    fn send(&self, method: &str, path: &str, body: Option<&str>) -> Result<String, DaemonError>;
+
+    /// POST `len` bytes read from `body` as-is, without holding them in memory.
+    fn upload(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: &mut dyn Read,
+        len: u64,
+    ) -> Result<String, DaemonError>;
+
+    /// Stop the daemon if it's running, start a new one and wait until it's
+    /// healthy.
+    fn restart(&self) -> Result<(), DaemonError>;
+}
+
+impl dyn DaemonApi + '_ {
+    /// GET and parse the response, retrying briefly if the daemon is
+    /// unreachable (e.g. mid-restart).
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, DaemonError> {
+        let mut attempt = 0;
+        loop {
+            match self.send("GET", path, None) {
+                Err(e) if e.is_retryable() && attempt < GET_RETRIES => {
+                    attempt += 1;
+                    std::thread::sleep(RETRY_DELAY * attempt);
+                }
+                result => return parse(&result?),
+            }
+        }
+    }
+
+    pub fn post<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        payload: &serde_json::Value,
+    ) -> Result<T, DaemonError> {
+        parse(&self.send("POST", path, Some(&payload.to_string()))?)
+    }
+
+    pub fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T, DaemonError> {
+        parse(&self.send("DELETE", path, None)?)
+    }
+
+    pub fn post_raw<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: &mut dyn Read,
+        len: u64,
+    ) -> Result<T, DaemonError> {
+        parse(&self.upload(path, content_type, body, len)?)
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &str) -> Result<T, DaemonError> {
+    serde_json::from_str(body)
+        .map_err(|e| DaemonError::Protocol(format!("Failed to parse response: {e}")))
+}
+
+/// Managed handle to the daemon API. In debug builds, set
+/// TOUCHGRASS_FAKE_DAEMON=1 to run the app against `FakeDaemon`, or =offline
+/// to simulate a stopped daemon.
+pub struct DaemonHandle {
+    api: Arc<dyn DaemonApi>,
+    passive: Arc<dyn DaemonApi>,
+}
+
+impl DaemonHandle {
+    pub fn new() -> Self {
+        #[cfg(debug_assertions)]
+        match std::env::var("TOUCHGRASS_FAKE_DAEMON").as_deref() {
+            Ok("offline") => return Self::single(Arc::new(FakeDaemon::new().offline())),
+            Ok(v) if !v.is_empty() && v != "0" => {
+                return Self::single(Arc::new(FakeDaemon::with_defaults()))
+            }
+            _ => {}
+        }
+        Self {
+            api: Arc::new(DaemonClient::new().start_on_demand()),
+            passive: Arc::new(DaemonClient::new()),
+        }
+    }
+
+    #[cfg(any(test, debug_assertions))]
+    fn single(api: Arc<dyn DaemonApi>) -> Self {
+        Self {
+            passive: api.clone(),
+            api,
+        }
+    }
+
+    /// For user-initiated calls: starts the daemon if it isn't running. That
+    /// can take seconds, so commands using it are `#[tauri::command(async)]`.
+    pub fn api(&self) -> &dyn DaemonApi {
+        self.api.as_ref()
+    }
+
+    /// For background polling: fails with `NotRunning` rather than starting
+    /// a daemon nobody asked for.
+    pub fn passive(&self) -> &dyn DaemonApi {
+        self.passive.as_ref()
+    }
+}
+
+// --- Real client ---
+
+/// Talks HTTP/1.1 to the daemon's control server, one connection per call.
+pub struct DaemonClient {
+    io_timeout: Duration,
+    /// Set when the client starts the daemon on demand. Holding the lock
+    /// makes concurrent calls wait for one start instead of spawning several
+    /// daemons; it records when the last start failed.
+    start_guard: Option<Mutex<Option<Instant>>>,
+}
+
+impl DaemonClient {
+    pub fn new() -> Self {
+        Self {
+            io_timeout: IO_TIMEOUT,
+            start_guard: None,
+        }
+    }
+
+    /// Start the daemon when it isn't running, then retry the call once.
+    pub fn start_on_demand(mut self) -> Self {
+        self.start_guard = Some(Mutex::new(None));
+        self
+    }
+
+    fn open(&self) -> Result<(String, DaemonStream), DaemonError> {
+        let token = read_auth_token()?;
+        let stream = connect()?;
+        stream
+            .set_read_timeout(Some(self.io_timeout))
+            .and_then(|_| stream.set_write_timeout(Some(self.io_timeout)))
+            .map_err(io_error)?;
+        Ok((token, stream))
+    }
+
+    /// Open a connection, starting the daemon first if this client does that.
+    fn connection(&self) -> Result<(String, DaemonStream), DaemonError> {
+        match (self.open(), &self.start_guard) {
+            (Err(e @ DaemonError::NotRunning(_)), Some(guard)) => {
+                self.start(guard, e)?;
+                self.open()
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Launch the daemon and wait until it's healthy. Only one caller starts
+    /// it; the rest wait on the guard and find it running.
+    fn start(
+        &self,
+        guard: &Mutex<Option<Instant>>,
+        not_running: DaemonError,
+    ) -> Result<(), DaemonError> {
+        let mut last_failure = guard.lock().unwrap();
+        if connect().is_ok() {
+            return Ok(());
+        }
+        if last_failure.is_some_and(|at| at.elapsed() < START_COOLDOWN) {
+            return Err(not_running);
+        }
+
+        log::info!("Daemon not running; starting it");
+        match launch() {
+            Ok(()) => {
+                *last_failure = None;
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("Could not start daemon: {e}");
+                *last_failure = Some(Instant::now());
+                Err(DaemonError::NotRunning(e))
+            }
+        }
+    }
+}
+
+/// Spawn the daemon and wait until it answers `/health`.
+fn launch() -> Result<(), String> {
+    daemon::spawn_daemon()?;
+    if daemon::wait_until_healthy(&DaemonClient::new()) {
+        Ok(())
+    } else {
+        Err("daemon started but health check timed out".to_string())
+    }
+}
+
+impl DaemonApi for DaemonClient {
+    fn send(&self, method: &str, path: &str, body: Option<&str>) -> Result<String, DaemonError> {
+        let (token, mut stream) = self.connection()?;
+
+        let body_bytes = body.unwrap_or("");
+        let content_length = body_bytes.len();
+        let request = format!(
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nx-touchgrass-auth: {token}\r\nContent-Type: application/json\r\nContent-Length: {content_length}\r\nConnection: close\r\n\r\n{body_bytes}"
+        );
+        stream.write_all(request.as_bytes()).map_err(io_error)?;
+
+        let response = read_response(&mut stream)?;
+        let response = String::from_utf8(response)
+            .map_err(|e| DaemonError::Protocol(format!("not valid UTF-8: {e}")))?;
+        parse_response(&response)
+    }
+
+    fn upload(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: &mut dyn Read,
+        len: u64,
+    ) -> Result<String, DaemonError> {
+        let (token, mut stream) = self.connection()?;
+        let request = format!(
+            "POST {path} HTTP/1.1\r\nHost: localhost\r\nx-touchgrass-auth: {token}\r\nContent-Type: {content_type}\r\nContent-Length: {len}\r\nConnection: close\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).map_err(io_error)?;
+        let sent = std::io::copy(&mut body.take(len), &mut stream).map_err(io_error)?;
+        if sent < len {
+            return Err(DaemonError::Protocol(format!(
+                "body ended after {sent} of {len} bytes"
+            )));
+        }
+        stream
+            .set_read_timeout(Some(UPLOAD_RESPONSE_TIMEOUT))
+            .map_err(io_error)?;
+
+        let response = read_response(&mut stream)?;
+        let response = String::from_utf8(response)
+            .map_err(|e| DaemonError::Protocol(format!("not valid UTF-8: {e}")))?;
+        parse_response(&response)
+    }
+
+    fn restart(&self) -> Result<(), DaemonError> {
+        // Under the start guard, so a call starting the daemon on demand
+        // can't launch a second one meanwhile
+        let mut last_failure = self.start_guard.as_ref().map(|g| g.lock().unwrap());
+        daemon::stop_daemon(&DaemonClient::new())?;
+        let started = launch();
+        if let Some(last_failure) = last_failure.as_mut() {
+            **last_failure = started.is_err().then(Instant::now);
+        }
+        started.map_err(DaemonError::NotRunning)
+    }
+}
+
+fn home_dir() -> Result<std::path::PathBuf, DaemonError> {
+    dirs::home_dir()
+        .map(|h| h.join(".touchgrass"))
+        .ok_or_else(|| DaemonError::NotRunning("cannot determine home directory".into()))
+}
+
+/// The daemon writes daemon.auth when it starts, so a missing file means it
+/// isn't running.
+pub(crate) fn read_auth_token() -> Result<String, DaemonError> {
+    let path = home_dir()?.join("daemon.auth");
+    std::fs::read_to_string(&path)
+        .map(|s| s.trim().to_string())
+        .map_err(|e| DaemonError::NotRunning(format!("failed to read auth token: {e}")))
+}
+
+#[cfg(unix)]
+pub(crate) type DaemonStream = UnixStream;
+#[cfg(not(unix))]
+pub(crate) type DaemonStream = std::net::TcpStream;
+
+/// Connect to the daemon's control server: daemon.sock, or the TCP port in
+/// daemon.port where Unix sockets aren't available.
+pub(crate) fn connect() -> Result<DaemonStream, DaemonError> {
+    #[cfg(unix)]
+    {
+        let sock_path = home_dir()?.join("daemon.sock");
+        UnixStream::connect(&sock_path).map_err(|e| DaemonError::NotRunning(e.to_string()))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let port_path = home_dir()?.join("daemon.port");
+        let port_str = std::fs::read_to_string(&port_path)
+            .map_err(|e| DaemonError::NotRunning(format!("failed to read daemon port: {e}")))?;
+        let port: u16 = port_str
+            .trim()
+            .parse()
+            .map_err(|e| DaemonError::NotRunning(format!("invalid daemon port: {e}")))?;
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+        std::net::TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| match e.kind() {
+            std::io::ErrorKind::TimedOut => DaemonError::Timeout,
+            _ => DaemonError::NotRunning(e.to_string()),
+        })
+    }
+}
+
+/// Read until the server closes or Content-Length bytes of body arrived
+/// (avoids blocking on a read waiting for the server to close the socket).
+fn read_response(stream: &mut impl Read) -> Result<Vec<u8>, DaemonError> {
+    let mut response = Vec::new();
+    let mut buf = [0u8; 8192];
+    let mut expected_len: Option<usize> = None;
+
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                response.extend_from_slice(&buf[..n]);
+                if expected_len.is_none() {
+                    if let Some(pos) = find_header_end(&response) {
+                        let headers = String::from_utf8_lossy(&response[..pos]);
+                        expected_len = header(&headers, "content-length")
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .map(|cl| pos + 4 + cl);
+                    }
+                }
+                if expected_len.is_some_and(|len| response.len() >= len) {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+    Ok(response)
+}
+
+fn parse_response(response: &str) -> Result<String, DaemonError> {
+    let body_start = response
+        .find("\r\n\r\n")
+        .ok_or_else(|| DaemonError::Protocol("no header/body separator".into()))?;
+    let headers = &response[..body_start];
+    let body = &response[body_start + 4..];
+
+    let status_code: u16 = headers
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| DaemonError::Protocol("cannot parse status code".into()))?;
+
+    let body = if header(headers, "transfer-encoding")
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+    {
+        decode_chunked(body)?
+    } else {
+        body.to_string()
+    };
+
+    if status_code == 401 {
+        return Err(DaemonError::Unauthorized);
+    }
+    if status_code >= 400 {
+        // Prefer the error message from the JSON body
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|json| json.get("error")?.as_str().map(String::from))
+            .unwrap_or_else(|| format!("HTTP {status_code}: {body}"));
+        return Err(DaemonError::Api {
+            status: status_code,
+            message,
+        });
+    }
+    Ok(body)
+}
+
+fn header<'a>(headers: &'a str, name: &str) -> Option<&'a str> {
+    headers.split("\r\n").find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
+
+fn find_header_end(data: &[u8]) -> Option<usize> {
+    data.windows(4).position(|w| w == b"\r\n\r\n")
+}
+
+fn decode_chunked(body: &str) -> Result<String, DaemonError> {
+    let mut result = String::new();
+    let mut remaining = body;
+
+    loop {
+        // Skip leading whitespace/newlines
+        remaining = remaining.trim_start();
+        if remaining.is_empty() {
+            break;
+        }
+
+        // Find chunk size line
+        let size_end = remaining.find("\r\n").unwrap_or(remaining.len());
+        let size_str = &remaining[..size_end];
+        let chunk_size = usize::from_str_radix(size_str.trim(), 16)
+            .map_err(|_| DaemonError::Protocol(format!("invalid chunk size: '{size_str}'")))?;
+
+        if chunk_size == 0 {
+            break;
+        }
+
+        // Move past the size line + \r\n
+        let data_start = size_end + 2;
+        if data_start + chunk_size > remaining.len() {
+            // Grab what we can
+            result.push_str(&remaining[data_start.min(remaining.len())..]);
+            break;
+        }
+
+        result.push_str(&remaining[data_start..data_start + chunk_size]);
+        remaining = &remaining[data_start + chunk_size..];
+    }
+
+    Ok(result)
+}
+
+// --- Fake ---
+
+/// Answers from canned responses keyed by method and path (query string
+/// ignored). Unknown routes get the daemon's 404.
+#[cfg(any(test, debug_assertions))]
+pub struct FakeDaemon {
+    responses: HashMap<String, Result<String, DaemonError>>,
+    offline: bool,
+}
+
+#[cfg(any(test, debug_assertions))]
+impl FakeDaemon {
+    pub fn new() -> Self {
+        Self {
+            responses: HashMap::new(),
+            offline: false,
+        }
+    }
+
+    /// A healthy daemon with no channels, sessions or jobs.
+    pub fn with_defaults() -> Self {
+        let empty_list = |key: &str| {
+            let mut body = serde_json::json!({ "ok": true });
+            body[key] = serde_json::json!([]);
+            body
+        };
+        Self::new()
+            .with_response(
+                "GET",
+                "/health",
+                serde_json::json!({ "ok": true, "pid": std::process::id(), "startedAt": 0 }),
+            )
+            .with_response("GET", "/config/channels", empty_list("channels"))
+            .with_response("GET", "/channels", empty_list("channels"))
+            .with_response("GET", "/chat-preferences", empty_list("preferences"))
+            .with_response("GET", "/input-needed", empty_list("sessions"))
+            .with_response("GET", "/sessions", empty_list("sessions"))
+            .with_response("GET", "/sessions/recent", empty_list("sessions"))
+            .with_response("GET", "/links", empty_list("links"))
+            .with_response("GET", "/skills", empty_list("skills"))
+            .with_response("GET", "/background-jobs", empty_list("sessions"))
+            .with_response(
+                "GET",
+                "/agent-soul",
+                serde_json::json!({ "ok": true, "soul": null }),
+            )
+            .with_response(
+                "POST",
+                "/generate-code",
+                serde_json::json!({ "ok": true, "code": "TG-FAKE-0000" }),
+            )
+            .with_response("POST", "/shutdown", serde_json::json!({ "ok": true }))
+    }
+
+    pub fn with_response(mut self, method: &str, path: &str, body: serde_json::Value) -> Self {
+        self.responses
+            .insert(format!("{method} {path}"), Ok(body.to_string()));
+        self
+    }
+
+    /// Fail requests to this route with `error`.
+    #[cfg(test)]
+    pub fn with_error(mut self, method: &str, path: &str, error: DaemonError) -> Self {
+        self.responses
+            .insert(format!("{method} {path}"), Err(error));
+        self
+    }
+
+    /// Fail every request as if no daemon were running.
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+}
+
+#[cfg(any(test, debug_assertions))]
+impl DaemonApi for FakeDaemon {
+    fn send(&self, method: &str, path: &str, _body: Option<&str>) -> Result<String, DaemonError> {
+        if self.offline {
+            return Err(DaemonError::NotRunning("fake daemon is offline".into()));
+        }
+        let route = path.split('?').next().unwrap_or(path);
+        self.responses
+            .get(&format!("{method} {route}"))
+            .cloned()
+            .unwrap_or_else(|| {
+                Err(DaemonError::Api {
+                    status: 404,
+                    message: "Not found".into(),
+                })
+            })
+    }
+
+    fn upload(
+        &self,
+        path: &str,
+        _content_type: &str,
+        body: &mut dyn Read,
+        len: u64,
+    ) -> Result<String, DaemonError> {
+        std::io::copy(&mut body.take(len), &mut std::io::sink()).map_err(io_error)?;
+        self.send("POST", path, None)
+    }
+
+    fn restart(&self) -> Result<(), DaemonError> {
+        self.send("POST", "/shutdown", Some("{}")).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn http(status: &str, headers: &str, body: &str) -> String {
+        format!("HTTP/1.1 {status}\r\n{headers}\r\n{body}")
+    }
+
+    /// Hands out its data a few bytes at a time, then fails like a socket
+    /// whose peer stopped answering.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        then: std::io::ErrorKind,
+    }
+
+    impl Trickle {
+        fn new(data: &str, then: std::io::ErrorKind) -> Self {
+            Self {
+                data: data.as_bytes().to_vec(),
+                pos: 0,
+                then,
+            }
+        }
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pos == self.data.len() {
+                return Err(self.then.into());
+            }
+            let n = buf.len().min(7).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    /// Fails the first `failures` requests with `error`, then answers `{}`.
+    struct Flaky {
+        failures: u32,
+        error: DaemonError,
+        calls: AtomicU32,
+    }
+
+    impl Flaky {
+        fn new(failures: u32, error: DaemonError) -> Self {
+            Self {
+                failures,
+                error,
+                calls: AtomicU32::new(0),
+            }
+        }
+    }
+
+    impl DaemonApi for Flaky {
+        fn send(&self, _: &str, _: &str, _: Option<&str>) -> Result<String, DaemonError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(self.error.clone());
+            }
+            Ok("{}".into())
+        }
+
+        fn upload(
+            &self,
+            _: &str,
+            _: &str,
+            _: &mut dyn Read,
+            _: u64,
+        ) -> Result<String, DaemonError> {
+            unreachable!()
+        }
+
+        fn restart(&self) -> Result<(), DaemonError> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn parse_response_returns_the_body_of_a_success() {
+        let response = http("200 OK", "Content-Length: 11\r\n", r#"{"ok":true}"#);
+        assert_eq!(parse_response(&response).unwrap(), r#"{"ok":true}"#);
+    }
+
+    #[test]
+    fn parse_response_maps_error_statuses() {
+        let response = http("401 Unauthorized", "", "");
+        assert_eq!(parse_response(&response), Err(DaemonError::Unauthorized));
+
+        let response = http(
+            "404 Not Found",
+            "",
+            r#"{"ok":false,"error":"No such session"}"#,
+        );
+        assert_eq!(
+            parse_response(&response),
+            Err(DaemonError::Api {
+                status: 404,
+                message: "No such session".into()
+            })
+        );
+
+        let response = http("500 Internal Server Error", "", "boom");
+        assert_eq!(
+            parse_response(&response),
+            Err(DaemonError::Api {
+                status: 500,
+                message: "HTTP 500: boom".into()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_response_decodes_chunked_bodies() {
+        let response = http(
+            "400 Bad Request",
+            "transfer-encoding: Chunked\r\n",
+            "6\r\n{\"erro\r\nc\r\nr\":\"no cwd\"}\r\n0\r\n\r\n",
+        );
+        assert_eq!(
+            parse_response(&response),
+            Err(DaemonError::Api {
+                status: 400,
+                message: "no cwd".into()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_response_rejects_malformed_responses() {
+        assert!(matches!(
+            parse_response("HTTP/1.1 200 OK\r\n"),
+            Err(DaemonError::Protocol(_))
+        ));
+        assert!(matches!(
+            parse_response("HTTP/1.1 OK\r\n\r\n{}"),
+            Err(DaemonError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn decode_chunked_joins_chunks_until_the_last() {
+        assert_eq!(
+            decode_chunked("3\r\nabc\r\nA\r\n0123456789\r\n0\r\n\r\nignored").unwrap(),
+            "abc0123456789"
+        );
+        assert_eq!(decode_chunked("").unwrap(), "");
+    }
+
+    #[test]
+    fn decode_chunked_keeps_a_truncated_chunk() {
+        assert_eq!(decode_chunked("a\r\nabc").unwrap(), "abc");
+    }
+
+    #[test]
+    fn decode_chunked_rejects_bad_sizes() {
+        assert!(matches!(
+            decode_chunked("xyz\r\nabc\r\n"),
+            Err(DaemonError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn read_response_stops_after_content_length() {
+        // Reading past the body would hit the timeout
+        let response = http("200 OK", "Content-Length: 2\r\n", "{}");
+        let mut stream = Trickle::new(&response, std::io::ErrorKind::TimedOut);
+        assert_eq!(read_response(&mut stream).unwrap(), response.as_bytes());
+    }
+
+    #[test]
+    fn read_response_reads_to_eof_without_content_length() {
+        let response = http("200 OK", "", r#"{"ok":true}"#);
+        assert_eq!(
+            read_response(&mut response.as_bytes()).unwrap(),
+            response.as_bytes()
+        );
+    }
+
+    #[test]
+    fn read_response_maps_read_errors() {
+        let partial = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n{";
+        let mut stream = Trickle::new(partial, std::io::ErrorKind::WouldBlock);
+        assert_eq!(read_response(&mut stream), Err(DaemonError::Timeout));
+        let mut stream = Trickle::new(partial, std::io::ErrorKind::ConnectionReset);
+        assert!(matches!(
+            read_response(&mut stream),
+            Err(DaemonError::NotRunning(_))
+        ));
+    }
+
+    #[test]
+    fn get_retries_while_the_daemon_is_unreachable() {
+        let flaky = Flaky::new(2, DaemonError::NotRunning("refused".into()));
+        let api: &dyn DaemonApi = &flaky;
+        assert_eq!(
+            api.get::<serde_json::Value>("/health").unwrap(),
+            serde_json::json!({})
+        );
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        let flaky = Flaky::new(u32::MAX, DaemonError::NotRunning("refused".into()));
+        let api: &dyn DaemonApi = &flaky;
+        assert!(api.get::<serde_json::Value>("/health").is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1 + GET_RETRIES);
+    }
+
+    #[test]
+    fn get_does_not_retry_timeouts_or_posts() {
+        let flaky = Flaky::new(1, DaemonError::Timeout);
+        let api: &dyn DaemonApi = &flaky;
+        assert_eq!(
+            api.get::<serde_json::Value>("/health"),
+            Err(DaemonError::Timeout)
+        );
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+
+        let flaky = Flaky::new(1, DaemonError::NotRunning("refused".into()));
+        let api: &dyn DaemonApi = &flaky;
+        assert!(api
+            .post::<serde_json::Value>("/generate-code", &serde_json::json!({}))
+            .is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn fake_daemon_answers_scripted_routes() {
+        let fake = FakeDaemon::with_defaults()
+            .with_error("GET", "/links", DaemonError::Unauthorized)
+            .with_response(
+                "POST",
+                "/session/r-1/stop",
+                serde_json::json!({ "ok": true }),
+            );
+        let api: &dyn DaemonApi = &fake;
+
+        let skills: serde_json::Value = api.get("/skills?cwd=%2Ftmp").unwrap();
+        assert_eq!(skills, serde_json::json!({ "ok": true, "skills": [] }));
+        assert_eq!(
+            api.get::<serde_json::Value>("/links"),
+            Err(DaemonError::Unauthorized)
+        );
+        assert!(api
+            .post::<serde_json::Value>("/session/r-1/stop", &serde_json::json!({}))
+            .is_ok());
+        assert!(matches!(
+            api.delete::<serde_json::Value>("/session/r-1/stop"),
+            Err(DaemonError::Api { status: 404, .. })
+        ));
+    }
+
+    #[test]
+    fn offline_fake_daemon_is_not_running() {
+        let fake = FakeDaemon::with_defaults().offline();
+        let api: &dyn DaemonApi = &fake;
+        assert!(matches!(
+            api.get::<serde_json::Value>("/health"),
+            Err(DaemonError::NotRunning(_))
+        ));
+    }
+
+    #[test]
+    fn fake_daemon_restarts_unless_offline() {
+        assert_eq!(FakeDaemon::with_defaults().restart(), Ok(()));
+        assert!(matches!(
+            FakeDaemon::with_defaults().offline().restart(),
+            Err(DaemonError::NotRunning(_))
+        ));
+    }
+
+    #[test]
+    fn errors_serialize_with_kind_and_message() {
+        assert_eq!(
+            serde_json::to_value(DaemonError::Timeout).unwrap(),
+            serde_json::json!({ "kind": "timeout", "message": "Daemon did not respond in time" })
+        );
+        assert_eq!(
+            serde_json::to_value(DaemonError::Api {
+                status: 404,
+                message: "No such session".into()
+            })
+            .unwrap(),
+            serde_json::json!({ "kind": "api", "message": "No such session", "status": 404 })
+        );
+    }
+}
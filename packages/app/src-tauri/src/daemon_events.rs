@@ -0,0 +1,462 @@
+use crate::pty_manager;
+use crate::webhooks;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Newest daemon push event version this build understands. Events from a
+/// newer daemon are passed through as raw JSON rather than misread.
+pub const DAEMON_EVENT_VERSION: u32 = 1;
+
+/// Sequence numbers remembered per daemon run, matching the number of events
+/// the daemon keeps for replay.
+const SEEN_EVENTS: usize = 500;
+
+/// Events the daemon pushes to `POST /event`, tagged by `type`. Each kind is
+/// emitted to the UI as `daemon-<kind>`; anything this build can't read
+/// becomes `Raw` and is emitted as `daemon-raw`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum DaemonPushEvent {
+    ChannelLinked(ChannelLinked),
+    ChannelUnlinked(ChannelUnlinked),
+    #[serde(rename = "user-paired", alias = "pairing-complete")]
+    PairingComplete(PairingComplete),
+    RemoteInput(RemoteInput),
+    SessionLinked(SessionLink),
+    SessionUnlinked(SessionLink),
+    JobUpdated(JobUpdated),
+    #[serde(skip)]
+    Raw(RawEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelLinked {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(alias = "chatId")]
+    pub chat_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelUnlinked {
+    #[serde(alias = "chatId")]
+    pub chat_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairingComplete {
+    #[serde(default, alias = "userId")]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Channel name the user paired through
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+/// A channel message delivered to a session's input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteInput {
+    /// Daemon session ID (r-...)
+    #[serde(alias = "sessionId")]
+    pub session_id: String,
+    /// The app's session for it, if it runs in this app
+    #[serde(default)]
+    pub app_session_id: Option<String>,
+    #[serde(alias = "chatId")]
+    pub chat_id: String,
+    #[serde(default, alias = "userId")]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    pub text: String,
+}
+
+/// A chat connected to or disconnected from a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLink {
+    /// Daemon session ID (r-...)
+    #[serde(alias = "sessionId")]
+    pub session_id: String,
+    #[serde(default)]
+    pub app_session_id: Option<String>,
+    #[serde(alias = "chatId")]
+    pub chat_id: String,
+}
+
+/// A background job in a session started, finished, failed or was killed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobUpdated {
+    /// Daemon session ID (r-...)
+    #[serde(alias = "sessionId")]
+    pub session_id: String,
+    #[serde(default)]
+    pub app_session_id: Option<String>,
+    #[serde(alias = "taskId")]
+    pub task_id: String,
+    /// "running" | "completed" | "failed" | "killed"
+    pub status: String,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub urls: Vec<String>,
+}
+
+/// An event this build doesn't know, or from a newer protocol version.
+#[derive(Debug, Clone, Serialize)]
+pub struct RawEvent {
+    pub event_type: String,
+    pub version: u32,
+    pub body: serde_json::Value,
+}
+
+/// Flat payload of the original `daemon-event`, kept for existing listeners.
+#[derive(Clone, Serialize)]
+struct LegacyDaemonEvent {
+    event_type: String,
+    title: Option<String>,
+    chat_id: Option<String>,
+    username: Option<String>,
+}
+
+impl DaemonPushEvent {
+    /// Parse a `/event` body. Only `type` is required; `version` defaults to 1.
+    pub fn parse(json: serde_json::Value) -> Result<Self, String> {
+        let event_type = json
+            .get("type")
+            .and_then(|v| v.as_str())
+            .filter(|t| !t.is_empty())
+            .ok_or("missing type")?
+            .to_string();
+        let version = json
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .map_or(1, |v| v as u32);
+
+        let raw = |body: serde_json::Value| {
+            DaemonPushEvent::Raw(RawEvent {
+                event_type: event_type.clone(),
+                version,
+                body,
+            })
+        };
+        if version > DAEMON_EVENT_VERSION {
+            return Ok(raw(json));
+        }
+        match serde_json::from_value::<DaemonPushEvent>(json.clone()) {
+            Ok(event) => Ok(event),
+            Err(e) => {
+                log::debug!("Daemon event {event_type} passed through raw: {e}");
+                Ok(raw(json))
+            }
+        }
+    }
+
+    /// Kebab-case kind, e.g. "remote-input". Raw events keep their own type.
+    pub fn kind(&self) -> &str {
+        match self {
+            DaemonPushEvent::ChannelLinked(_) => "channel-linked",
+            DaemonPushEvent::ChannelUnlinked(_) => "channel-unlinked",
+            DaemonPushEvent::PairingComplete(_) => "pairing-complete",
+            DaemonPushEvent::RemoteInput(_) => "remote-input",
+            DaemonPushEvent::SessionLinked(_) => "session-linked",
+            DaemonPushEvent::SessionUnlinked(_) => "session-unlinked",
+            DaemonPushEvent::JobUpdated(_) => "job-updated",
+            DaemonPushEvent::Raw(raw) => &raw.event_type,
+        }
+    }
+
+    /// Tauri event name, e.g. "daemon-remote-input". Raw events share
+    /// "daemon-raw", so a listener for a typed name only gets typed payloads.
+    pub fn tauri_event_name(&self) -> String {
+        match self {
+            DaemonPushEvent::Raw(_) => "daemon-raw".to_string(),
+            typed => format!("daemon-{}", typed.kind()),
+        }
+    }
+
+    pub fn payload(&self) -> serde_json::Value {
+        let value = match self {
+            DaemonPushEvent::ChannelLinked(e) => serde_json::to_value(e),
+            DaemonPushEvent::ChannelUnlinked(e) => serde_json::to_value(e),
+            DaemonPushEvent::PairingComplete(e) => serde_json::to_value(e),
+            DaemonPushEvent::RemoteInput(e) => serde_json::to_value(e),
+            DaemonPushEvent::SessionLinked(e) | DaemonPushEvent::SessionUnlinked(e) => {
+                serde_json::to_value(e)
+            }
+            DaemonPushEvent::JobUpdated(e) => serde_json::to_value(e),
+            DaemonPushEvent::Raw(e) => serde_json::to_value(e),
+        };
+        value.unwrap_or_default()
+    }
+
+    /// Fill in the app session for events about a daemon session.
+    fn resolve_app_session(&mut self, app: &AppHandle) {
+        let (remote_id, app_session_id) = match self {
+            DaemonPushEvent::RemoteInput(e) => (&e.session_id, &mut e.app_session_id),
+            DaemonPushEvent::SessionLinked(e) | DaemonPushEvent::SessionUnlinked(e) => {
+                (&e.session_id, &mut e.app_session_id)
+            }
+            DaemonPushEvent::JobUpdated(e) => (&e.session_id, &mut e.app_session_id),
+            _ => return,
+        };
+        *app_session_id = pty_manager::session_for_remote(app, remote_id);
+    }
+
+    fn legacy(&self) -> LegacyDaemonEvent {
+        let mut legacy = LegacyDaemonEvent {
+            event_type: self.kind().to_string(),
+            title: None,
+            chat_id: None,
+            username: None,
+        };
+        match self {
+            DaemonPushEvent::ChannelLinked(e) => {
+                legacy.title = e.title.clone();
+                legacy.chat_id = Some(e.chat_id.clone());
+            }
+            DaemonPushEvent::ChannelUnlinked(e) => legacy.chat_id = Some(e.chat_id.clone()),
+            DaemonPushEvent::PairingComplete(e) => {
+                // Existing listeners know this one by its wire name
+                legacy.event_type = "user-paired".into();
+                legacy.username = e.username.clone();
+            }
+            DaemonPushEvent::RemoteInput(e) => legacy.chat_id = Some(e.chat_id.clone()),
+            DaemonPushEvent::SessionLinked(e) | DaemonPushEvent::SessionUnlinked(e) => {
+                legacy.chat_id = Some(e.chat_id.clone())
+            }
+            DaemonPushEvent::JobUpdated(_) => {}
+            DaemonPushEvent::Raw(e) => {
+                let str_field =
+                    |name: &str| e.body.get(name).and_then(|v| v.as_str()).map(String::from);
+                legacy.title = str_field("title");
+                legacy.chat_id = str_field("chatId");
+                legacy.username = str_field("username");
+            }
+        }
+        legacy
+    }
+}
+
+/// Daemon run and sequence number of an event body (`bootId`, `seq`), sent by
+/// daemons that number their events.
+pub fn sequence(json: &serde_json::Value) -> Option<(String, u64)> {
+    let boot_id = json.get("bootId").and_then(|v| v.as_str())?;
+    let seq = json.get("seq").and_then(|v| v.as_u64())?;
+    Some((boot_id.to_string(), seq))
+}
+
+/// Recently dispatched sequence numbers of the current daemon run.
+#[derive(Default)]
+struct Seen {
+    boot_id: String,
+    seqs: VecDeque<u64>,
+}
+
+impl Seen {
+    /// Record an event, returning false if it was already dispatched.
+    fn first_time(&mut self, boot_id: &str, seq: u64) -> bool {
+        if self.boot_id != boot_id {
+            self.boot_id = boot_id.to_string();
+            self.seqs.clear();
+        }
+        if self.seqs.contains(&seq) {
+            return false;
+        }
+        if self.seqs.len() >= SEEN_EVENTS {
+            self.seqs.pop_front();
+        }
+        self.seqs.push_back(seq);
+        true
+    }
+}
+
+/// Events can reach the app twice, pushed to `/event` and replayed on the
+/// event stream; this keeps the second copy from being dispatched.
+pub struct SeenDaemonEvents {
+    seen: Mutex<Seen>,
+}
+
+impl SeenDaemonEvents {
+    pub fn new() -> Self {
+        Self {
+            seen: Mutex::new(Seen::default()),
+        }
+    }
+}
+
+/// Emit a daemon push event to the UI and forward it to webhooks, unless an
+/// event with the same `sequence` was already dispatched.
+pub fn dispatch(app: &AppHandle, mut event: DaemonPushEvent, sequence: Option<(String, u64)>) {
+    if let Some((boot_id, seq)) = sequence {
+        let seen = app.state::<SeenDaemonEvents>();
+        if !seen.seen.lock().unwrap().first_time(&boot_id, seq) {
+            log::debug!("Skipping daemon event {seq}, already dispatched");
+            return;
+        }
+    }
+    event.resolve_app_session(app);
+    let payload = event.payload();
+
+    webhooks::publish(app, "daemon", event.kind(), None, None, payload.clone());
+    let _ = app.emit(&event.tauri_event_name(), payload);
+    let _ = app.emit("daemon-event", event.legacy());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn raw(event: &DaemonPushEvent) -> &RawEvent {
+        match event {
+            DaemonPushEvent::Raw(raw) => raw,
+            other => panic!("expected a raw event, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_known_events() {
+        let event = DaemonPushEvent::parse(json!({
+            "type": "channel-linked",
+            "title": "Team",
+            "chat_id": "telegram:1",
+        }))
+        .unwrap();
+        let DaemonPushEvent::ChannelLinked(linked) = &event else {
+            panic!("expected channel-linked, got {event:?}");
+        };
+        assert_eq!(linked.title.as_deref(), Some("Team"));
+        assert_eq!(linked.chat_id, "telegram:1");
+        assert_eq!(event.tauri_event_name(), "daemon-channel-linked");
+    }
+
+    #[test]
+    fn accepts_camel_case_fields() {
+        let event = DaemonPushEvent::parse(json!({
+            "type": "remote-input",
+            "version": 1,
+            "sessionId": "r-1",
+            "chatId": "telegram:1",
+            "userId": "u-1",
+            "text": "hi",
+        }))
+        .unwrap();
+        let DaemonPushEvent::RemoteInput(input) = &event else {
+            panic!("expected remote-input, got {event:?}");
+        };
+        assert_eq!(input.session_id, "r-1");
+        assert_eq!(input.chat_id, "telegram:1");
+        assert_eq!(input.user_id.as_deref(), Some("u-1"));
+        assert_eq!(input.app_session_id, None);
+
+        let event = DaemonPushEvent::parse(json!({
+            "type": "job-updated",
+            "sessionId": "r-1",
+            "taskId": "t-1",
+            "status": "running",
+        }))
+        .unwrap();
+        let DaemonPushEvent::JobUpdated(job) = &event else {
+            panic!("expected job-updated, got {event:?}");
+        };
+        assert_eq!(
+            (job.session_id.as_str(), job.task_id.as_str()),
+            ("r-1", "t-1")
+        );
+        assert!(job.urls.is_empty());
+    }
+
+    #[test]
+    fn pairing_has_two_wire_names() {
+        for name in ["user-paired", "pairing-complete"] {
+            let event = DaemonPushEvent::parse(json!({
+                "type": name,
+                "userId": "u-1",
+                "username": "sam",
+            }))
+            .unwrap();
+            let DaemonPushEvent::PairingComplete(paired) = &event else {
+                panic!("expected pairing-complete for {name}, got {event:?}");
+            };
+            assert_eq!(paired.user_id.as_deref(), Some("u-1"));
+            assert_eq!(event.kind(), "pairing-complete");
+            // Legacy listeners still see the original name
+            assert_eq!(event.legacy().event_type, "user-paired");
+            assert_eq!(event.legacy().username.as_deref(), Some("sam"));
+        }
+    }
+
+    #[test]
+    fn newer_versions_pass_through_raw() {
+        let body = json!({
+            "type": "channel-linked",
+            "version": DAEMON_EVENT_VERSION + 1,
+            "chatId": "telegram:1",
+        });
+        let event = DaemonPushEvent::parse(body.clone()).unwrap();
+        let raw = raw(&event);
+        assert_eq!(raw.event_type, "channel-linked");
+        assert_eq!(raw.version, DAEMON_EVENT_VERSION + 1);
+        assert_eq!(raw.body, body);
+        assert_eq!(event.legacy().chat_id.as_deref(), Some("telegram:1"));
+    }
+
+    #[test]
+    fn unknown_types_pass_through_raw() {
+        let event = DaemonPushEvent::parse(json!({ "type": "Quota Warning", "left": 3 })).unwrap();
+        assert_eq!(raw(&event).version, 1);
+        assert_eq!(event.kind(), "Quota Warning");
+        assert_eq!(event.tauri_event_name(), "daemon-raw");
+        assert_eq!(event.payload()["body"]["left"], 3);
+    }
+
+    #[test]
+    fn known_types_missing_fields_pass_through_raw() {
+        let event =
+            DaemonPushEvent::parse(json!({ "type": "remote-input", "text": "hi" })).unwrap();
+        assert_eq!(raw(&event).event_type, "remote-input");
+        // Not under the typed name, whose listeners expect a RemoteInput
+        assert_eq!(event.tauri_event_name(), "daemon-raw");
+        assert_eq!(event.payload()["event_type"], "remote-input");
+        assert_eq!(event.payload()["body"]["text"], "hi");
+    }
+
+    #[test]
+    fn reads_the_sequence_from_the_body() {
+        let body = json!({ "type": "channel-unlinked", "seq": 7, "bootId": "42-abc" });
+        assert_eq!(sequence(&body), Some(("42-abc".to_string(), 7)));
+        assert_eq!(
+            sequence(&json!({ "type": "channel-unlinked", "seq": 7 })),
+            None
+        );
+    }
+
+    #[test]
+    fn each_sequence_number_is_dispatched_once_per_run() {
+        let mut seen = Seen::default();
+        assert!(seen.first_time("run-1", 2));
+        assert!(seen.first_time("run-1", 1));
+        assert!(!seen.first_time("run-1", 2));
+        // A restarted daemon numbers from 1 again
+        assert!(seen.first_time("run-2", 1));
+        assert!(seen.first_time("run-2", 2));
+
+        for seq in 3..3 + SEEN_EVENTS as u64 {
+            assert!(seen.first_time("run-2", seq));
+        }
+        assert_eq!(seen.seqs.len(), SEEN_EVENTS);
+        assert!(seen.first_time("run-2", 1));
+    }
+
+    #[test]
+    fn type_is_required() {
+        assert!(DaemonPushEvent::parse(json!({ "chatId": "telegram:1" })).is_err());
+        assert!(DaemonPushEvent::parse(json!({ "type": "" })).is_err());
+        assert!(DaemonPushEvent::parse(json!({ "type": 3 })).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::daemon;
+use crate::daemon_client::{self, DaemonStream};
+use crate::daemon_events::{self, DaemonPushEvent};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// The daemon pings idle streams every few seconds; silence longer than this
+/// means the connection is dead.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Where the app is in the daemon's event stream. Sequence numbers restart
+/// whenever the daemon does, so they only mean something with the boot ID.
+#[derive(Default)]
+struct Cursor {
+    boot_id: Option<String>,
+    last_seq: u64,
+}
+
+/// Keep a subscription open to the daemon's `/app/events` stream, replaying
+/// whatever was missed while disconnected. Reconnects with backoff.
+pub fn start_subscription(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut cursor = Cursor::default();
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            match subscribe(&app, &mut cursor, &mut backoff) {
+                Ok(()) => log::info!("Daemon event stream closed"),
+                Err(e) => log::debug!("Daemon event stream: {e}"),
+            }
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+/// Run one connection until the daemon closes it or it fails.
+fn subscribe(app: &AppHandle, cursor: &mut Cursor, backoff: &mut Duration) -> Result<(), String> {
+    let path = match &cursor.boot_id {
+        Some(boot_id) => format!(
+            "/app/events?since={}&boot={}",
+            cursor.last_seq,
+            daemon::urlencoding(boot_id)
+        ),
+        // Daemons from before the stream existed answer 404 here
+        None => "/app/events".to_string(),
+    };
+    open(&path)?.read_events(|event_name, id, data| {
+        handle_event(app, event_name, id, data, cursor, backoff)
+    })
+}
+
+/// An open server-sent event stream from the daemon.
+pub(crate) struct EventStream {
+    reader: Box<dyn BufRead + Send>,
+    /// Second handle on the connection, so another thread can close it
+    socket: DaemonStream,
+}
+
+/// Send a GET for a server-sent event stream and read up to its body.
+pub(crate) fn open(path: &str) -> Result<EventStream, String> {
+    let token = daemon_client::read_auth_token()?;
+    let mut stream = daemon_client::connect()?;
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| format!("Failed to set read timeout: {e}"))?;
+    let socket = stream
+        .try_clone()
+        .map_err(|e| format!("Failed to clone connection: {e}"))?;
+
+    let request = format!(
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\nx-touchgrass-auth: {token}\r\nAccept: text/event-stream\r\n\r\n"
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send request: {e}"))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    read_line(&mut reader, &mut status_line)?;
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or("Invalid HTTP response: cannot parse status code")?;
+
+    let mut chunked = false;
+    loop {
+        let mut line = String::new();
+        read_line(&mut reader, &mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("transfer-encoding")
+                && value.trim().eq_ignore_ascii_case("chunked")
+            {
+                chunked = true;
+            }
+        }
+    }
+    if status != 200 {
+        return Err(format!("HTTP {status}"));
+    }
+
+    let reader: Box<dyn BufRead + Send> = if chunked {
+        Box::new(BufReader::new(Chunked::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+    Ok(EventStream { reader, socket })
+}
+
+impl EventStream {
+    /// A handle that can shut the connection down, ending `read_events`.
+    pub(crate) fn closer(&self) -> Result<DaemonStream, String> {
+        self.socket
+            .try_clone()
+            .map_err(|e| format!("Failed to clone connection: {e}"))
+    }
+
+    /// Parse server-sent events until the stream ends, passing each event's
+    /// name, ID and data to `on_event`.
+    pub(crate) fn read_events(
+        mut self,
+        on_event: impl FnMut(&str, Option<u64>, &str),
+    ) -> Result<(), String> {
+        read_sse(&mut self.reader, on_event)
+    }
+}
+
+fn read_sse(
+    reader: &mut impl BufRead,
+    mut on_event: impl FnMut(&str, Option<u64>, &str),
+) -> Result<(), String> {
+    let mut event_name = String::new();
+    let mut id: Option<u64> = None;
+    let mut data = String::new();
+    loop {
+        let mut line = String::new();
+        if read_line(reader, &mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if !data.is_empty() {
+                on_event(&event_name, id, &data);
+            }
+            event_name.clear();
+            id = None;
+            data.clear();
+            continue;
+        }
+        if line.starts_with(':') {
+            continue; // Keep-alive comment
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event_name = value.to_string(),
+            "id" => id = value.parse().ok(),
+            "data" => {
+                if !data.is_empty() {
+                    data.push('\n');
+                }
+                data.push_str(value);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handle_event(
+    app: &AppHandle,
+    event_name: &str,
+    id: Option<u64>,
+    data: &str,
+    cursor: &mut Cursor,
+    backoff: &mut Duration,
+) {
+    let json: serde_json::Value = match serde_json::from_str(data) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Invalid daemon event data: {e}");
+            return;
+        }
+    };
+
+    if event_name == "hello" {
+        let boot_id = json
+            .get("bootId")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        if boot_id != cursor.boot_id {
+            // The daemon restarted and replays its whole buffer
+            cursor.boot_id = boot_id;
+            cursor.last_seq = 0;
+        }
+        *backoff = MIN_BACKOFF;
+        log::info!("Subscribed to daemon events");
+        return;
+    }
+
+    if let Some(seq) = id {
+        if seq <= cursor.last_seq {
+            return;
+        }
+        cursor.last_seq = seq;
+    }
+    let sequence = daemon_events::sequence(&json);
+    match DaemonPushEvent::parse(json) {
+        Ok(event) => daemon_events::dispatch(app, event, sequence),
+        Err(e) => log::warn!("Ignoring daemon event: {e}"),
+    }
+}
+
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize, String> {
+    reader
+        .read_line(line)
+        .map_err(|e| format!("Failed to read stream: {e}"))
+}
+
+/// Decodes a chunked transfer-encoded body as it arrives.
+struct Chunked<R> {
+    inner: R,
+    remaining: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Chunked<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Chunked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        while self.remaining == 0 {
+            let mut size_line = String::new();
+            if self.inner.read_line(&mut size_line)? == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+            // Skip the CRLF that ends the previous chunk
+            let size = size_line.trim();
+            if size.is_empty() {
+                continue;
+            }
+            let size = size.split(';').next().unwrap_or_default();
+            let size = usize::from_str_radix(size, 16).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid chunk size")
+            })?;
+            if size == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+            self.remaining = size;
+        }
+        let len = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..len])?;
+        if n == 0 {
+            self.done = true;
+        }
+        self.remaining -= n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Event = (String, Option<u64>, String);
+
+    fn events(stream: &str) -> Vec<Event> {
+        let mut events = Vec::new();
+        read_sse(&mut stream.as_bytes(), |name, id, data| {
+            events.push((name.to_string(), id, data.to_string()))
+        })
+        .unwrap();
+        events
+    }
+
+    fn event(name: &str, id: Option<u64>, data: &str) -> Event {
+        (name.to_string(), id, data.to_string())
+    }
+
+    fn decode(body: &str) -> std::io::Result<String> {
+        let mut out = String::new();
+        Chunked::new(body.as_bytes()).read_to_string(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn parses_named_and_numbered_events() {
+        let stream = "event: hello\ndata: {\"bootId\":\"b\"}\n\nid: 3\ndata: {}\n\n";
+        assert_eq!(
+            events(stream),
+            [
+                event("hello", None, r#"{"bootId":"b"}"#),
+                event("", Some(3), "{}")
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_data_lines_and_accepts_crlf() {
+        let stream = "data: one\r\ndata:two\r\n\r\n";
+        assert_eq!(events(stream), [event("", None, "one\ntwo")]);
+    }
+
+    #[test]
+    fn skips_comments_unknown_fields_and_empty_events() {
+        let stream = ": ping\n\nretry: 100\nevent: nothing\n\nfoo: bar\nid: x\ndata: {}\n\n";
+        // Fields reset after each blank line; a bad ID is dropped
+        assert_eq!(events(stream), [event("", None, "{}")]);
+    }
+
+    #[test]
+    fn an_unterminated_event_is_not_delivered() {
+        assert_eq!(events("id: 1\ndata: {}\n"), []);
+    }
+
+    #[test]
+    fn decodes_chunks_across_boundaries() {
+        let body = "5\r\ndata:\r\n8;ext=1\r\n {}\n\n:p\n\r\n0\r\n\r\n";
+        assert_eq!(decode(body).unwrap(), "data: {}\n\n:p\n");
+    }
+
+    #[test]
+    fn chunked_body_feeds_the_event_parser() {
+        let body = "a\r\nid: 7\ndata\r\n6\r\n: {}\n\n\r\n0\r\n\r\n";
+        let mut reader = BufReader::new(Chunked::new(body.as_bytes()));
+        let mut seen = Vec::new();
+        read_sse(&mut reader, |name, id, data| {
+            seen.push(event(name, id, data))
+        })
+        .unwrap();
+        assert_eq!(seen, [event("", Some(7), "{}")]);
+    }
+
+    #[test]
+    fn chunked_stops_at_eof_or_the_last_chunk() {
+        assert_eq!(decode("3\r\nabc\r\n").unwrap(), "abc");
+        assert_eq!(decode("3\r\nabc\r\n0\r\n\r\n3\r\nxyz\r\n").unwrap(), "abc");
+        assert_eq!(decode("").unwrap(), "");
+    }
+
+    #[test]
+    fn chunked_rejects_bad_sizes() {
+        let err = decode("zz\r\nabc\r\n").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
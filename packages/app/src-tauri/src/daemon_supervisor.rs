@@ -0,0 +1,338 @@
+use crate::config;
+use crate::daemon::HealthResponse;
+use crate::daemon_client::{DaemonError, DaemonHandle};
+use crate::project::AppStateMutex;
+use crate::pty_manager::{self, PtyManagerMutex};
+use crate::state::DaemonSettings;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often the daemon's health is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// At most this many supervisor restarts per window; beyond that the daemon
+/// is assumed to be crash-looping and left down.
+const MAX_RESTARTS: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DaemonState {
+    /// Not checked yet
+    Unknown,
+    Up,
+    Down,
+    /// The supervisor is starting it again
+    Restarting,
+}
+
+/// Payload of `daemon-status-changed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonStatus {
+    pub state: DaemonState,
+    pub pid: Option<u64>,
+    /// Seconds since the daemon started
+    pub uptime_secs: Option<u64>,
+    /// Most recent health check or restart failure
+    pub last_error: Option<String>,
+    /// Restarts made by the supervisor since the app started
+    pub restarts: u32,
+}
+
+/// Watches the daemon's `/health`, reports changes to the UI and, when
+/// `keep_alive` is on, restarts it while sessions have channels attached.
+pub struct DaemonSupervisor {
+    status: Mutex<DaemonStatus>,
+    /// Daemon start time (ms since epoch, from /health)
+    started_at: Mutex<Option<f64>>,
+    recent_restarts: Mutex<RestartWindow>,
+}
+
+/// Supervisor restarts within the last `RESTART_WINDOW`.
+#[derive(Default)]
+struct RestartWindow {
+    restarts: VecDeque<Instant>,
+}
+
+impl RestartWindow {
+    /// Record a restart at `now`, unless `MAX_RESTARTS` were already made
+    /// within the window.
+    fn try_record(&mut self, now: Instant) -> bool {
+        while self
+            .restarts
+            .front()
+            .is_some_and(|at| now.saturating_duration_since(*at) > RESTART_WINDOW)
+        {
+            self.restarts.pop_front();
+        }
+        if self.restarts.len() >= MAX_RESTARTS {
+            return false;
+        }
+        self.restarts.push_back(now);
+        true
+    }
+}
+
+impl DaemonSupervisor {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(DaemonStatus {
+                state: DaemonState::Unknown,
+                pid: None,
+                uptime_secs: None,
+                last_error: None,
+                restarts: 0,
+            }),
+            started_at: Mutex::new(None),
+            recent_restarts: Mutex::new(RestartWindow::default()),
+        }
+    }
+
+    pub fn status(&self) -> DaemonStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.uptime_secs = self.started_at.lock().unwrap().and_then(|started_at| {
+            let now_ms = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()?
+                .as_millis() as f64;
+            Some(((now_ms - started_at).max(0.0) / 1000.0) as u64)
+        });
+        status
+    }
+
+    fn check(&self, app: &AppHandle) {
+        let handle = app.state::<DaemonHandle>();
+        match handle.passive().get::<HealthResponse>("/health") {
+            Ok(health) => {
+                *self.started_at.lock().unwrap() = health.started_at;
+                self.update(app, |s| {
+                    s.state = DaemonState::Up;
+                    s.pid = health.pid;
+                });
+            }
+            Err(e) => {
+                *self.started_at.lock().unwrap() = None;
+                if should_restart(&e, keep_alive(app), || has_channel_sessions(app)) {
+                    self.restart(app, &handle, &e);
+                    return;
+                }
+                self.update(app, |s| mark_down(s, &e));
+            }
+        }
+    }
+
+    fn restart(&self, app: &AppHandle, handle: &DaemonHandle, reason: &DaemonError) {
+        let allowed = self
+            .recent_restarts
+            .lock()
+            .unwrap()
+            .try_record(Instant::now());
+        if !allowed {
+            self.update(app, |s| {
+                s.state = DaemonState::Down;
+                s.pid = None;
+                s.last_error = Some(format!(
+                    "Daemon stopped {MAX_RESTARTS} times in {} minutes; not restarting it ({reason})",
+                    RESTART_WINDOW.as_secs() / 60
+                ));
+            });
+            return;
+        }
+
+        log::warn!("Daemon is down ({reason}); restarting it");
+        self.update(app, |s| {
+            s.state = DaemonState::Restarting;
+            s.pid = None;
+        });
+        // The start-on-demand client spawns it, so a concurrent command
+        // waits for this start instead of launching a second daemon
+        match handle.api().get::<HealthResponse>("/health") {
+            Ok(health) => {
+                *self.started_at.lock().unwrap() = health.started_at;
+                self.update(app, |s| {
+                    s.state = DaemonState::Up;
+                    s.pid = health.pid;
+                    s.restarts += 1;
+                });
+            }
+            Err(e) => {
+                log::warn!("Daemon restart failed: {e}");
+                self.update(app, |s| {
+                    s.state = DaemonState::Down;
+                    s.last_error = Some(e.to_string());
+                });
+            }
+        }
+    }
+
+    /// Apply `change` and emit `daemon-status-changed` if anything besides
+    /// uptime changed.
+    fn update(&self, app: &AppHandle, change: impl FnOnce(&mut DaemonStatus)) {
+        let changed = apply(&mut self.status.lock().unwrap(), change);
+        if changed {
+            let _ = app.emit("daemon-status-changed", self.status());
+        }
+    }
+}
+
+/// Apply `change`, returning whether anything besides uptime changed.
+fn apply(status: &mut DaemonStatus, change: impl FnOnce(&mut DaemonStatus)) -> bool {
+    let before = (
+        status.state,
+        status.pid,
+        status.last_error.clone(),
+        status.restarts,
+    );
+    change(status);
+    before
+        != (
+            status.state,
+            status.pid,
+            status.last_error.clone(),
+            status.restarts,
+        )
+}
+
+/// Whether a failed health check should restart the daemon. A daemon that
+/// answers slowly or oddly is still running; starting another would fight it
+/// for the socket.
+fn should_restart(
+    error: &DaemonError,
+    keep_alive: bool,
+    has_channel_sessions: impl FnOnce() -> bool,
+) -> bool {
+    matches!(error, DaemonError::NotRunning(_)) && keep_alive && has_channel_sessions()
+}
+
+fn mark_down(status: &mut DaemonStatus, error: &DaemonError) {
+    status.state = DaemonState::Down;
+    status.pid = None;
+    // Not running is the normal idle state, not an error
+    if !matches!(error, DaemonError::NotRunning(_)) {
+        status.last_error = Some(error.to_string());
+    }
+}
+
+fn keep_alive(app: &AppHandle) -> bool {
+    let state = app.state::<AppStateMutex>();
+    let s = state.lock().unwrap();
+    s.daemon.keep_alive
+}
+
+/// Whether any app session was started with a channel and still runs its
+/// CLI session, which reattaches to the daemon once it's back.
+fn has_channel_sessions(app: &AppHandle) -> bool {
+    let session_ids: Vec<String> = {
+        let pty_mgr = app.state::<PtyManagerMutex>();
+        let mgr = pty_mgr.lock().unwrap();
+        mgr.sessions
+            .values()
+            .filter(|s| s.info.channel.is_some())
+            .map(|s| s.info.id.clone())
+            .collect()
+    };
+    session_ids
+        .iter()
+        .any(|id| pty_manager::remote_for_session(app, id).is_some())
+}
+
+/// Start the background thread that checks the daemon's health.
+pub fn start_supervising(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        app.state::<DaemonSupervisor>().check(&app);
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_daemon_status(supervisor: State<'_, DaemonSupervisor>) -> DaemonStatus {
+    supervisor.status()
+}
+
+#[tauri::command]
+pub fn get_daemon_settings(state: State<'_, AppStateMutex>) -> DaemonSettings {
+    let s = state.lock().unwrap();
+    s.daemon.clone()
+}
+
+#[tauri::command]
+pub fn set_daemon_settings(state: State<'_, AppStateMutex>, settings: DaemonSettings) {
+    let mut s = state.lock().unwrap();
+    s.daemon = settings;
+    config::save_state(&s);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> DaemonStatus {
+        DaemonSupervisor::new().status.into_inner().unwrap()
+    }
+
+    #[test]
+    fn restarts_at_most_max_per_window() {
+        let start = Instant::now();
+        let mut window = RestartWindow::default();
+        for i in 0..MAX_RESTARTS as u32 {
+            assert!(window.try_record(start + Duration::from_secs(i.into())));
+        }
+        assert!(!window.try_record(start + Duration::from_secs(60)));
+        // A refused restart doesn't count towards the window
+        assert_eq!(window.restarts.len(), MAX_RESTARTS);
+
+        // Once the first one falls out of the window, one more is allowed
+        let later = start + RESTART_WINDOW + Duration::from_millis(1);
+        assert!(window.try_record(later));
+        assert!(!window.try_record(later));
+    }
+
+    #[test]
+    fn only_restarts_a_daemon_that_is_not_running() {
+        let not_running = DaemonError::NotRunning("connection refused".into());
+        assert!(should_restart(&not_running, true, || true));
+        assert!(!should_restart(&not_running, false, || true));
+        assert!(!should_restart(&not_running, true, || false));
+
+        for error in [
+            DaemonError::Timeout,
+            DaemonError::Protocol("garbage".into()),
+            DaemonError::Unauthorized,
+        ] {
+            assert!(!should_restart(&error, true, || true), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn reports_changes_except_uptime() {
+        let mut status = status();
+        assert!(apply(&mut status, |s| s.state = DaemonState::Up));
+        assert!(!apply(&mut status, |s| s.state = DaemonState::Up));
+        assert!(!apply(&mut status, |s| s.uptime_secs = Some(30)));
+        assert!(apply(&mut status, |s| s.pid = Some(42)));
+        assert!(apply(&mut status, |s| s.restarts += 1));
+        assert!(apply(&mut status, |s| s.last_error = Some("boom".into())));
+    }
+
+    #[test]
+    fn down_records_errors_but_not_a_stopped_daemon() {
+        let mut status = status();
+        status.state = DaemonState::Up;
+        status.pid = Some(42);
+
+        assert!(apply(&mut status, |s| {
+            mark_down(s, &DaemonError::NotRunning("refused".into()))
+        }));
+        assert_eq!(status.state, DaemonState::Down);
+        assert_eq!(status.pid, None);
+        assert_eq!(status.last_error, None);
+
+        mark_down(&mut status, &DaemonError::Timeout);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("Daemon did not respond in time")
+        );
+    }
+}
@@ -0,0 +1,553 @@
+use crate::config;
+use crate::hook_event::HookEvent;
+use crate::project::AppStateMutex;
+use crate::state::EventLogSettings;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+/// Events returned by `query_hook_events` when no limit is given.
+const DEFAULT_QUERY_LIMIT: usize = 500;
+
+/// How often old log files are pruned while the app is running.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Longer strings in a logged event (file contents in Write/Edit input, tool
+/// output) are cut to this many bytes.
+const MAX_LOGGED_STRING: usize = 4 * 1024;
+
+/// A session log past this size is cut back to its newest lines, so a
+/// long-running session can't grow its file (or the cost of reading it)
+/// without bound.
+const MAX_LOG_FILE_BYTES: u64 = 4 * 1024 * 1024;
+/// What is kept when a log is cut back, leaving room for the next couple of
+/// MB of appends before the next rewrite.
+const TRIMMED_LOG_FILE_BYTES: usize = 2 * 1024 * 1024;
+
+/// One line of ~/.touchgrass/events/<session_id>.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    /// Unix epoch milliseconds
+    pub timestamp: u64,
+    pub session_id: String,
+    pub project_id: Option<String>,
+    pub hook_event_name: String,
+    pub tool_name: Option<String>,
+    /// The full hook event as emitted to the UI
+    pub event: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EventQuery {
+    pub session_id: Option<String>,
+    pub project_id: Option<String>,
+    pub hook_event_name: Option<String>,
+    pub tool_name: Option<String>,
+    /// Inclusive lower bound, epoch milliseconds
+    pub since: Option<u64>,
+    /// Exclusive upper bound, epoch milliseconds
+    pub until: Option<u64>,
+    /// Most recent N matches (default 500)
+    pub limit: Option<usize>,
+}
+
+/// Append-only per-session JSONL log of hook events, so the UI can rebuild a
+/// session timeline after a restart.
+pub struct EventLog {
+    write_lock: Mutex<()>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self {
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn append(&self, event: &HookEvent, project_id: Option<String>) {
+        let Some(dir) = events_dir() else { return };
+        let Some(file_name) = log_file_name(&event.session_id) else {
+            return;
+        };
+        let entry = LoggedEvent {
+            timestamp: now_ms(),
+            session_id: event.session_id.clone(),
+            project_id,
+            hook_event_name: event.hook_event_name.clone(),
+            tool_name: event.tool_name.clone(),
+            event: truncate_strings(serde_json::to_value(event).unwrap_or_default()),
+        };
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+
+        let _guard = self.write_lock.lock().unwrap();
+        let _ = std::fs::create_dir_all(&dir);
+        let path = dir.join(file_name);
+        if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_FILE_BYTES) {
+            trim_to_newest(&path, TRIMMED_LOG_FILE_BYTES);
+        }
+        match std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                let _ = writeln!(file, "{line}");
+            }
+            Err(e) => log::warn!("Failed to write event log: {e}"),
+        }
+    }
+
+    /// Delete session logs not written to within the retention window and
+    /// drop older events from the rest.
+    fn prune(&self, retention_days: u32) {
+        let Some(dir) = events_dir() else { return };
+        let _guard = self.write_lock.lock().unwrap();
+        prune_dir(&dir, retention_days);
+    }
+}
+
+fn events_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".touchgrass").join("events"))
+}
+
+/// Session IDs come from the request path, so only allow safe characters.
+fn log_file_name(session_id: &str) -> Option<String> {
+    let safe = !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    safe.then(|| format!("{session_id}.jsonl"))
+}
+
+/// Cut every string in `value` longer than `MAX_LOGGED_STRING`, noting how
+/// much was dropped.
+fn truncate_strings(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::String(s) if s.len() > MAX_LOGGED_STRING => {
+            let mut end = MAX_LOGGED_STRING;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            Value::String(format!(
+                "{}… [{} bytes truncated]",
+                &s[..end],
+                s.len() - end
+            ))
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(truncate_strings).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, truncate_strings(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// The longest run of whole lines at the end of `data` that fits in
+/// `max_bytes`.
+fn newest_lines(data: &str, max_bytes: usize) -> &str {
+    if data.len() <= max_bytes {
+        return data;
+    }
+    let start = data.len() - max_bytes;
+    if data.as_bytes()[start - 1] == b'\n' {
+        return &data[start..];
+    }
+    match data.as_bytes()[start..].iter().position(|&b| b == b'\n') {
+        Some(i) => &data[start + i + 1..],
+        None => "",
+    }
+}
+
+fn trim_to_newest(path: &Path, max_bytes: usize) {
+    let Ok(data) = std::fs::read_to_string(path) else {
+        return;
+    };
+    if let Err(e) = std::fs::write(path, newest_lines(&data, max_bytes)) {
+        log::warn!("Failed to trim event log {}: {e}", path.display());
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn matches(entry: &LoggedEvent, query: &EventQuery) -> bool {
+    if query.project_id.is_some() && entry.project_id != query.project_id {
+        return false;
+    }
+    if query.tool_name.is_some() && entry.tool_name != query.tool_name {
+        return false;
+    }
+    if let Some(name) = &query.hook_event_name {
+        if &entry.hook_event_name != name {
+            return false;
+        }
+    }
+    if query.since.is_some_and(|s| entry.timestamp < s) {
+        return false;
+    }
+    !query.until.is_some_and(|u| entry.timestamp >= u)
+}
+
+fn prune_dir(dir: &Path, retention_days: u32) {
+    if retention_days == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let max_age = Duration::from_secs(u64::from(retention_days) * 24 * 60 * 60);
+    let cutoff_ms = now_ms().saturating_sub(max_age.as_millis() as u64);
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if expired {
+            let _ = std::fs::remove_file(&path);
+        } else {
+            drop_events_before(&path, cutoff_ms);
+        }
+    }
+}
+
+/// Rewrite a log without its events older than `cutoff_ms`. Events are
+/// appended in order, so a log whose first event is recent is left alone.
+fn drop_events_before(path: &Path, cutoff_ms: u64) {
+    let is_recent = |line: &str| {
+        serde_json::from_str::<LoggedEvent>(line).is_ok_and(|e| e.timestamp >= cutoff_ms)
+    };
+    let Ok(file) = std::fs::File::open(path) else {
+        return;
+    };
+    let mut first = String::new();
+    if BufReader::new(file).read_line(&mut first).is_err() || is_recent(&first) {
+        return;
+    }
+    let Ok(data) = std::fs::read_to_string(path) else {
+        return;
+    };
+    let kept: String = data
+        .lines()
+        .filter(|line| is_recent(line))
+        .map(|line| format!("{line}\n"))
+        .collect();
+    if let Err(e) = std::fs::write(path, kept) {
+        log::warn!("Failed to prune event log {}: {e}", path.display());
+    }
+}
+
+/// Prune once at startup and then periodically in the background.
+pub fn start_retention(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let retention_days = {
+            let state = app.state::<AppStateMutex>();
+            let s = state.lock().unwrap();
+            s.event_log.retention_days
+        };
+        app.state::<EventLog>().prune(retention_days);
+        std::thread::sleep(PRUNE_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn query_hook_events(query: EventQuery) -> Vec<LoggedEvent> {
+    match events_dir() {
+        Some(dir) => query_dir(&dir, &query),
+        None => Vec::new(),
+    }
+}
+
+fn query_dir(dir: &Path, query: &EventQuery) -> Vec<LoggedEvent> {
+    let files: Vec<PathBuf> = match &query.session_id {
+        Some(id) => log_file_name(id).map(|f| dir.join(f)).into_iter().collect(),
+        None => std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("jsonl"))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let mut results: Vec<LoggedEvent> = Vec::new();
+    for path in files {
+        // Skip files last written before the requested range
+        if let Some(since) = query.since {
+            let modified_ms = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64);
+            if modified_ms.is_some_and(|m| m < since) {
+                continue;
+            }
+        }
+        let Ok(data) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        results.extend(
+            data.lines()
+                .filter_map(|line| serde_json::from_str::<LoggedEvent>(line).ok())
+                .filter(|entry| matches(entry, query)),
+        );
+    }
+
+    results.sort_by_key(|e| e.timestamp);
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    if results.len() > limit {
+        results.drain(..results.len() - limit);
+    }
+    results
+}
+
+#[tauri::command]
+pub fn get_event_log_settings(state: State<'_, AppStateMutex>) -> EventLogSettings {
+    let s = state.lock().unwrap();
+    s.event_log.clone()
+}
+
+#[tauri::command]
+pub fn set_event_log_settings(
+    state: State<'_, AppStateMutex>,
+    event_log: State<'_, EventLog>,
+    settings: EventLogSettings,
+) -> Result<(), String> {
+    let retention_days = settings.retention_days;
+    {
+        let mut s = state.lock().unwrap();
+        s.event_log = settings;
+        config::save_state(&s);
+    }
+    event_log.prune(retention_days);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(session_id: &str, timestamp: u64, name: &str, tool: Option<&str>) -> LoggedEvent {
+        LoggedEvent {
+            timestamp,
+            session_id: session_id.into(),
+            project_id: Some("p1".into()),
+            hook_event_name: name.into(),
+            tool_name: tool.map(String::from),
+            event: json!({}),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tg-events-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_log(dir: &Path, session_id: &str, entries: &[LoggedEvent]) -> PathBuf {
+        let path = dir.join(log_file_name(session_id).unwrap());
+        let lines: Vec<String> = entries
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect();
+        std::fs::write(&path, lines.join("\n") + "\nnot json\n").unwrap();
+        path
+    }
+
+    #[test]
+    fn only_safe_session_ids_name_files() {
+        assert_eq!(
+            log_file_name("abc-123_x").as_deref(),
+            Some("abc-123_x.jsonl")
+        );
+        assert_eq!(log_file_name(""), None);
+        assert_eq!(log_file_name("../etc/passwd"), None);
+        assert_eq!(log_file_name("a/b"), None);
+        assert_eq!(log_file_name("a.b"), None);
+    }
+
+    #[test]
+    fn filters_by_every_query_field() {
+        let e = entry("s1", 1_000, "PreToolUse", Some("Bash"));
+        assert!(matches(&e, &EventQuery::default()));
+        let q = |f: fn(&mut EventQuery)| {
+            let mut q = EventQuery::default();
+            f(&mut q);
+            q
+        };
+        assert!(matches(&e, &q(|q| q.tool_name = Some("Bash".into()))));
+        assert!(!matches(&e, &q(|q| q.tool_name = Some("Edit".into()))));
+        assert!(!matches(&e, &q(|q| q.project_id = Some("p2".into()))));
+        assert!(!matches(
+            &e,
+            &q(|q| q.hook_event_name = Some("Stop".into()))
+        ));
+        assert!(matches(&e, &q(|q| q.since = Some(1_000))));
+        assert!(!matches(&e, &q(|q| q.since = Some(1_001))));
+        assert!(!matches(&e, &q(|q| q.until = Some(1_000))));
+        assert!(matches(&e, &q(|q| q.until = Some(1_001))));
+    }
+
+    #[test]
+    fn truncates_long_strings_on_char_boundaries() {
+        // The limit falls inside a two-byte character
+        let long = format!("x{}", "é".repeat(MAX_LOGGED_STRING));
+        let value = truncate_strings(json!({
+            "tool_input": { "content": long, "file_path": "/a.rs" },
+            "list": [long, 7, null],
+        }));
+        let content = value["tool_input"]["content"].as_str().unwrap();
+        assert!(content.len() < MAX_LOGGED_STRING + 64);
+        assert!(content.ends_with(&format!(
+            "… [{} bytes truncated]",
+            long.len() - (MAX_LOGGED_STRING - 1)
+        )));
+        assert_eq!(value["tool_input"]["file_path"], "/a.rs");
+        assert_eq!(value["list"][0], value["tool_input"]["content"]);
+        assert_eq!(value["list"][1], 7);
+
+        let short = "x".repeat(MAX_LOGGED_STRING);
+        assert_eq!(truncate_strings(json!(short)), json!(short));
+    }
+
+    #[test]
+    fn queries_across_sessions_newest_last_with_limit() {
+        let dir = temp_dir("query");
+        write_log(
+            &dir,
+            "s1",
+            &[
+                entry("s1", 10, "PreToolUse", Some("Bash")),
+                entry("s1", 30, "Stop", None),
+            ],
+        );
+        write_log(&dir, "s2", &[entry("s2", 20, "PreToolUse", Some("Edit"))]);
+
+        let all = query_dir(&dir, &EventQuery::default());
+        let times: Vec<u64> = all.iter().map(|e| e.timestamp).collect();
+        assert_eq!(times, [10, 20, 30]);
+
+        let query = EventQuery {
+            limit: Some(2),
+            ..EventQuery::default()
+        };
+        let times: Vec<u64> = query_dir(&dir, &query)
+            .iter()
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(times, [20, 30]);
+
+        let query = EventQuery {
+            session_id: Some("s1".into()),
+            hook_event_name: Some("PreToolUse".into()),
+            ..EventQuery::default()
+        };
+        let found = query_dir(&dir, &query);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tool_name.as_deref(), Some("Bash"));
+
+        let query = EventQuery {
+            session_id: Some("../s1".into()),
+            ..EventQuery::default()
+        };
+        assert!(query_dir(&dir, &query).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prunes_only_expired_logs() {
+        let dir = temp_dir("prune");
+        let old = write_log(&dir, "old", &[]);
+        let fresh = write_log(&dir, "fresh", &[]);
+        let other = dir.join("notes.txt");
+        std::fs::write(&other, "keep").unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60);
+        for path in [&old, &other] {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(long_ago)
+                .unwrap();
+        }
+
+        prune_dir(&dir, 0);
+        assert!(old.exists());
+        prune_dir(&dir, 2);
+        assert!(!old.exists());
+        assert!(fresh.exists());
+        assert!(other.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prunes_expired_events_from_live_logs() {
+        let dir = temp_dir("prune-lines");
+        let day_ms = 24 * 60 * 60 * 1000;
+        let now = now_ms();
+        let live = write_log(
+            &dir,
+            "live",
+            &[
+                entry("live", now - 3 * day_ms, "SessionStart", None),
+                entry("live", now - day_ms, "PreToolUse", Some("Bash")),
+                entry("live", now, "Stop", None),
+            ],
+        );
+        let recent = write_log(&dir, "recent", &[entry("recent", now, "Stop", None)]);
+        let untouched = std::fs::read_to_string(&recent).unwrap();
+
+        prune_dir(&dir, 2);
+        let times: Vec<u64> = query_dir(&dir, &EventQuery::default())
+            .iter()
+            .filter(|e| e.session_id == "live")
+            .map(|e| e.timestamp)
+            .collect();
+        assert_eq!(times, [now - day_ms, now]);
+        assert_eq!(std::fs::read_to_string(&live).unwrap().lines().count(), 2);
+        // A log that starts inside the window isn't rewritten
+        assert_eq!(std::fs::read_to_string(&recent).unwrap(), untouched);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn oversized_logs_keep_their_newest_whole_lines() {
+        let data = "first\nsecond\nthird\n";
+        assert_eq!(newest_lines(data, 100), data);
+        assert_eq!(newest_lines(data, data.len()), data);
+        // Cut inside "second": it is dropped whole
+        assert_eq!(newest_lines(data, 10), "third\n");
+        // Cut right after a newline
+        assert_eq!(newest_lines(data, 13), "second\nthird\n");
+        assert_eq!(newest_lines(data, 3), "");
+        assert_eq!(newest_lines("é\né\n", 4), "é\n");
+
+        let dir = temp_dir("trim");
+        let path = dir.join("s.jsonl");
+        std::fs::write(&path, data).unwrap();
+        trim_to_newest(&path, 10);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
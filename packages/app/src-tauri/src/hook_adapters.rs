@@ -0,0 +1,308 @@
+use crate::claude_hooks;
+use crate::hook_event::HookSource;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// Codex `notify` program, kept in sync with this build.
+const CODEX_NOTIFY_SCRIPT: &str = include_str!("../../../cli/src/hooks/codex-notify.sh");
+
+/// Map a hook body from `source` onto the Claude Code hook event model, so
+/// state tracking, notifications and the event log treat every tool alike.
+/// Fields the model doesn't know are kept as they are.
+pub fn to_claude(source: HookSource, json: Value) -> Value {
+    match source {
+        HookSource::Claude => json,
+        HookSource::Codex => from_codex(json),
+        HookSource::Gemini => from_gemini(json),
+    }
+}
+
+/// Codex runs its `notify` program with e.g.
+/// `{"type": "agent-turn-complete", "thread-id": "...", "cwd": "...",
+///   "input-messages": [...], "last-assistant-message": "..."}`.
+fn from_codex(json: Value) -> Value {
+    let Value::Object(mut body) = json else {
+        return json;
+    };
+    let kind = body
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let name = match kind.as_str() {
+        "agent-turn-complete" => {
+            body.insert("stop_hook_active".into(), json!(false));
+            "Stop".to_string()
+        }
+        // Unknown notification types are logged under their own name
+        _ => kind,
+    };
+    body.insert("hook_event_name".into(), json!(name));
+    if let Some(thread_id) = body.get("thread-id").cloned() {
+        body.insert("session_id".into(), thread_id);
+    }
+    Value::Object(body)
+}
+
+/// Gemini CLI hooks already send `hook_event_name`, `session_id`, `cwd` and
+/// Claude-style tool fields; only the event names differ.
+fn from_gemini(json: Value) -> Value {
+    let Value::Object(mut body) = json else {
+        return json;
+    };
+    let name = body
+        .get("hook_event_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let mapped = match name {
+        "BeforeAgent" => "UserPromptSubmit",
+        "BeforeTool" => "PreToolUse",
+        "AfterTool" => "PostToolUse",
+        "AfterAgent" => "Stop",
+        "PreCompress" => "PreCompact",
+        // SessionStart and Notification share Claude's names
+        other => other,
+    }
+    .to_string();
+    body.insert("hook_event_name".into(), json!(mapped));
+
+    if body.get("notification_type").and_then(|v| v.as_str()) == Some("ToolPermission") {
+        body.insert("notification_type".into(), json!("permission_prompt"));
+    }
+    Value::Object(body)
+}
+
+/// Gemini tool call states that haven't produced a result yet.
+const GEMINI_PENDING_STATUSES: &[&str] =
+    &["validating", "scheduled", "awaiting_approval", "executing"];
+
+/// Read position in a Gemini chat log (`~/.gemini/tmp/<hash>/chats/
+/// session-*.json`), for sessions running without touchgrass's Gemini hooks.
+/// The log is rewritten whole, so progress is tracked by message count and
+/// tool call ID.
+#[derive(Debug, Default)]
+pub struct GeminiLogCursor {
+    messages: usize,
+    tool_calls: HashSet<String>,
+    /// A prompt was seen and no `AfterAgent` has been produced for it yet
+    turn_open: bool,
+}
+
+impl GeminiLogCursor {
+    /// Start after everything already in `log`, e.g. when resuming.
+    pub fn at_end(log: &Value) -> Self {
+        let mut cursor = Self::default();
+        cursor.advance(log, false);
+        cursor.turn_open = false;
+        cursor
+    }
+
+    /// Gemini hook bodies for what was added to `log` since the last call.
+    /// `settled` means the file hasn't changed since it was last read, so a
+    /// turn that ends in a model reply is over.
+    pub fn advance(&mut self, log: &Value, settled: bool) -> Vec<Value> {
+        let Some(messages) = log.get("messages").and_then(|m| m.as_array()) else {
+            return Vec::new();
+        };
+        let session_id = log.get("sessionId").cloned().unwrap_or(Value::Null);
+        let event = |name: &str, mut fields: Value| {
+            fields["hook_event_name"] = json!(name);
+            fields["session_id"] = session_id.clone();
+            fields
+        };
+
+        let mut events = Vec::new();
+        for (i, message) in messages.iter().enumerate() {
+            let kind = message.get("type").and_then(|t| t.as_str());
+            if i >= self.messages && kind == Some("user") {
+                let prompt = gemini_text(message.get("content"));
+                events.push(event("BeforeAgent", json!({ "prompt": prompt })));
+                self.turn_open = true;
+            }
+            // Calls are added to a reply as they finish, so older replies are
+            // checked again
+            let calls = message.get("toolCalls").and_then(|c| c.as_array());
+            for call in calls.into_iter().flatten() {
+                let status = call.get("status").and_then(|s| s.as_str()).unwrap_or("");
+                let Some(id) = call.get("id").and_then(|id| id.as_str()) else {
+                    continue;
+                };
+                if GEMINI_PENDING_STATUSES.contains(&status) || self.tool_calls.contains(id) {
+                    continue;
+                }
+                self.tool_calls.insert(id.to_string());
+                events.push(event(
+                    "AfterTool",
+                    json!({
+                        "tool_name": call.get("name").cloned().unwrap_or(Value::Null),
+                        "tool_input": call.get("args").cloned().unwrap_or(Value::Null),
+                        "tool_response": call.get("result").cloned().unwrap_or(Value::Null),
+                        "tool_use_id": id,
+                    }),
+                ));
+            }
+        }
+        self.messages = messages.len();
+
+        let replied = messages
+            .last()
+            .and_then(|m| m.get("type"))
+            .and_then(|t| t.as_str())
+            == Some("gemini");
+        if settled && self.turn_open && replied {
+            events.push(event("AfterAgent", json!({})));
+            self.turn_open = false;
+        }
+        events
+    }
+}
+
+/// Message content is a string or a list of `{"text": ...}` parts.
+fn gemini_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Extra arguments for a Codex command so it reports finished turns to the
+/// app, e.g. ` -c 'notify=["/home/me/.touchgrass/hooks/codex-notify.sh"]'`.
+/// Empty when the user has their own `notify` program, which `-c` would
+/// replace.
+pub fn codex_notify_args() -> String {
+    if user_has_codex_notify() {
+        log::debug!("Codex notify already configured; not adding touchgrass's");
+        return String::new();
+    }
+    let path = match claude_hooks::ensure_script("codex-notify.sh", CODEX_NOTIFY_SCRIPT) {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(e) => {
+            log::warn!("Codex notify script unavailable: {e}");
+            return String::new();
+        }
+    };
+    // The argument is single-quoted for the shell and TOML-quoted inside
+    if path.contains(['\'', '"', '\\']) {
+        return String::new();
+    }
+    format!(" -c 'notify=[\"{path}\"]'")
+}
+
+/// Whether ~/.codex/config.toml sets a top-level `notify` key.
+fn user_has_codex_notify() -> bool {
+    let Some(path) = dirs::home_dir().map(|h| h.join(".codex").join("config.toml")) else {
+        return false;
+    };
+    let Ok(config) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    config
+        .lines()
+        .map(str::trim)
+        // Top-level keys come before the first table header
+        .take_while(|line| !line.starts_with('['))
+        .any(|line| {
+            line.strip_prefix("notify")
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gemini_log(messages: Value) -> Value {
+        json!({ "sessionId": "g-1", "projectHash": "abc", "messages": messages })
+    }
+
+    fn names(events: &[Value]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|e| e["hook_event_name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn maps_gemini_event_names() {
+        let body = to_claude(
+            HookSource::Gemini,
+            json!({ "hook_event_name": "AfterAgent", "session_id": "g-1" }),
+        );
+        assert_eq!(body["hook_event_name"], "Stop");
+        let body = to_claude(
+            HookSource::Gemini,
+            json!({ "hook_event_name": "Notification", "notification_type": "ToolPermission" }),
+        );
+        assert_eq!(body["notification_type"], "permission_prompt");
+    }
+
+    #[test]
+    fn maps_codex_turn_complete_to_stop() {
+        let body = to_claude(
+            HookSource::Codex,
+            json!({ "type": "agent-turn-complete", "thread-id": "t-1" }),
+        );
+        assert_eq!(body["hook_event_name"], "Stop");
+        assert_eq!(body["session_id"], "t-1");
+        assert_eq!(body["stop_hook_active"], false);
+    }
+
+    #[test]
+    fn gemini_log_turns_become_hook_events() {
+        let mut cursor = GeminiLogCursor::default();
+        let prompt = json!({ "id": "1", "type": "user", "content": [{ "text": "fix it" }] });
+        let events = cursor.advance(&gemini_log(json!([prompt])), false);
+        assert_eq!(names(&events), ["BeforeAgent"]);
+        assert_eq!(events[0]["prompt"], "fix it");
+        assert_eq!(events[0]["session_id"], "g-1");
+
+        let reply = |status: &str| {
+            json!({
+                "id": "2",
+                "type": "gemini",
+                "content": "done",
+                "toolCalls": [{ "id": "call-1", "name": "run_shell_command",
+                                "args": { "command": "ls" }, "status": status }],
+            })
+        };
+        let log = gemini_log(json!([prompt, reply("executing")]));
+        assert!(cursor.advance(&log, false).is_empty());
+
+        let log = gemini_log(json!([prompt, reply("success")]));
+        let events = cursor.advance(&log, false);
+        assert_eq!(names(&events), ["AfterTool"]);
+        assert_eq!(events[0]["tool_name"], "run_shell_command");
+        assert_eq!(events[0]["tool_input"]["command"], "ls");
+
+        // The turn ends once the file stops changing, and only once
+        assert_eq!(names(&cursor.advance(&log, true)), ["AfterAgent"]);
+        assert!(cursor.advance(&log, true).is_empty());
+    }
+
+    #[test]
+    fn gemini_log_cursor_can_start_at_the_end() {
+        let log = gemini_log(json!([
+            { "id": "1", "type": "user", "content": "old" },
+            { "id": "2", "type": "gemini", "content": "reply",
+              "toolCalls": [{ "id": "c", "name": "read_file", "status": "success" }] },
+        ]));
+        let mut cursor = GeminiLogCursor::at_end(&log);
+        assert!(cursor.advance(&log, true).is_empty());
+    }
+
+    #[test]
+    fn gemini_log_events_parse_as_hook_events() {
+        let mut cursor = GeminiLogCursor::default();
+        let log = gemini_log(json!([{ "id": "1", "type": "user", "content": "hi" }]));
+        let body = cursor.advance(&log, false).remove(0);
+        let event =
+            crate::hook_event::HookEvent::parse(HookSource::Gemini, "s1".into(), body).unwrap();
+        assert_eq!(event.hook_event_name, "UserPromptSubmit");
+        assert_eq!(event.tool_session_id.as_deref(), Some("g-1"));
+    }
+}
@@ -0,0 +1,338 @@
+use crate::hook_adapters;
+use serde::{Deserialize, Serialize};
+
+/// Which tool sent a hook event. Codex and Gemini events are mapped onto the
+/// Claude Code event model by `hook_adapters`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookSource {
+    #[default]
+    Claude,
+    Codex,
+    Gemini,
+}
+
+impl HookSource {
+    /// From the `x-touchgrass-source` header; absent means Claude.
+    pub fn from_header(value: Option<&str>) -> Result<Self, String> {
+        match value {
+            None | Some("claude") => Ok(HookSource::Claude),
+            Some("codex") => Ok(HookSource::Codex),
+            Some("gemini") => Ok(HookSource::Gemini),
+            Some(other) => Err(format!("unknown hook source {other}")),
+        }
+    }
+
+    /// The `x-touchgrass-source` header value for this source.
+    pub fn header_value(self) -> &'static str {
+        match self {
+            HookSource::Claude => "claude",
+            HookSource::Codex => "codex",
+            HookSource::Gemini => "gemini",
+        }
+    }
+
+    /// Whether the tool reports the whole turn (prompt, tools, stop), so its
+    /// events alone can drive the session state. Codex only reports turn ends.
+    pub fn reports_lifecycle(self) -> bool {
+        self != HookSource::Codex
+    }
+}
+
+/// A Claude Code hook event as received on `/hook/{sessionId}`, or a Codex or
+/// Gemini event mapped onto the same model.
+///
+/// The flat `tool_name`/`tool_input`/`claude_session_id` fields are kept for
+/// existing listeners; `payload` carries the typed, per-event fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookEvent {
+    /// touchgrass app session ID
+    pub session_id: String,
+    pub source: HookSource,
+    pub hook_event_name: String,
+    pub tool_name: Option<String>,
+    pub tool_input: Option<serde_json::Value>,
+    /// Claude Code's own session ID (for --resume)
+    pub claude_session_id: Option<String>,
+    /// The sending tool's own session ID, whichever tool it is
+    pub tool_session_id: Option<String>,
+    pub transcript_path: Option<String>,
+    pub cwd: Option<String>,
+    pub permission_mode: Option<String>,
+    pub payload: HookPayload,
+}
+
+/// Per-event fields. Events this build doesn't know (or that fail to parse)
+/// are kept as the raw JSON body in `Unknown`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum HookPayload {
+    SessionStart(SessionStart),
+    UserPromptSubmit(UserPromptSubmit),
+    PreToolUse(ToolUse),
+    PermissionRequest(ToolUse),
+    PostToolUse(PostToolUse),
+    Notification(Notification),
+    Stop(Stop),
+    SubagentStop(Stop),
+    PreCompact(PreCompact),
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStart {
+    /// "startup" | "resume" | "clear" | "compact"
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPromptSubmit {
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolUse {
+    pub tool_name: String,
+    #[serde(default)]
+    pub tool_input: serde_json::Value,
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostToolUse {
+    pub tool_name: String,
+    #[serde(default)]
+    pub tool_input: serde_json::Value,
+    #[serde(default)]
+    pub tool_response: serde_json::Value,
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub message: String,
+    #[serde(default)]
+    pub notification_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stop {
+    #[serde(default)]
+    pub stop_hook_active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreCompact {
+    /// "manual" | "auto"
+    #[serde(default)]
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub custom_instructions: Option<String>,
+}
+
+impl HookEvent {
+    /// Parse a hook body sent by `source`, mapping Codex and Gemini bodies
+    /// onto Claude's model first. Only `hook_event_name` is required.
+    pub fn parse(
+        source: HookSource,
+        session_id: String,
+        json: serde_json::Value,
+    ) -> Result<Self, String> {
+        let json = hook_adapters::to_claude(source, json);
+        let str_field = |name: &str| json.get(name).and_then(|v| v.as_str()).map(String::from);
+
+        let hook_event_name = str_field("hook_event_name")
+            .filter(|n| !n.is_empty())
+            .ok_or("missing hook_event_name")?;
+
+        let payload = parse_payload(&hook_event_name, &json);
+
+        let tool_session_id = str_field("session_id");
+        Ok(HookEvent {
+            session_id,
+            source,
+            tool_name: str_field("tool_name"),
+            tool_input: json.get("tool_input").cloned(),
+            claude_session_id: tool_session_id.clone().filter(|_| source == HookSource::Claude),
+            tool_session_id,
+            transcript_path: str_field("transcript_path"),
+            cwd: str_field("cwd"),
+            permission_mode: str_field("permission_mode"),
+            hook_event_name,
+            payload,
+        })
+    }
+
+    /// Per-event Tauri event name, e.g. "hook-pre-tool-use".
+    pub fn typed_event_name(&self) -> String {
+        let mut name = String::from("hook");
+        for c in self.hook_event_name.chars() {
+            if c.is_ascii_uppercase() {
+                name.push('-');
+                name.push(c.to_ascii_lowercase());
+            } else if c.is_ascii_alphanumeric() {
+                name.push(c);
+            }
+        }
+        name
+    }
+}
+
+fn parse_payload(hook_event_name: &str, json: &serde_json::Value) -> HookPayload {
+    fn typed<T: serde::de::DeserializeOwned>(
+        json: &serde_json::Value,
+        wrap: fn(T) -> HookPayload,
+    ) -> HookPayload {
+        match serde_json::from_value::<T>(json.clone()) {
+            Ok(v) => wrap(v),
+            Err(e) => {
+                log::debug!("Hook payload did not match its schema: {e}");
+                HookPayload::Unknown(json.clone())
+            }
+        }
+    }
+
+    match hook_event_name {
+        "SessionStart" => typed(json, HookPayload::SessionStart),
+        "UserPromptSubmit" => typed(json, HookPayload::UserPromptSubmit),
+        "PreToolUse" => typed(json, HookPayload::PreToolUse),
+        "PermissionRequest" => typed(json, HookPayload::PermissionRequest),
+        "PostToolUse" => typed(json, HookPayload::PostToolUse),
+        "Notification" => typed(json, HookPayload::Notification),
+        "Stop" => typed(json, HookPayload::Stop),
+        "SubagentStop" => typed(json, HookPayload::SubagentStop),
+        "PreCompact" => typed(json, HookPayload::PreCompact),
+        _ => HookPayload::Unknown(json.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(json: serde_json::Value) -> Result<HookEvent, String> {
+        HookEvent::parse(HookSource::Claude, "s1".into(), json)
+    }
+
+    #[test]
+    fn parses_typed_payload_and_flat_fields() {
+        let event = parse(json!({
+            "hook_event_name": "PreToolUse",
+            "session_id": "claude-123",
+            "transcript_path": "/tmp/t.jsonl",
+            "cwd": "/work",
+            "permission_mode": "default",
+            "tool_name": "Bash",
+            "tool_input": { "command": "ls" },
+            "tool_use_id": "toolu_1",
+        }))
+        .unwrap();
+        assert_eq!(event.session_id, "s1");
+        assert_eq!(event.tool_name.as_deref(), Some("Bash"));
+        assert_eq!(event.tool_input, Some(json!({ "command": "ls" })));
+        assert_eq!(event.claude_session_id.as_deref(), Some("claude-123"));
+        assert_eq!(event.tool_session_id.as_deref(), Some("claude-123"));
+        assert_eq!(event.cwd.as_deref(), Some("/work"));
+        let HookPayload::PreToolUse(tool) = &event.payload else {
+            panic!("expected PreToolUse, got {:?}", event.payload);
+        };
+        assert_eq!(tool.tool_use_id.as_deref(), Some("toolu_1"));
+        assert_eq!(event.typed_event_name(), "hook-pre-tool-use");
+    }
+
+    #[test]
+    fn source_header_round_trips() {
+        for source in [HookSource::Claude, HookSource::Codex, HookSource::Gemini] {
+            assert_eq!(
+                HookSource::from_header(Some(source.header_value())),
+                Ok(source)
+            );
+        }
+        assert_eq!(HookSource::from_header(None), Ok(HookSource::Claude));
+        assert!(HookSource::from_header(Some("vim")).is_err());
+    }
+
+    #[test]
+    fn requires_an_event_name() {
+        assert!(parse(json!({ "session_id": "x" })).is_err());
+        assert!(parse(json!({ "hook_event_name": "" })).is_err());
+        assert!(parse(json!({ "hook_event_name": 5 })).is_err());
+        assert!(parse(json!("not an object")).is_err());
+    }
+
+    #[test]
+    fn unknown_events_pass_the_raw_body_through() {
+        let body = json!({
+            "hook_event_name": "FutureEvent",
+            "session_id": "claude-123",
+            "something_new": { "nested": [1, 2] },
+        });
+        let event = parse(body.clone()).unwrap();
+        assert_eq!(event.hook_event_name, "FutureEvent");
+        assert_eq!(event.typed_event_name(), "hook-future-event");
+        assert!(matches!(&event.payload, HookPayload::Unknown(raw) if *raw == body));
+        let serialized = serde_json::to_value(&event).unwrap();
+        assert_eq!(serialized["payload"], body);
+    }
+
+    #[test]
+    fn known_events_that_fail_their_schema_are_kept_raw() {
+        let body = json!({ "hook_event_name": "UserPromptSubmit", "prompt": 42 });
+        let event = parse(body.clone()).unwrap();
+        assert!(matches!(&event.payload, HookPayload::Unknown(raw) if *raw == body));
+    }
+
+    #[test]
+    fn optional_fields_default() {
+        let event = parse(json!({ "hook_event_name": "Stop" })).unwrap();
+        assert!(matches!(
+            event.payload,
+            HookPayload::Stop(Stop {
+                stop_hook_active: false
+            })
+        ));
+        assert_eq!(event.claude_session_id, None);
+        assert_eq!(event.tool_name, None);
+
+        let event = parse(json!({ "hook_event_name": "SubagentStop" })).unwrap();
+        assert!(matches!(event.payload, HookPayload::SubagentStop(_)));
+        assert_eq!(event.typed_event_name(), "hook-subagent-stop");
+    }
+
+    #[test]
+    fn other_sources_keep_their_session_id_out_of_claude_session_id() {
+        let event = HookEvent::parse(
+            HookSource::Gemini,
+            "s1".into(),
+            json!({
+                "hook_event_name": "BeforeTool",
+                "session_id": "gemini-9",
+                "tool_name": "run_shell_command",
+                "tool_input": { "command": "ls" },
+            }),
+        )
+        .unwrap();
+        assert_eq!(event.hook_event_name, "PreToolUse");
+        assert_eq!(event.claude_session_id, None);
+        assert_eq!(event.tool_session_id.as_deref(), Some("gemini-9"));
+        assert!(matches!(event.payload, HookPayload::PreToolUse(_)));
+    }
+
+    #[test]
+    fn reads_source_header() {
+        assert_eq!(HookSource::from_header(None), Ok(HookSource::Claude));
+        assert_eq!(
+            HookSource::from_header(Some("codex")),
+            Ok(HookSource::Codex)
+        );
+        assert_eq!(
+            HookSource::from_header(Some("gemini")),
+            Ok(HookSource::Gemini)
+        );
+        assert!(HookSource::from_header(Some("cursor")).is_err());
+    }
+}
@@ -1,30 +1,44 @@
-use std::io::{BufRead, BufReader, Read, Write};
+use crate::approvals::{self, ApprovalManager, ApprovalOutcome};
+use crate::daemon_events::{self, DaemonPushEvent};
+use crate::event_log::EventLog;
+use crate::hook_event::{HookEvent, HookPayload, HookSource};
+use crate::http::{self, Connection, Limits, Request, Response, WorkerPool};
+use crate::notifications::{self, NotificationKind};
+use crate::policy;
+use crate::pty_manager;
+use crate::session_state::SessionStates;
+use crate::webhooks;
+use std::io::Write;
 use std::net::TcpListener;
-use tauri::{AppHandle, Emitter};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Connections handled concurrently; the rest wait in the queue. Approval
+/// requests hold a worker until answered, so at most
+/// `approvals::MAX_WAITING` of them do and the rest stay free for other routes.
+const WORKERS: usize = 16;
+const _: () = assert!(approvals::MAX_WAITING < WORKERS);
+/// Accepted connections waiting for a worker before new ones get a 503.
+const QUEUE_LEN: usize = 64;
+/// How long the app may take to answer its own `post_hook`.
+const POST_HOOK_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Lightweight HTTP server that receives Claude Code hook events from the
-/// touchgrass hook script and emits them as Tauri events.
+/// touchgrass hook script (and Codex/Gemini events from their scripts, tagged
+/// with `x-touchgrass-source`) and emits them as Tauri events.
 /// Also receives push events from the daemon (e.g., channel linked).
+/// Listens on ~/.touchgrass/app.sock where Unix sockets are available, with
+/// TCP on localhost as the fallback transport.
 pub struct HookServer {
     pub port: u16,
-}
-
-#[derive(Clone, serde::Serialize)]
-struct HookEvent {
-    session_id: String,
-    hook_event_name: String,
-    tool_name: Option<String>,
-    tool_input: Option<serde_json::Value>,
-    /// Claude Code's own session ID (for --resume)
-    claude_session_id: Option<String>,
-}
-
-#[derive(Clone, serde::Serialize)]
-struct DaemonEvent {
-    event_type: String,
-    title: Option<String>,
-    chat_id: Option<String>,
-    username: Option<String>,
+    /// Per-launch secret required in the `x-touchgrass-auth` header
+    pub secret: String,
+    /// Unix socket path (mode 0600), if the socket listener started
+    pub socket_path: Option<PathBuf>,
 }
 
 impl HookServer {
@@ -36,171 +50,296 @@ impl HookServer {
             .map_err(|e| format!("Addr failed: {e}"))?
             .port();
 
-        // Write app.port so the daemon can push events to us
+        let secret = generate_secret();
+
+        // Write app.port and app.auth so the daemon can push events to us
         if let Some(home) = dirs::home_dir() {
-            let port_path = home.join(".touchgrass").join("app.port");
-            let _ = std::fs::write(&port_path, port.to_string());
+            let dir = home.join(".touchgrass");
+            let _ = std::fs::create_dir_all(&dir);
+            write_secret_file(&dir.join("app.auth"), &secret)?;
+            let _ = std::fs::write(dir.join("app.port"), port.to_string());
         }
 
+        let server_secret = secret.clone();
+        let limits = Limits::default();
+        let pool = Arc::new(WorkerPool::new(
+            WORKERS,
+            QUEUE_LEN,
+            move |stream: Box<dyn Connection>| {
+                http::serve_connection(stream, &limits, |request| {
+                    handle_request(request, &app, &server_secret)
+                });
+            },
+        ));
+
+        let tcp_pool = pool.clone();
         std::thread::spawn(move || {
             for stream in listener.incoming() {
                 let Ok(stream) = stream else { continue };
-                let app = app.clone();
-                std::thread::spawn(move || handle_connection(stream, &app));
+                if let Err(stream) = tcp_pool.try_submit(Box::new(stream)) {
+                    http::reject_busy(stream);
+                }
             }
         });
 
-        Ok(HookServer { port })
+        let socket_path = start_unix_listener(pool);
+
+        Ok(HookServer {
+            port,
+            secret,
+            socket_path,
+        })
+    }
+
+    /// Post an event mapped from a tool's session log to `/hook/{session_id}`,
+    /// so it takes the same route as events from the hook scripts.
+    pub fn post_hook(
+        &self,
+        source: HookSource,
+        session_id: &str,
+        body: &serde_json::Value,
+    ) -> Result<(), String> {
+        let url = format!("http://127.0.0.1:{}/hook/{session_id}", self.port);
+        ureq::post(&url)
+            .timeout(POST_HOOK_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .set("x-touchgrass-auth", &self.secret)
+            .set("x-touchgrass-source", source.header_value())
+            .send_string(&body.to_string())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
     }
 }
 
 impl Drop for HookServer {
     fn drop(&mut self) {
-        // Clean up app.port on shutdown
+        // Clean up app.port, app.auth and app.sock on shutdown
         if let Some(home) = dirs::home_dir() {
-            let port_path = home.join(".touchgrass").join("app.port");
-            let _ = std::fs::remove_file(&port_path);
+            let dir = home.join(".touchgrass");
+            let _ = std::fs::remove_file(dir.join("app.port"));
+            let _ = std::fs::remove_file(dir.join("app.auth"));
+        }
+        if let Some(path) = &self.socket_path {
+            let _ = std::fs::remove_file(path);
         }
     }
 }
 
-fn handle_connection(mut stream: std::net::TcpStream, app: &AppHandle) {
-    let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(5)));
-
-    let mut reader = BufReader::new(&stream);
+/// Bind ~/.touchgrass/app.sock and feed its connections into the shared
+/// worker pool. Returns `None` (TCP only) if the socket cannot be created.
+#[cfg(unix)]
+fn start_unix_listener(pool: Arc<WorkerPool<Box<dyn Connection>>>) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
 
-    // Read request line: POST /hook/{sessionId} HTTP/1.1
-    let mut request_line = String::new();
-    if reader.read_line(&mut request_line).is_err() {
-        return;
+    let dir = dirs::home_dir()?.join(".touchgrass");
+    // Bind inside a directory only we can enter (as the CLI creates it), so
+    // the socket is never reachable by others, even before the chmod below
+    if let Err(e) = std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)))
+    {
+        log::warn!("Hook socket directory unusable ({}): {e}", dir.display());
+        return None;
     }
 
-    let parts: Vec<&str> = request_line.trim().split_whitespace().collect();
-    if parts.len() < 2 || parts[0] != "POST" {
-        let _ = write_response(&mut stream, 405, r#"{"error":"method not allowed"}"#);
-        return;
+    let path = dir.join("app.sock");
+    if path.exists() {
+        // Another running app instance owns a socket that still accepts
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            log::warn!("{} is in use by another instance", path.display());
+            return None;
+        }
+        // Otherwise a previous instance left a stale socket behind
+        let _ = std::fs::remove_file(&path);
     }
 
-    let path = parts[1];
+    let listener = match UnixListener::bind(&path) {
+        Ok(l) => l,
+        Err(e) => {
+            log::warn!("Hook socket bind failed ({}): {e}", path.display());
+            return None;
+        }
+    };
+    if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)) {
+        log::warn!("Hook socket chmod failed: {e}");
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
 
-    // Handle daemon push events (POST /event)
-    if path == "/event" {
-        // Read headers to find Content-Length
-        let mut content_length: usize = 0;
-        loop {
-            let mut line = String::new();
-            if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
-                break;
-            }
-            if let Some(val) = line
-                .to_lowercase()
-                .strip_prefix("content-length:")
-                .map(|v| v.trim().to_string())
-            {
-                content_length = val.parse().unwrap_or(0);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            if let Err(stream) = pool.try_submit(Box::new(stream)) {
+                http::reject_busy(stream);
             }
         }
+    });
 
-        let mut body = vec![0u8; content_length];
-        if content_length > 0 && reader.read_exact(&mut body).is_err() {
-            let _ = write_response(&mut stream, 400, r#"{"error":"bad body"}"#);
-            return;
-        }
+    Some(path)
+}
 
-        let json: serde_json::Value = match serde_json::from_slice(&body) {
-            Ok(v) => v,
-            Err(_) => {
-                let _ = write_response(&mut stream, 400, r#"{"error":"invalid json"}"#);
-                return;
-            }
-        };
+#[cfg(not(unix))]
+fn start_unix_listener(_pool: Arc<WorkerPool<Box<dyn Connection>>>) -> Option<PathBuf> {
+    None
+}
+
+/// 256-bit random hex secret (two v4 UUIDs' worth of OS randomness).
+fn generate_secret() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Write the secret readable only by the current user.
+fn write_secret_file(path: &std::path::Path, secret: &str) -> Result<(), String> {
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    file.write_all(secret.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn handle_request(request: &Request, app: &AppHandle, secret: &str) -> Response {
+    if request.method != "POST" {
+        return Response::error(405, "method not allowed");
+    }
+
+    let authorized = request
+        .header("x-touchgrass-auth")
+        .is_some_and(|a| constant_time_eq(a.as_bytes(), secret.as_bytes()));
+    if !authorized {
+        return Response::error(401, "unauthorized");
+    }
 
-        let event = DaemonEvent {
-            event_type: json.get("type").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            title: json.get("title").and_then(|v| v.as_str()).map(String::from),
-            chat_id: json.get("chatId").and_then(|v| v.as_str()).map(String::from),
-            username: json.get("username").and_then(|v| v.as_str()).map(String::from),
+    // Handle daemon push events (POST /event)
+    if request.path == "/event" {
+        let json: serde_json::Value = match serde_json::from_slice(&request.body) {
+            Ok(v) => v,
+            Err(_) => return Response::error(400, "invalid json"),
         };
 
-        let _ = app.emit("daemon-event", event);
-        let _ = write_response(&mut stream, 200, r#"{"ok":true}"#);
-        return;
+        let sequence = daemon_events::sequence(&json);
+        match DaemonPushEvent::parse(json) {
+            Ok(event) => daemon_events::dispatch(app, event, sequence),
+            Err(e) => return Response::error(400, &e),
+        }
+        return Response::json(200, r#"{"ok":true}"#);
     }
 
-    let session_id = match path.strip_prefix("/hook/") {
+    let session_id = match request.path.strip_prefix("/hook/") {
         Some(id) if !id.is_empty() => id.to_string(),
-        _ => {
-            let _ = write_response(&mut stream, 404, r#"{"error":"not found"}"#);
-            return;
-        }
+        _ => return Response::error(404, "not found"),
     };
 
-    // Read headers to find Content-Length
-    let mut content_length: usize = 0;
-    loop {
-        let mut line = String::new();
-        if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
-            break;
-        }
-        if let Some(val) = line
-            .to_lowercase()
-            .strip_prefix("content-length:")
-            .map(|v| v.trim().to_string())
-        {
-            content_length = val.parse().unwrap_or(0);
-        }
+    let json: serde_json::Value = match serde_json::from_slice(&request.body) {
+        Ok(v) => v,
+        Err(_) => return Response::error(400, "invalid json"),
+    };
+
+    let source = match HookSource::from_header(request.header("x-touchgrass-source")) {
+        Ok(source) => source,
+        Err(e) => return Response::error(400, &e),
+    };
+    let event = match HookEvent::parse(source, session_id, json) {
+        Ok(event) => event,
+        Err(e) => return Response::error(400, &e),
+    };
+
+    ingest(app, &event);
+
+    // Only Claude's hook script waits for a decision
+    if event.source != HookSource::Claude {
+        return Response::json(200, r#"{"ok":true}"#);
     }
 
-    // Read body
-    let mut body = vec![0u8; content_length];
-    if content_length > 0 && reader.read_exact(&mut body).is_err() {
-        let _ = write_response(&mut stream, 400, r#"{"error":"bad body"}"#);
-        return;
+    // Rules decide PreToolUse without a human when one matches
+    if event.hook_event_name == "PreToolUse" {
+        if let Some(tool_name) = event.tool_name.as_deref() {
+            if let Some((decision, reason)) = policy::decide(
+                app,
+                &event.session_id,
+                tool_name,
+                event.tool_input.as_ref(),
+                event.cwd.as_deref(),
+            ) {
+                let outcome = ApprovalOutcome { decision, reason };
+                let body = approvals::hook_decision_json(&event.hook_event_name, &outcome);
+                return Response::json(200, body.to_string());
+            }
+        }
     }
 
-    // Parse JSON
-    let json: serde_json::Value = match serde_json::from_slice(&body) {
-        Ok(v) => v,
-        Err(_) => {
-            let _ = write_response(&mut stream, 400, r#"{"error":"invalid json"}"#);
-            return;
+    // Approval mode: hold the hook open until the user decides in the app
+    if approvals::APPROVAL_EVENTS.contains(&event.hook_event_name.as_str()) {
+        let settings = approvals::current_settings(app);
+        if settings.enabled {
+            let states = app.state::<SessionStates>();
+            states.set_awaiting_permission(app, &event.session_id, true);
+            let outcome = app.state::<ApprovalManager>().wait_for_decision(
+                app,
+                &settings,
+                &event.session_id,
+                &event.hook_event_name,
+                event.tool_name,
+                event.tool_input,
+            );
+            states.set_awaiting_permission(app, &event.session_id, false);
+            let body = approvals::hook_decision_json(&event.hook_event_name, &outcome);
+            return Response::json(200, body.to_string());
         }
-    };
+    }
+
+    Response::json(200, r#"{"ok":true}"#)
+}
 
-    let hook_event_name = json
-        .get("hook_event_name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
+/// Log, track and forward a hook event, the same way for every source.
+fn ingest(app: &AppHandle, event: &HookEvent) {
+    let project_id = policy::project_for_session(app, &event.session_id).map(|(id, _)| id);
+    app.state::<EventLog>().append(event, project_id.clone());
 
-    if hook_event_name.is_empty() {
-        let _ = write_response(&mut stream, 400, r#"{"error":"missing hook_event_name"}"#);
-        return;
+    // Keep the tool's session ID for resume even when no window is listening
+    if let Some(tool_session_id) = event.tool_session_id.as_deref() {
+        pty_manager::record_tool_session_id(app, &event.session_id, tool_session_id);
     }
 
-    let event = HookEvent {
-        session_id,
-        hook_event_name,
-        tool_name: json.get("tool_name").and_then(|v| v.as_str()).map(String::from),
-        tool_input: json.get("tool_input").cloned(),
-        claude_session_id: json.get("session_id").and_then(|v| v.as_str()).map(String::from),
-    };
+    app.state::<SessionStates>().on_hook_event(app, event);
+    match &event.payload {
+        HookPayload::Notification(n) => notifications::notify(
+            app,
+            &event.session_id,
+            NotificationKind::Attention,
+            &n.message,
+        ),
+        HookPayload::Stop(_) => {
+            notifications::notify(app, &event.session_id, NotificationKind::Finished, "")
+        }
+        _ => {}
+    }
 
     let _ = app.emit("hook-event", event);
-    let _ = write_response(&mut stream, 200, r#"{"ok":true}"#);
-}
-
-fn write_response(stream: &mut std::net::TcpStream, status: u16, body: &str) -> std::io::Result<()> {
-    let reason = match status {
-        200 => "OK",
-        400 => "Bad Request",
-        404 => "Not Found",
-        405 => "Method Not Allowed",
-        _ => "Error",
-    };
-    write!(
-        stream,
-        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
-        body.len()
-    )
+    let _ = app.emit(&event.typed_event_name(), event);
+    webhooks::publish(
+        app,
+        "hook",
+        &event.hook_event_name,
+        Some(&event.session_id),
+        project_id.as_deref(),
+        serde_json::to_value(event).unwrap_or_default(),
+    );
 }
@@ -0,0 +1,804 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Minimal HTTP/1.1 server-side layer used by the hook server: bounded request
+/// parsing (Content-Length and chunked bodies), keep-alive, and a fixed-size
+/// worker pool.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub max_request_line: usize,
+    pub max_header_line: usize,
+    pub max_header_count: usize,
+    pub max_header_bytes: usize,
+    pub max_body: usize,
+    /// Requests served on one connection before it is closed
+    pub max_requests_per_connection: usize,
+    /// How long a single read may wait, and how long an idle keep-alive
+    /// connection is held
+    pub read_timeout: Duration,
+    /// How long a request may take to arrive in full, counted from its first
+    /// byte, however steadily it trickles in
+    pub request_timeout: Duration,
+    pub write_timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_request_line: 8 * 1024,
+            max_header_line: 8 * 1024,
+            max_header_count: 64,
+            max_header_bytes: 32 * 1024,
+            // Claude's Write/Edit tool_input can carry whole files
+            max_body: 8 * 1024 * 1024,
+            max_requests_per_connection: 100,
+            read_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(15),
+            write_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    keep_alive: bool,
+}
+
+impl Request {
+    /// Case-insensitive header lookup.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+
+    /// `{"error": message}` with the given status.
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, serde_json::json!({ "error": message }).to_string())
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    /// Connection closed or timed out before a request started
+    Closed,
+    Io(std::io::Error),
+    BadRequest(&'static str),
+    /// 408
+    Timeout,
+    /// 413
+    PayloadTooLarge,
+    /// 414
+    RequestLineTooLong,
+    /// 431
+    HeadersTooLarge,
+    /// 501
+    NotImplemented(&'static str),
+}
+
+impl HttpError {
+    /// Response to send before closing, if the peer can still receive one.
+    fn response(&self) -> Option<Response> {
+        match self {
+            HttpError::Closed | HttpError::Io(_) => None,
+            HttpError::BadRequest(msg) => Some(Response::error(400, msg)),
+            HttpError::Timeout => Some(Response::error(408, "request timeout")),
+            HttpError::PayloadTooLarge => Some(Response::error(413, "payload too large")),
+            HttpError::RequestLineTooLong => Some(Response::error(414, "request line too long")),
+            HttpError::HeadersTooLarge => {
+                Some(Response::error(431, "request header fields too large"))
+            }
+            HttpError::NotImplemented(msg) => Some(Response::error(501, msg)),
+        }
+    }
+}
+
+impl From<std::io::Error> for HttpError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => HttpError::Timeout,
+            _ => HttpError::Io(e),
+        }
+    }
+}
+
+/// Streams the hook server can serve (TCP, and Unix sockets where available).
+pub trait Connection: Read + Write + Send {
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()>;
+}
+
+impl Connection for std::net::TcpStream {
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(read))?;
+        self.set_write_timeout(Some(write))
+    }
+}
+
+#[cfg(unix)]
+impl Connection for std::os::unix::net::UnixStream {
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(read))?;
+        self.set_write_timeout(Some(write))
+    }
+}
+
+impl Connection for Box<dyn Connection> {
+    fn set_timeouts(&self, read: Duration, write: Duration) -> std::io::Result<()> {
+        (**self).set_timeouts(read, write)
+    }
+}
+
+/// Stream whose reads stop at a deadline: each read waits at most the read
+/// timeout and never past the end of the current request's time.
+struct Deadline<S> {
+    stream: S,
+    read_timeout: Duration,
+    write_timeout: Duration,
+    until: Option<Instant>,
+}
+
+impl<S: Connection> Deadline<S> {
+    fn new(stream: S, limits: &Limits) -> Self {
+        let _ = stream.set_timeouts(limits.read_timeout, limits.write_timeout);
+        Self {
+            stream,
+            read_timeout: limits.read_timeout,
+            write_timeout: limits.write_timeout,
+            until: None,
+        }
+    }
+
+    fn set(&mut self, until: Option<Instant>) {
+        if until.is_none() && self.until.is_some() {
+            let _ = self
+                .stream
+                .set_timeouts(self.read_timeout, self.write_timeout);
+        }
+        self.until = until;
+    }
+}
+
+impl<S: Connection> Read for Deadline<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(until) = self.until {
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Err(std::io::ErrorKind::TimedOut.into());
+            }
+            self.stream
+                .set_timeouts(left.min(self.read_timeout), self.write_timeout)?;
+        }
+        self.stream.read(buf)
+    }
+}
+
+impl<S: Connection> Write for Deadline<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Serve requests on `stream` until the peer closes, asks to close, or an
+/// error occurs.
+pub fn serve_connection<S, F>(stream: S, limits: &Limits, handler: F)
+where
+    S: Connection,
+    F: Fn(&Request) -> Response,
+{
+    let mut reader = BufReader::new(Deadline::new(stream, limits));
+
+    for _ in 0..limits.max_requests_per_connection {
+        // Waiting for the next request is bounded by the read timeout alone;
+        // the request deadline starts with its first byte
+        reader.get_mut().set(None);
+        match reader.fill_buf() {
+            Ok(buf) if !buf.is_empty() => {}
+            _ => return,
+        }
+        reader
+            .get_mut()
+            .set(Some(Instant::now() + limits.request_timeout));
+
+        let request = match read_request(&mut reader, limits) {
+            Ok(request) => request,
+            Err(e) => {
+                if let HttpError::Io(err) = &e {
+                    log::debug!("HTTP connection error: {err}");
+                }
+                if let Some(response) = e.response() {
+                    let _ = write_response(reader.get_mut(), &response, false);
+                }
+                return;
+            }
+        };
+
+        let response = handler(&request);
+        if write_response(reader.get_mut(), &response, request.keep_alive).is_err()
+            || !request.keep_alive
+        {
+            return;
+        }
+    }
+}
+
+/// Read one request. Sends `100 Continue` through the underlying stream when
+/// the client asks for it.
+pub fn read_request<S: Read + Write>(
+    reader: &mut BufReader<S>,
+    limits: &Limits,
+) -> Result<Request, HttpError> {
+    // Tolerate stray CRLFs between pipelined requests (RFC 9112 §2.2)
+    let request_line = loop {
+        match read_line(reader, limits.max_request_line) {
+            Ok(Some(line)) if line.is_empty() => continue,
+            Ok(Some(line)) => break line,
+            Ok(None) => return Err(HttpError::Closed),
+            Err(HttpError::Timeout) => return Err(HttpError::Closed),
+            Err(HttpError::HeadersTooLarge) => return Err(HttpError::RequestLineTooLong),
+            Err(e) => return Err(e),
+        }
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(HttpError::BadRequest("malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(HttpError::BadRequest("unsupported HTTP version"));
+    }
+    let http10 = version == "HTTP/1.0";
+
+    let headers = read_headers(reader, limits)?;
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body: Vec::new(),
+        keep_alive: false,
+    };
+
+    let connection = request.header("connection").map(str::to_ascii_lowercase);
+    request.keep_alive = match connection.as_deref() {
+        Some(c) if c.contains("close") => false,
+        Some(c) if c.contains("keep-alive") => true,
+        _ => !http10,
+    };
+
+    let chunked = match request.header("transfer-encoding") {
+        None => false,
+        Some(te) if te.eq_ignore_ascii_case("chunked") => true,
+        Some(_) => return Err(HttpError::NotImplemented("unsupported transfer-encoding")),
+    };
+    let content_length = match request.header("content-length") {
+        Some(_) if chunked => {
+            return Err(HttpError::BadRequest(
+                "both content-length and transfer-encoding",
+            ))
+        }
+        Some(v) => Some(
+            v.trim()
+                .parse::<usize>()
+                .map_err(|_| HttpError::BadRequest("invalid content-length"))?,
+        ),
+        None => None,
+    };
+    if content_length.is_some_and(|len| len > limits.max_body) {
+        return Err(HttpError::PayloadTooLarge);
+    }
+
+    let has_body = chunked || content_length.is_some_and(|len| len > 0);
+    if has_body
+        && request
+            .header("expect")
+            .is_some_and(|e| e.eq_ignore_ascii_case("100-continue"))
+    {
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    }
+
+    if chunked {
+        request.body = read_chunked_body(reader, limits)?;
+    } else if let Some(len) = content_length {
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body)?;
+        request.body = body;
+    }
+
+    Ok(request)
+}
+
+fn read_headers<R: BufRead>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<Vec<(String, String)>, HttpError> {
+    let mut headers = Vec::new();
+    let mut total = 0;
+    loop {
+        let line = read_line(reader, limits.max_header_line)?.ok_or(HttpError::Closed)?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        total += line.len();
+        if headers.len() >= limits.max_header_count || total > limits.max_header_bytes {
+            return Err(HttpError::HeadersTooLarge);
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or(HttpError::BadRequest("malformed header"))?;
+        if name.is_empty() || name.ends_with(char::is_whitespace) {
+            return Err(HttpError::BadRequest("malformed header"));
+        }
+        headers.push((name.to_string(), value.trim().to_string()));
+    }
+}
+
+fn read_chunked_body<R: BufRead>(reader: &mut R, limits: &Limits) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader, limits.max_header_line)?
+            .ok_or(HttpError::BadRequest("truncated chunked body"))?;
+        // Ignore chunk extensions: "1a;name=value"
+        let size_str = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_str, 16)
+            .map_err(|_| HttpError::BadRequest("invalid chunk size"))?;
+        if size == 0 {
+            break;
+        }
+        if body.len() + size > limits.max_body {
+            return Err(HttpError::PayloadTooLarge);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        let mut crlf = [0u8; 2];
+        reader.read_exact(&mut crlf)?;
+        if &crlf != b"\r\n" {
+            return Err(HttpError::BadRequest("missing chunk terminator"));
+        }
+    }
+    // Trailer fields are read (and bounded) but not used
+    read_headers(reader, limits)?;
+    Ok(body)
+}
+
+/// Read a CRLF- (or LF-) terminated line of at most `max` bytes, without the
+/// terminator. Returns `None` on EOF before any bytes.
+fn read_line<R: BufRead>(reader: &mut R, max: usize) -> Result<Option<String>, HttpError> {
+    let mut buf = Vec::new();
+    let n = reader
+        .by_ref()
+        .take(max as u64 + 2)
+        .read_until(b'\n', &mut buf)?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if buf.last() != Some(&b'\n') {
+        return if buf.len() > max {
+            Err(HttpError::HeadersTooLarge)
+        } else {
+            Err(HttpError::BadRequest("unexpected end of request"))
+        };
+    }
+    buf.pop();
+    if buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    if buf.len() > max {
+        return Err(HttpError::HeadersTooLarge);
+    }
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|_| HttpError::BadRequest("header is not valid UTF-8"))
+}
+
+pub fn write_response<W: Write>(
+    stream: &mut W,
+    response: &Response,
+    keep_alive: bool,
+) -> std::io::Result<()> {
+    let status = response.status;
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        414 => "URI Too Long",
+        431 => "Request Header Fields Too Large",
+        501 => "Not Implemented",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: {connection}\r\n\r\n{}",
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Fixed number of worker threads fed by a bounded queue. When the queue is
+/// full, `try_submit` hands the item back so the caller can shed load.
+pub struct WorkerPool<T: Send + 'static> {
+    tx: SyncSender<T>,
+}
+
+impl<T: Send + 'static> WorkerPool<T> {
+    pub fn new<F>(workers: usize, queue: usize, handler: F) -> Self
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let (tx, rx) = sync_channel::<T>(queue);
+        let rx: Arc<Mutex<Receiver<T>>> = Arc::new(Mutex::new(rx));
+        let handler = Arc::new(handler);
+        for _ in 0..workers {
+            let rx = rx.clone();
+            let handler = handler.clone();
+            std::thread::spawn(move || loop {
+                let item = {
+                    let rx = rx.lock().unwrap();
+                    rx.recv()
+                };
+                match item {
+                    Ok(item) => handler(item),
+                    Err(_) => break,
+                }
+            });
+        }
+        Self { tx }
+    }
+
+    pub fn try_submit(&self, item: T) -> Result<(), T> {
+        match self.tx.try_send(item) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(item)) | Err(TrySendError::Disconnected(item)) => Err(item),
+        }
+    }
+}
+
+/// Tell a client the server is saturated and close the connection.
+pub fn reject_busy<S: Connection>(mut stream: S) {
+    let _ = stream.set_timeouts(Duration::from_secs(1), Duration::from_secs(1));
+    let _ = write_response(&mut stream, &Response::error(503, "server busy"), false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+
+    fn echo(request: &Request) -> Response {
+        Response::json(
+            200,
+            format!(
+                "{} {} {}",
+                request.method,
+                request.path,
+                String::from_utf8_lossy(&request.body)
+            ),
+        )
+    }
+
+    /// Serve one connection with `limits`, send `raw` from a client socket and
+    /// return everything the server wrote back before closing.
+    fn exchange(limits: Limits, raw: &[u8], close_write: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve_connection(stream, &limits, echo);
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client.write_all(raw).unwrap();
+        if close_write {
+            client.shutdown(std::net::Shutdown::Write).unwrap();
+        }
+        let mut out = Vec::new();
+        let mut buf = [0u8; 4096];
+        while let Ok(n) = client.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        server.join().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn statuses(response: &str) -> Vec<&str> {
+        response
+            .split("HTTP/1.1 ")
+            .skip(1)
+            .map(|r| &r[..3])
+            .collect()
+    }
+
+    #[test]
+    fn serves_content_length_body() {
+        let out = exchange(
+            Limits::default(),
+            b"POST /hook HTTP/1.1\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+            false,
+        );
+        assert!(out.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(out.contains("Connection: close\r\n"));
+        assert!(out.ends_with("POST /hook hello"));
+    }
+
+    #[test]
+    fn serves_chunked_body_with_extensions_and_trailers() {
+        let out = exchange(
+            Limits::default(),
+            b"POST /c HTTP/1.1\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+              5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: 1\r\n\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["200"]);
+        assert!(out.ends_with("POST /c hello world"));
+    }
+
+    #[test]
+    fn keeps_connection_alive_between_requests() {
+        let out = exchange(
+            Limits::default(),
+            b"POST /one HTTP/1.1\r\nContent-Length: 1\r\n\r\na\
+              \r\n\
+              POST /two HTTP/1.1\r\nContent-Length: 1\r\nConnection: close\r\n\r\nb",
+            false,
+        );
+        assert_eq!(statuses(&out), ["200", "200"]);
+        assert!(out.contains("Connection: keep-alive\r\n"));
+        assert!(out.contains("POST /one a"));
+        assert!(out.ends_with("POST /two b"));
+    }
+
+    #[test]
+    fn closes_after_max_requests_per_connection() {
+        let limits = Limits {
+            max_requests_per_connection: 1,
+            ..Limits::default()
+        };
+        let out = exchange(
+            limits,
+            b"POST /one HTTP/1.1\r\nContent-Length: 0\r\n\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["200"]);
+    }
+
+    #[test]
+    fn rejects_oversized_content_length() {
+        let limits = Limits {
+            max_body: 16,
+            ..Limits::default()
+        };
+        let out = exchange(
+            limits,
+            b"POST / HTTP/1.1\r\nContent-Length: 17\r\n\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["413"]);
+        assert!(out.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn rejects_oversized_chunked_body() {
+        let limits = Limits {
+            max_body: 8,
+            ..Limits::default()
+        };
+        let out = exchange(
+            limits,
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n5\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["413"]);
+    }
+
+    #[test]
+    fn rejects_too_many_headers() {
+        let limits = Limits {
+            max_header_count: 2,
+            ..Limits::default()
+        };
+        let out = exchange(limits, b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n", false);
+        assert_eq!(statuses(&out), ["431"]);
+    }
+
+    #[test]
+    fn rejects_too_many_header_bytes() {
+        let limits = Limits {
+            max_header_bytes: 40,
+            ..Limits::default()
+        };
+        let raw = format!(
+            "GET / HTTP/1.1\r\nA: {}\r\nB: {}\r\n",
+            "x".repeat(30),
+            "y".repeat(30)
+        );
+        let out = exchange(limits, raw.as_bytes(), false);
+        assert_eq!(statuses(&out), ["431"]);
+    }
+
+    #[test]
+    fn rejects_overlong_header_line() {
+        let limits = Limits {
+            max_header_line: 16,
+            ..Limits::default()
+        };
+        let raw = format!("GET / HTTP/1.1\r\nA: {}\r\n", "x".repeat(32));
+        let out = exchange(limits, raw.as_bytes(), false);
+        assert_eq!(statuses(&out), ["431"]);
+    }
+
+    #[test]
+    fn rejects_overlong_request_line() {
+        let limits = Limits {
+            max_request_line: 16,
+            ..Limits::default()
+        };
+        let raw = format!("GET /{} HTTP/1.1\r\n\r\n", "x".repeat(32));
+        let out = exchange(limits, raw.as_bytes(), false);
+        assert!(out.starts_with("HTTP/1.1 414 URI Too Long\r\n"));
+    }
+
+    #[test]
+    fn times_out_waiting_for_headers() {
+        let limits = Limits {
+            read_timeout: Duration::from_millis(200),
+            ..Limits::default()
+        };
+        // The client keeps its side open without finishing the headers
+        let out = exchange(limits, b"POST / HTTP/1.1\r\nHost: x\r\n", false);
+        assert_eq!(statuses(&out), ["408"]);
+    }
+
+    #[test]
+    fn times_out_a_request_that_trickles_in() {
+        let limits = Limits {
+            read_timeout: Duration::from_secs(2),
+            request_timeout: Duration::from_millis(400),
+            ..Limits::default()
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve_connection(stream, &limits, echo);
+        });
+
+        // Every byte arrives well within the read timeout, and the client
+        // goes quiet before the deadline so the server's close is clean
+        let started = Instant::now();
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client.write_all(b"POST / HTTP/1.1\r\n").unwrap();
+        for byte in b"X-Slow" {
+            std::thread::sleep(Duration::from_millis(50));
+            client.write_all(&[*byte]).unwrap();
+        }
+        let mut out = String::new();
+        client.read_to_string(&mut out).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(statuses(&out), ["408"]);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn idle_connection_closes_without_response() {
+        let limits = Limits {
+            read_timeout: Duration::from_millis(200),
+            ..Limits::default()
+        };
+        assert_eq!(exchange(limits.clone(), b"", false), "");
+        assert_eq!(exchange(limits, b"\r\n", true), "");
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let out = exchange(Limits::default(), b"NONSENSE\r\n\r\n", false);
+        assert_eq!(statuses(&out), ["400"]);
+        let out = exchange(
+            Limits::default(),
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["501"]);
+        let out = exchange(
+            Limits::default(),
+            b"POST / HTTP/1.1\r\nContent-Length: 1\r\nTransfer-Encoding: chunked\r\n\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["400"]);
+    }
+
+    #[test]
+    fn answers_expect_continue() {
+        let out = exchange(
+            Limits::default(),
+            b"POST / HTTP/1.1\r\nContent-Length: 2\r\nExpect: 100-continue\r\nConnection: close\r\n\r\nok",
+            false,
+        );
+        assert!(out.starts_with("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn http10_closes_by_default() {
+        let out = exchange(
+            Limits::default(),
+            b"POST / HTTP/1.0\r\nContent-Length: 0\r\n\r\nPOST / HTTP/1.0\r\n\r\n",
+            false,
+        );
+        assert_eq!(statuses(&out), ["200"]);
+        assert!(out.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn saturated_pool_hands_back_work_and_rejects_busy() {
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        let pool = WorkerPool::new(1, 1, move |item: u32| {
+            started_tx.send(item).unwrap();
+            let _ = release_rx.lock().unwrap().recv();
+        });
+
+        // One item running, one queued, then the pool is full
+        assert!(pool.try_submit(1).is_ok());
+        assert_eq!(started_rx.recv().unwrap(), 1);
+        assert!(pool.try_submit(2).is_ok());
+        assert_eq!(pool.try_submit(3), Err(3));
+
+        release_tx.send(()).unwrap();
+        assert_eq!(started_rx.recv().unwrap(), 2);
+        release_tx.send(()).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        reject_busy(stream);
+        let mut out = String::new();
+        client.read_to_string(&mut out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(out.ends_with(r#"{"error":"server busy"}"#));
+    }
+}
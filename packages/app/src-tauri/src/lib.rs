@@ -1,17 +1,46 @@
 mod appearance;
+mod approvals;
+mod away;
+mod claude_hooks;
 mod config;
 mod daemon;
+mod daemon_client;
+mod daemon_events;
+mod daemon_stream;
+mod daemon_supervisor;
+mod event_log;
+mod hook_adapters;
+mod hook_event;
 mod hook_server;
+mod http;
+mod notifications;
+mod policy;
+mod ports;
 mod preset;
+mod process_tree;
 mod project;
 mod pty_manager;
+mod remote_tab;
+mod session_state;
 mod setup;
 mod state;
+mod tool_sessions;
+mod webhooks;
 mod workspace;
 
+use approvals::ApprovalManager;
+use daemon_client::DaemonHandle;
+use daemon_events::SeenDaemonEvents;
+use daemon_supervisor::DaemonSupervisor;
+use away::AwayTracker;
+use event_log::EventLog;
+use notifications::Notifier;
+use ports::PortTracker;
 use pty_manager::PtyManager;
+use session_state::SessionStates;
 use std::sync::Mutex;
 use tauri::Manager;
+use webhooks::WebhookDispatcher;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -38,10 +67,36 @@ pub fn run() {
                     log::warn!("Hook server failed to start: {e}");
                 }
             }
+            // Poll session process trees for listening dev servers
+            ports::start_polling(app.handle().clone());
+            // Move quiet sessions to idle
+            session_state::start_ticking(app.handle().clone());
+            // Notify when channel sessions wait for an answer
+            notifications::start_input_needed_polling(app.handle().clone());
+            // Forward prompts to channels for sessions left idle
+            away::start_checking(app.handle().clone());
+            // Prune old hook event logs
+            event_log::start_retention(app.handle().clone());
+            // Send queued outbound webhook deliveries
+            webhooks::start_delivery(app.handle().clone());
+            // Subscribe to daemon events (replaces the app.port push)
+            daemon_stream::start_subscription(app.handle().clone());
+            // Watch daemon health and restart it if asked to
+            daemon_supervisor::start_supervising(app.handle().clone());
             Ok(())
         })
         .manage(Mutex::new(app_state))
         .manage(Mutex::new(PtyManager::new()))
+        .manage(PortTracker::new())
+        .manage(ApprovalManager::new())
+        .manage(EventLog::new())
+        .manage(SessionStates::new())
+        .manage(Notifier::new())
+        .manage(AwayTracker::new())
+        .manage(WebhookDispatcher::new())
+        .manage(SeenDaemonEvents::new())
+        .manage(DaemonHandle::new())
+        .manage(DaemonSupervisor::new())
         .invoke_handler(tauri::generate_handler![
             // Project commands
             project::list_projects,
@@ -66,6 +121,45 @@ pub fn run() {
             pty_manager::set_tool_session_id,
             pty_manager::rename_session,
             pty_manager::get_last_session,
+            remote_tab::attach_remote_session,
+            // Session state commands
+            session_state::get_session_states,
+            // Port commands
+            ports::get_session_ports,
+            // Approval commands
+            approvals::get_approval_settings,
+            approvals::set_approval_settings,
+            approvals::list_pending_approvals,
+            approvals::respond_to_approval,
+            // Policy commands
+            policy::list_policy_rules,
+            policy::add_policy_rule,
+            policy::update_policy_rule,
+            policy::remove_policy_rule,
+            policy::reorder_policy_rules,
+            policy::get_policy_log,
+            // Event log commands
+            event_log::query_hook_events,
+            event_log::get_event_log_settings,
+            event_log::set_event_log_settings,
+            // Notification commands
+            notifications::get_notification_settings,
+            notifications::set_notification_settings,
+            // Away mode commands
+            away::get_away_settings,
+            away::set_away_settings,
+            away::get_away_forwarding,
+            // Claude hook commands
+            claude_hooks::get_claude_hooks_status,
+            claude_hooks::preview_claude_hooks,
+            claude_hooks::apply_claude_hooks,
+            // Webhook commands
+            webhooks::list_webhooks,
+            webhooks::add_webhook,
+            webhooks::update_webhook,
+            webhooks::remove_webhook,
+            webhooks::test_webhook,
+            webhooks::get_webhook_deliveries,
             // Preset commands
             preset::list_presets,
             preset::add_preset,
@@ -76,6 +170,9 @@ pub fn run() {
             daemon::daemon_health,
             daemon::daemon_list_channels,
             daemon::daemon_runtime_channels,
+            daemon::daemon_list_chat_preferences,
+            daemon::daemon_get_chat_preferences,
+            daemon::daemon_set_chat_preferences,
             daemon::daemon_get_channel,
             daemon::daemon_add_channel,
             daemon::daemon_remove_channel,
@@ -84,11 +181,28 @@ pub fn run() {
             daemon::daemon_generate_code,
             daemon::daemon_restart,
             daemon::daemon_input_needed,
+            daemon::daemon_set_forwarding,
             daemon::daemon_recent_sessions,
             daemon::daemon_list_skills,
             daemon::daemon_list_background_jobs,
             daemon::daemon_get_agent_soul,
             daemon::daemon_set_agent_soul,
+            daemon::daemon_list_sessions,
+            daemon::daemon_send_message,
+            daemon::daemon_send_input,
+            daemon::daemon_send_file,
+            daemon::daemon_send_snapshot,
+            daemon::daemon_peek_session,
+            daemon::daemon_stop_session,
+            daemon::daemon_kill_session,
+            daemon::daemon_list_links,
+            daemon::daemon_link_session,
+            daemon::daemon_unlink_session,
+            daemon::daemon_move_session,
+            // Daemon supervisor commands
+            daemon_supervisor::get_daemon_status,
+            daemon_supervisor::get_daemon_settings,
+            daemon_supervisor::set_daemon_settings,
             // Workspace commands
             workspace::list_workspaces,
             workspace::add_workspace,
@@ -0,0 +1,534 @@
+use crate::config;
+use crate::daemon;
+use crate::daemon_client::DaemonHandle;
+use crate::policy;
+use crate::project::AppStateMutex;
+use crate::pty_manager;
+use crate::state::NotificationSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::sync::{mpsc::Sender, Arc};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often the daemon is asked which sessions are waiting for input. The
+/// interval backs off to `INPUT_NEEDED_MAX_POLL` while the daemon isn't
+/// running or the notification is turned off.
+const INPUT_NEEDED_POLL: Duration = Duration::from_secs(3);
+const INPUT_NEEDED_MAX_POLL: Duration = Duration::from_secs(60);
+
+/// Minimum gap between two notifications for the same session, so a hook
+/// and the daemon reporting the same prompt don't both notify.
+const SESSION_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Notifications watched for clicks at once on macOS and Windows, where each
+/// one needs its own waiting thread. Later ones are shown without
+/// click-to-focus until a watched one is answered or dismissed.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+const MAX_CLICK_WATCHERS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Attention,
+    Finished,
+    Crashed,
+    InputNeeded,
+}
+
+#[derive(Clone, Serialize)]
+struct FocusSession {
+    session_id: String,
+    project_id: Option<String>,
+}
+
+/// Rate limiting for desktop notifications, and the notifications being
+/// watched for clicks.
+pub struct Notifier {
+    last_sent: Mutex<HashMap<String, Instant>>,
+    /// ID of the notification on screen and the session clicking it opens.
+    /// Each new notification replaces it.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    shown: Arc<Mutex<Option<(u32, FocusSession)>>>,
+    /// Worker thread waiting for clicks, started with the first notification
+    #[cfg(all(unix, not(target_os = "macos")))]
+    clicks: Mutex<Option<Sender<notify_rust::NotificationHandle>>>,
+    /// Threads waiting for a click on one notification each
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    watching: AtomicUsize,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self {
+            last_sent: Mutex::new(HashMap::new()),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            shown: Arc::new(Mutex::new(None)),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            clicks: Mutex::new(None),
+            #[cfg(not(all(unix, not(target_os = "macos"))))]
+            watching: AtomicUsize::new(0),
+        }
+    }
+
+    fn allow(&self, session_id: &str) -> bool {
+        self.allow_at(session_id, Instant::now())
+    }
+
+    fn allow_at(&self, session_id: &str, now: Instant) -> bool {
+        let mut last_sent = self.last_sent.lock().unwrap();
+        last_sent.retain(|_, t| now.duration_since(*t) < SESSION_COOLDOWN);
+        if last_sent.contains_key(session_id) {
+            return false;
+        }
+        last_sent.insert(session_id.to_string(), now);
+        true
+    }
+}
+
+/// Show a desktop notification for a session event, subject to the user's
+/// settings. Clicking it focuses the window and emits `focus-session`.
+pub fn notify(app: &AppHandle, session_id: &str, kind: NotificationKind, body: &str) {
+    let project_id = policy::project_for_session(app, session_id).map(|(id, _)| id);
+    let (settings, project) = {
+        let state = app.state::<AppStateMutex>();
+        let s = state.lock().unwrap();
+        let project = project_id.and_then(|id| s.projects.iter().find(|p| p.id == id).cloned());
+        (s.notifications.clone(), project)
+    };
+
+    let project_id = project.as_ref().map(|p| p.id.as_str());
+    if !should_notify(&settings, kind, project_id, local_minute_of_day()) {
+        return;
+    }
+    // The user is already looking at the app
+    if app
+        .get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false)
+    {
+        return;
+    }
+    if !app.state::<Notifier>().allow(session_id) {
+        return;
+    }
+
+    let label = session_label(app, session_id);
+    let title = match (&project, label) {
+        (Some(p), Some(label)) => format!("{} · {label}", p.name),
+        (Some(p), None) => p.name.clone(),
+        (None, Some(label)) => label,
+        (None, None) => "touchgrass".to_string(),
+    };
+    let body = if body.is_empty() {
+        default_body(kind).to_string()
+    } else {
+        body.to_string()
+    };
+
+    show(
+        app.clone(),
+        &title,
+        &body,
+        FocusSession {
+            session_id: session_id.to_string(),
+            project_id: project.map(|p| p.id),
+        },
+    );
+}
+
+/// `minute_of_day` is the local time, if known, for quiet hours.
+fn should_notify(
+    settings: &NotificationSettings,
+    kind: NotificationKind,
+    project_id: Option<&str>,
+    minute_of_day: Option<u32>,
+) -> bool {
+    if !settings.enabled {
+        return false;
+    }
+    let event_enabled = match kind {
+        NotificationKind::Attention => settings.events.attention,
+        NotificationKind::Finished => settings.events.finished,
+        NotificationKind::Crashed => settings.events.crashed,
+        NotificationKind::InputNeeded => settings.events.input_needed,
+    };
+    if !event_enabled {
+        return false;
+    }
+    if project_id.is_some_and(|id| settings.muted_projects.iter().any(|m| m == id)) {
+        return false;
+    }
+    match (&settings.quiet_hours, minute_of_day) {
+        (Some(quiet), Some(now)) => !in_quiet_hours(&quiet.start, &quiet.end, now),
+        _ => true,
+    }
+}
+
+fn default_body(kind: NotificationKind) -> &'static str {
+    match kind {
+        NotificationKind::Attention => "Needs your attention",
+        NotificationKind::Finished => "Finished",
+        NotificationKind::Crashed => "Session crashed",
+        NotificationKind::InputNeeded => "Waiting for your input",
+    }
+}
+
+fn session_label(app: &AppHandle, session_id: &str) -> Option<String> {
+    let pty_mgr = app.state::<pty_manager::PtyManagerMutex>();
+    let mgr = pty_mgr.lock().unwrap();
+    mgr.sessions.get(session_id).map(|s| s.info.label.clone())
+}
+
+/// Shows at most one notification at a time: a new one replaces the one on
+/// screen, so a single worker waits for clicks.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show(app: AppHandle, title: &str, body: &str, focus: FocusSession) {
+    let notifier = app.state::<Notifier>();
+    let mut shown = notifier.shown.lock().unwrap();
+    let replacing = shown.as_ref().map(|(id, _)| *id);
+
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("touchgrass")
+        .summary(title)
+        .body(body)
+        .action("default", "Open");
+    if let Some(id) = replacing {
+        notification.id(id);
+    }
+    match notification.show() {
+        Ok(handle) => {
+            let id = handle.id();
+            *shown = Some((id, focus));
+            // A replaced notification keeps its ID and is already watched
+            if replacing != Some(id) {
+                notifier.watch_clicks(&app, handle);
+            }
+        }
+        Err(e) => log::warn!("Failed to show notification: {e}"),
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl Notifier {
+    /// Hand `handle` to the click worker, starting it if needed. The worker
+    /// waits on one notification at a time until it is clicked or closed.
+    fn watch_clicks(&self, app: &AppHandle, handle: notify_rust::NotificationHandle) {
+        let mut clicks = self.clicks.lock().unwrap();
+        let tx = clicks.get_or_insert_with(|| {
+            let (tx, rx) = std::sync::mpsc::channel::<notify_rust::NotificationHandle>();
+            let app = app.clone();
+            let shown = self.shown.clone();
+            std::thread::spawn(move || {
+                for handle in rx {
+                    let id = handle.id();
+                    handle.wait_for_action(|action| {
+                        let focus = shown
+                            .lock()
+                            .unwrap()
+                            .clone()
+                            .filter(|(shown_id, _)| *shown_id == id);
+                        if let (Some((_, focus)), "default") = (focus, action) {
+                            focus_session(&app, focus);
+                        }
+                    });
+                    let mut shown = shown.lock().unwrap();
+                    if shown.as_ref().is_some_and(|(shown_id, _)| *shown_id == id) {
+                        *shown = None;
+                    }
+                }
+            });
+            tx
+        });
+        let _ = tx.send(handle);
+    }
+}
+
+/// macOS and Windows report a click on the notification itself as its
+/// default response. Each notification is waited on from its own thread;
+/// macOS only delivers it once something waits for the response.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn show(app: AppHandle, title: &str, body: &str, focus: FocusSession) {
+    let handle = match notify_rust::Notification::new()
+        .summary(title)
+        .body(body)
+        .show()
+    {
+        Ok(handle) => handle,
+        Err(e) => {
+            log::warn!("Failed to show notification: {e}");
+            return;
+        }
+    };
+    let notifier = app.state::<Notifier>();
+    if notifier.watching.fetch_add(1, Ordering::SeqCst) >= MAX_CLICK_WATCHERS {
+        notifier.watching.fetch_sub(1, Ordering::SeqCst);
+        // Dropping the handle shows it without waiting
+        return;
+    }
+    std::thread::spawn(move || {
+        let result = handle.wait_for_response(|response: &notify_rust::NotificationResponse| {
+            if response.is_default_action() {
+                focus_session(&app, focus);
+            }
+        });
+        if let Err(e) = result {
+            log::debug!("Stopped waiting for a notification click: {e}");
+        }
+        app.state::<Notifier>()
+            .watching
+            .fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+fn focus_session(app: &AppHandle, focus: FocusSession) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit("focus-session", focus);
+}
+
+/// Parse "HH:MM" into minutes since midnight.
+fn parse_hhmm(value: &str) -> Option<u32> {
+    let (h, m) = value.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+fn in_quiet_hours(start: &str, end: &str, now: u32) -> bool {
+    let (Some(start), Some(end)) = (parse_hhmm(start), parse_hhmm(end)) else {
+        return false;
+    };
+    if start <= end {
+        now >= start && now < end
+    } else {
+        // Spans midnight, e.g. 22:00–07:00
+        now >= start || now < end
+    }
+}
+
+#[cfg(unix)]
+fn local_minute_of_day() -> Option<u32> {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs() as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return None;
+    }
+    Some(tm.tm_hour as u32 * 60 + tm.tm_min as u32)
+}
+
+#[cfg(not(unix))]
+fn local_minute_of_day() -> Option<u32> {
+    None
+}
+
+/// Poll the daemon for sessions waiting on an answer (channel-connected
+/// sessions that asked a question or need approval) and notify once per
+/// prompt.
+pub fn start_input_needed_polling(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut waiting: HashSet<String> = HashSet::new();
+        let mut interval = INPUT_NEEDED_POLL;
+        loop {
+            std::thread::sleep(interval);
+            let enabled = {
+                let state = app.state::<AppStateMutex>();
+                let s = state.lock().unwrap();
+                s.notifications.enabled && s.notifications.events.input_needed
+            };
+            if !enabled {
+                waiting.clear();
+                interval = INPUT_NEEDED_MAX_POLL;
+                continue;
+            }
+            let Ok(resp) = app
+                .state::<DaemonHandle>()
+                .passive()
+                .get::<daemon::InputNeededResponse>("/input-needed")
+            else {
+                waiting.clear();
+                interval = (interval * 2).min(INPUT_NEEDED_MAX_POLL);
+                continue;
+            };
+            interval = INPUT_NEEDED_POLL;
+
+            let prompts: Vec<_> = new_prompts(&waiting, &resp.sessions).collect();
+            let by_remote = if prompts.is_empty() {
+                HashMap::new()
+            } else {
+                pty_manager::sessions_by_remote(&app)
+            };
+            for session in prompts {
+                let Some(app_session) = by_remote.get(&session.session_id) else {
+                    continue;
+                };
+                let body = if session.input_type == "approval" {
+                    "Waiting for approval"
+                } else {
+                    "Waiting for your answer"
+                };
+                notify(&app, app_session, NotificationKind::InputNeeded, body);
+            }
+            waiting = resp.sessions.into_iter().map(|s| s.session_id).collect();
+        }
+    });
+}
+
+/// Sessions in `current` that weren't already waiting at the last poll.
+fn new_prompts<'a>(
+    waiting: &'a HashSet<String>,
+    current: &'a [daemon::InputNeededSession],
+) -> impl Iterator<Item = &'a daemon::InputNeededSession> {
+    current.iter().filter(|s| !waiting.contains(&s.session_id))
+}
+
+#[tauri::command]
+pub fn get_notification_settings(state: State<'_, AppStateMutex>) -> NotificationSettings {
+    let s = state.lock().unwrap();
+    s.notifications.clone()
+}
+
+#[tauri::command]
+pub fn set_notification_settings(
+    state: State<'_, AppStateMutex>,
+    settings: NotificationSettings,
+) -> Result<(), String> {
+    if let Some(quiet) = &settings.quiet_hours {
+        if parse_hhmm(&quiet.start).is_none() || parse_hhmm(&quiet.end).is_none() {
+            return Err("Quiet hours must be in HH:MM format.".into());
+        }
+    }
+    let mut s = state.lock().unwrap();
+    s.notifications = settings;
+    config::save_state(&s);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::QuietHours;
+
+    fn settings() -> NotificationSettings {
+        NotificationSettings::default()
+    }
+
+    #[test]
+    fn one_notification_per_session_per_cooldown() {
+        let notifier = Notifier::new();
+        let now = Instant::now();
+        assert!(notifier.allow_at("s1", now));
+        assert!(!notifier.allow_at("s1", now + Duration::from_secs(1)));
+        assert!(notifier.allow_at("s2", now + Duration::from_secs(1)));
+        assert!(!notifier.allow_at("s1", now + SESSION_COOLDOWN - Duration::from_millis(1)));
+        assert!(notifier.allow_at("s1", now + SESSION_COOLDOWN));
+    }
+
+    #[test]
+    fn only_new_prompts_notify() {
+        let session = |id: &str| daemon::InputNeededSession {
+            session_id: id.into(),
+            command: "claude".into(),
+            input_type: "question".into(),
+        };
+        let current = vec![session("r-1"), session("r-2")];
+        let waiting: HashSet<String> = ["r-1".to_string(), "r-9".to_string()].into();
+        let new: Vec<&str> = new_prompts(&waiting, &current)
+            .map(|s| s.session_id.as_str())
+            .collect();
+        assert_eq!(new, ["r-2"]);
+        assert_eq!(new_prompts(&HashSet::new(), &current).count(), 2);
+    }
+
+    #[test]
+    fn parses_hhmm() {
+        assert_eq!(parse_hhmm("00:00"), Some(0));
+        assert_eq!(parse_hhmm(" 7:05 "), Some(425));
+        assert_eq!(parse_hhmm("23:59"), Some(1439));
+        assert_eq!(parse_hhmm("24:00"), None);
+        assert_eq!(parse_hhmm("12:60"), None);
+        assert_eq!(parse_hhmm("1200"), None);
+        assert_eq!(parse_hhmm("ab:cd"), None);
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        assert!(!in_quiet_hours("09:00", "17:00", 8 * 60 + 59));
+        assert!(in_quiet_hours("09:00", "17:00", 9 * 60));
+        assert!(in_quiet_hours("09:00", "17:00", 16 * 60 + 59));
+        assert!(!in_quiet_hours("09:00", "17:00", 17 * 60));
+        // An empty range is never quiet
+        assert!(!in_quiet_hours("09:00", "09:00", 9 * 60));
+    }
+
+    #[test]
+    fn quiet_hours_across_midnight() {
+        assert!(in_quiet_hours("22:00", "07:00", 23 * 60));
+        assert!(in_quiet_hours("22:00", "07:00", 0));
+        assert!(in_quiet_hours("22:00", "07:00", 6 * 60 + 59));
+        assert!(!in_quiet_hours("22:00", "07:00", 7 * 60));
+        assert!(!in_quiet_hours("22:00", "07:00", 12 * 60));
+        assert!(!in_quiet_hours("bad", "07:00", 0));
+    }
+
+    #[test]
+    fn settings_gate_notifications() {
+        let mut s = settings();
+        assert!(should_notify(&s, NotificationKind::Finished, None, Some(0)));
+
+        s.events.finished = false;
+        assert!(!should_notify(&s, NotificationKind::Finished, None, None));
+        assert!(should_notify(&s, NotificationKind::Crashed, None, None));
+
+        s.muted_projects = vec!["p1".into()];
+        assert!(!should_notify(
+            &s,
+            NotificationKind::Crashed,
+            Some("p1"),
+            None
+        ));
+        assert!(should_notify(
+            &s,
+            NotificationKind::Crashed,
+            Some("p2"),
+            None
+        ));
+
+        s.quiet_hours = Some(QuietHours {
+            start: "22:00".into(),
+            end: "07:00".into(),
+        });
+        assert!(!should_notify(
+            &s,
+            NotificationKind::Crashed,
+            None,
+            Some(23 * 60)
+        ));
+        assert!(should_notify(
+            &s,
+            NotificationKind::Crashed,
+            None,
+            Some(12 * 60)
+        ));
+        // Without a local time, quiet hours can't apply
+        assert!(should_notify(&s, NotificationKind::Crashed, None, None));
+
+        s.enabled = false;
+        assert!(!should_notify(
+            &s,
+            NotificationKind::Crashed,
+            None,
+            Some(12 * 60)
+        ));
+    }
+}
@@ -0,0 +1,728 @@
+use crate::config;
+use crate::project::AppStateMutex;
+use crate::pty_manager::PtyManagerMutex;
+use crate::state::{Decision, PolicyRule};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Most recent decisions returned by `get_policy_log`.
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// The decision log is trimmed to its most recent lines past this size.
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const TRIMMED_LOG_LINES: usize = 2000;
+
+/// tool_input fields tried, in order, when a rule has no explicit field.
+const PRIMARY_FIELDS: &[&str] = &[
+    "command",
+    "file_path",
+    "notebook_path",
+    "path",
+    "url",
+    "pattern",
+    "query",
+];
+
+/// tool_input fields that hold a filesystem path.
+const PATH_FIELDS: &[&str] = &["file_path", "notebook_path", "path"];
+
+/// Shell syntax that chains, pipes or nests commands. An Allow rule only
+/// vouches for the command it names, so it never matches a Bash command
+/// containing any of these; the event falls through to the next rule or the
+/// tool's own prompt.
+const COMPOUND_SHELL: &[&str] = &[";", "&", "|", "`", "$(", "<(", ">(", "\n", "\r"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyLogEntry {
+    /// Unix epoch milliseconds
+    pub timestamp: u64,
+    pub session_id: String,
+    pub project_id: Option<String>,
+    pub tool_name: String,
+    pub tool_input: Option<serde_json::Value>,
+    pub decision: Decision,
+    pub rule_id: String,
+    /// Rule as written, e.g. "Bash(rm -rf*)"
+    pub rule: String,
+    pub reason: Option<String>,
+}
+
+/// A rule as submitted from the UI. `tool` accepts Claude's permission syntax,
+/// so "Bash(git status*)" is the same as tool "Bash" with pattern "git status*".
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewPolicyRule {
+    pub project_id: Option<String>,
+    pub tool: String,
+    pub field: Option<String>,
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub outside_project: bool,
+    pub decision: Decision,
+    pub reason: Option<String>,
+}
+
+/// Context needed to evaluate a rule against one hook event.
+pub struct PolicyContext<'a> {
+    pub project_id: Option<&'a str>,
+    /// Directory that "outside_project" rules compare against
+    pub project_path: Option<&'a str>,
+    pub tool_name: &'a str,
+    pub tool_input: Option<&'a serde_json::Value>,
+}
+
+/// First enabled rule (in list order) that applies to the event.
+pub fn evaluate<'r>(rules: &'r [PolicyRule], ctx: &PolicyContext) -> Option<&'r PolicyRule> {
+    rules.iter().find(|rule| rule_matches(rule, ctx))
+}
+
+fn rule_matches(rule: &PolicyRule, ctx: &PolicyContext) -> bool {
+    if !rule.enabled {
+        return false;
+    }
+    if rule.project_id.is_some() && rule.project_id.as_deref() != ctx.project_id {
+        return false;
+    }
+    if !glob_match(&rule.tool, ctx.tool_name) {
+        return false;
+    }
+    if rule.decision == Decision::Allow && ctx.tool_name == "Bash" && is_compound_command(ctx) {
+        return false;
+    }
+
+    if rule.outside_project {
+        let Some(project_path) = ctx.project_path else {
+            return false;
+        };
+        let Some(path) = ctx
+            .tool_input
+            .and_then(|input| first_string(input, PATH_FIELDS))
+        else {
+            return false;
+        };
+        if is_within(Path::new(project_path), path) {
+            return false;
+        }
+    }
+
+    let Some(pattern) = rule.pattern.as_deref() else {
+        return true;
+    };
+    let Some(input) = ctx.tool_input else {
+        return false;
+    };
+    match rule.field.as_deref() {
+        Some("*") => any_string_matches(input, pattern),
+        Some(field) => input
+            .get(field)
+            .and_then(|v| v.as_str())
+            .is_some_and(|v| glob_match(pattern, v)),
+        None => first_string(input, PRIMARY_FIELDS).is_some_and(|v| glob_match(pattern, v)),
+    }
+}
+
+fn is_compound_command(ctx: &PolicyContext) -> bool {
+    ctx.tool_input
+        .and_then(|input| input.get("command"))
+        .and_then(|v| v.as_str())
+        .is_some_and(|command| COMPOUND_SHELL.iter().any(|s| command.contains(s)))
+}
+
+/// Human-readable form of a rule, e.g. `Bash(git status*)` or `Edit[outside project]`.
+pub fn describe(rule: &PolicyRule) -> String {
+    let mut s = rule.tool.clone();
+    if let Some(pattern) = &rule.pattern {
+        match rule.field.as_deref() {
+            Some(field) => s.push_str(&format!("({field}={pattern})")),
+            None => s.push_str(&format!("({pattern})")),
+        }
+    }
+    if rule.outside_project {
+        s.push_str("[outside project]");
+    }
+    s
+}
+
+/// Split Claude's "Tool(pattern)" permission syntax into tool and pattern.
+fn parse_tool_spec(spec: &str) -> (String, Option<String>) {
+    let spec = spec.trim();
+    if let Some(open) = spec.find('(') {
+        if let Some(inner) = spec[open + 1..].strip_suffix(')') {
+            let tool = spec[..open].trim().to_string();
+            return (tool, Some(inner.to_string()).filter(|p| !p.is_empty()));
+        }
+    }
+    (spec.to_string(), None)
+}
+
+/// Check a rule from the UI the same way whether it is new or edited: split
+/// "Tool(pattern)" syntax and treat empty strings as unset.
+fn validate_rule(mut rule: PolicyRule) -> Result<PolicyRule, String> {
+    let (tool, spec_pattern) = parse_tool_spec(&rule.tool);
+    if tool.is_empty() {
+        return Err("Tool name cannot be empty.".into());
+    }
+    rule.tool = tool;
+    rule.field = rule.field.filter(|f| !f.is_empty());
+    rule.pattern = rule.pattern.filter(|p| !p.is_empty()).or(spec_pattern);
+    rule.reason = rule.reason.filter(|r| !r.is_empty());
+    Ok(rule)
+}
+
+/// Glob match supporting `*` (any run of characters) and `?` (one character).
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<usize> = None;
+    let mut star_ti = 0;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            star_ti = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            star_ti += 1;
+            ti = star_ti;
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+fn first_string<'a>(input: &'a serde_json::Value, fields: &[&str]) -> Option<&'a str> {
+    fields
+        .iter()
+        .find_map(|f| input.get(*f).and_then(|v| v.as_str()))
+}
+
+fn any_string_matches(value: &serde_json::Value, pattern: &str) -> bool {
+    match value {
+        serde_json::Value::String(s) => glob_match(pattern, s),
+        serde_json::Value::Array(items) => items.iter().any(|v| any_string_matches(v, pattern)),
+        serde_json::Value::Object(map) => map.values().any(|v| any_string_matches(v, pattern)),
+        _ => false,
+    }
+}
+
+/// Resolve `path` against `root` and check it stays inside `root`. Symlinks
+/// are followed as far as the path exists, so a link out of the project
+/// counts as outside it.
+fn is_within(root: &Path, path: &str) -> bool {
+    let root = normalize(root);
+    let candidate = if Path::new(path).is_absolute() {
+        normalize(Path::new(path))
+    } else {
+        normalize(&root.join(path))
+    };
+    resolve_existing(&candidate).starts_with(resolve_existing(&root))
+}
+
+/// Canonicalize the longest existing prefix of `path` and append the rest.
+fn resolve_existing(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(real) = existing.canonicalize() {
+            return rest.iter().rev().fold(real, |p, name| p.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// Project id and path for an app session, if it belongs to a known project.
+pub fn project_for_session(app: &AppHandle, session_id: &str) -> Option<(String, String)> {
+    let project_id = {
+        let pty_mgr = app.state::<PtyManagerMutex>();
+        let mgr = pty_mgr.lock().unwrap();
+        mgr.sessions.get(session_id)?.info.project_id.clone()
+    };
+    let state = app.state::<AppStateMutex>();
+    let s = state.lock().unwrap();
+    let project = s.projects.iter().find(|p| p.id == project_id)?;
+    Some((project.id.clone(), project.path.clone()))
+}
+
+/// Evaluate the rules for a PreToolUse event. Logs and emits
+/// `policy-decision` when a rule fires.
+pub fn decide(
+    app: &AppHandle,
+    session_id: &str,
+    tool_name: &str,
+    tool_input: Option<&serde_json::Value>,
+    cwd: Option<&str>,
+) -> Option<(Decision, Option<String>)> {
+    let project = project_for_session(app, session_id);
+    let project_id = project.as_ref().map(|(id, _)| id.as_str());
+    let project_path = project.as_ref().map(|(_, path)| path.as_str()).or(cwd);
+
+    let rule = {
+        let state = app.state::<AppStateMutex>();
+        let s = state.lock().unwrap();
+        let ctx = PolicyContext {
+            project_id,
+            project_path,
+            tool_name,
+            tool_input,
+        };
+        evaluate(&s.policy_rules, &ctx)?.clone()
+    };
+
+    let reason = Some(
+        rule.reason
+            .clone()
+            .unwrap_or_else(|| format!("touchgrass rule {}", describe(&rule))),
+    );
+    let entry = PolicyLogEntry {
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        session_id: session_id.to_string(),
+        project_id: project_id.map(String::from),
+        tool_name: tool_name.to_string(),
+        tool_input: tool_input.cloned(),
+        decision: rule.decision,
+        rule_id: rule.id.clone(),
+        rule: describe(&rule),
+        reason: reason.clone(),
+    };
+    log::info!(
+        "Policy {:?} {tool_name} in session {session_id} (rule {})",
+        rule.decision,
+        entry.rule
+    );
+    append_log(&entry);
+    let _ = app.emit("policy-decision", &entry);
+
+    Some((rule.decision, reason))
+}
+
+fn log_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".touchgrass").join("policy.log"))
+}
+
+fn append_log(entry: &PolicyLogEntry) {
+    let Some(path) = log_path() else { return };
+    let Ok(line) = serde_json::to_string(entry) else {
+        return;
+    };
+    let too_big = std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES);
+    if too_big {
+        let data = std::fs::read_to_string(&path).unwrap_or_default();
+        let lines: Vec<&str> = data.lines().collect();
+        let keep = &lines[lines.len().saturating_sub(TRIMMED_LOG_LINES)..];
+        let _ = std::fs::write(&path, format!("{}\n", keep.join("\n")));
+    }
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+    {
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+#[tauri::command]
+pub fn list_policy_rules(
+    state: State<'_, AppStateMutex>,
+    project_id: Option<String>,
+) -> Vec<PolicyRule> {
+    let s = state.lock().unwrap();
+    s.policy_rules
+        .iter()
+        .filter(|r| r.project_id.is_none() || r.project_id == project_id)
+        .cloned()
+        .collect()
+}
+
+#[tauri::command]
+pub fn add_policy_rule(
+    state: State<'_, AppStateMutex>,
+    rule: NewPolicyRule,
+) -> Result<PolicyRule, String> {
+    let policy_rule = validate_rule(PolicyRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: rule.project_id,
+        tool: rule.tool,
+        field: rule.field,
+        pattern: rule.pattern,
+        outside_project: rule.outside_project,
+        decision: rule.decision,
+        reason: rule.reason,
+        enabled: true,
+    })?;
+    let mut s = state.lock().unwrap();
+    s.policy_rules.push(policy_rule.clone());
+    config::save_state(&s);
+    Ok(policy_rule)
+}
+
+#[tauri::command]
+pub fn update_policy_rule(
+    state: State<'_, AppStateMutex>,
+    rule: PolicyRule,
+) -> Result<PolicyRule, String> {
+    let rule = validate_rule(rule)?;
+    let mut s = state.lock().unwrap();
+    let existing = s
+        .policy_rules
+        .iter_mut()
+        .find(|r| r.id == rule.id)
+        .ok_or_else(|| format!("Rule not found: {}", rule.id))?;
+    *existing = rule.clone();
+    config::save_state(&s);
+    Ok(rule)
+}
+
+#[tauri::command]
+pub fn remove_policy_rule(state: State<'_, AppStateMutex>, rule_id: String) -> Result<(), String> {
+    let mut s = state.lock().unwrap();
+    s.policy_rules.retain(|r| r.id != rule_id);
+    config::save_state(&s);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn reorder_policy_rules(
+    state: State<'_, AppStateMutex>,
+    rule_ids: Vec<String>,
+) -> Result<(), String> {
+    let mut s = state.lock().unwrap();
+    let mut reordered = Vec::with_capacity(s.policy_rules.len());
+    for id in &rule_ids {
+        if let Some(pos) = s.policy_rules.iter().position(|r| &r.id == id) {
+            reordered.push(s.policy_rules.remove(pos));
+        }
+    }
+    // Append any remaining rules not in the reorder list
+    reordered.append(&mut s.policy_rules);
+    s.policy_rules = reordered;
+    config::save_state(&s);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_policy_log(limit: Option<usize>) -> Vec<PolicyLogEntry> {
+    let Some(path) = log_path() else {
+        return Vec::new();
+    };
+    let data = std::fs::read_to_string(path).unwrap_or_default();
+    let mut entries: Vec<PolicyLogEntry> = data
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    if entries.len() > limit {
+        entries.drain(..entries.len() - limit);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(tool: &str, pattern: Option<&str>, decision: Decision) -> PolicyRule {
+        PolicyRule {
+            id: format!("{tool}-{pattern:?}"),
+            project_id: None,
+            tool: tool.into(),
+            field: None,
+            pattern: pattern.map(String::from),
+            outside_project: false,
+            decision,
+            reason: None,
+            enabled: true,
+        }
+    }
+
+    fn ctx<'a>(tool_name: &'a str, tool_input: Option<&'a serde_json::Value>) -> PolicyContext<'a> {
+        PolicyContext {
+            project_id: Some("p1"),
+            project_path: Some("/work/proj"),
+            tool_name,
+            tool_input,
+        }
+    }
+
+    #[test]
+    fn glob_literals_and_wildcards() {
+        assert!(glob_match("Bash", "Bash"));
+        assert!(!glob_match("Bash", "bash"));
+        assert!(!glob_match("Bash", "BashOutput"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything at all"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "x"));
+        assert!(glob_match("git status*", "git status"));
+        assert!(glob_match("git status*", "git status --short"));
+        assert!(!glob_match("git status*", "git stash"));
+        assert!(glob_match("?at", "cat"));
+        assert!(!glob_match("?at", "at"));
+        assert!(glob_match("é?", "éü"));
+    }
+
+    #[test]
+    fn glob_star_backtracks_and_crosses_slashes() {
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(glob_match("a*b*c", "abbbc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("*rm -rf*", "cd x && rm -rf /"));
+        assert!(glob_match("**", "x"));
+        assert!(glob_match("mcp__*__*", "mcp__github__create_issue"));
+    }
+
+    #[test]
+    fn parses_claude_permission_syntax() {
+        assert_eq!(
+            parse_tool_spec("Bash(git status*)"),
+            ("Bash".into(), Some("git status*".into()))
+        );
+        assert_eq!(parse_tool_spec("  Edit  "), ("Edit".into(), None));
+        assert_eq!(parse_tool_spec("Bash()"), ("Bash".into(), None));
+        assert_eq!(
+            parse_tool_spec("Bash(echo (hi))"),
+            ("Bash".into(), Some("echo (hi)".into()))
+        );
+        assert_eq!(
+            parse_tool_spec("Bash(unclosed"),
+            ("Bash(unclosed".into(), None)
+        );
+    }
+
+    #[test]
+    fn is_within_resolves_parent_dirs() {
+        let root = Path::new("/work/proj");
+        assert!(is_within(root, "src/main.rs"));
+        assert!(is_within(root, "./src/../README.md"));
+        assert!(is_within(root, "/work/proj/a/b"));
+        assert!(is_within(root, "/work/proj"));
+        assert!(!is_within(root, "../other/file"));
+        assert!(!is_within(root, "src/../../proj-other/x"));
+        assert!(!is_within(root, "/work/proj-other/x"));
+        assert!(!is_within(root, "/etc/passwd"));
+        assert!(!is_within(Path::new("/work/proj/../proj2"), "/work/proj/x"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn is_within_follows_symlinks_out_of_the_project() {
+        let base = std::env::temp_dir().join(format!("tg-policy-{}", std::process::id()));
+        let project = base.join("proj");
+        let outside = base.join("outside");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        let _ = std::fs::remove_file(project.join("escape"));
+        std::os::unix::fs::symlink(&outside, project.join("escape")).unwrap();
+
+        assert!(is_within(&project, "src/new_file.rs"));
+        assert!(!is_within(&project, "escape/secret"));
+        assert!(!is_within(
+            &project,
+            &project.join("escape").to_string_lossy()
+        ));
+        // A project path given through a symlink still contains its files
+        let _ = std::fs::remove_file(base.join("link-to-proj"));
+        std::os::unix::fs::symlink(&project, base.join("link-to-proj")).unwrap();
+        assert!(is_within(
+            &base.join("link-to-proj"),
+            &project.join("src").to_string_lossy()
+        ));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn first_enabled_matching_rule_wins() {
+        let input = json!({ "command": "rm -rf build" });
+        let mut disabled = rule("Bash", Some("rm *"), Decision::Allow);
+        disabled.enabled = false;
+        let rules = vec![
+            disabled,
+            rule("Bash", Some("rm -rf*"), Decision::Deny),
+            rule("Bash", None, Decision::Allow),
+        ];
+        let hit = evaluate(&rules, &ctx("Bash", Some(&input))).unwrap();
+        assert_eq!(hit.decision, Decision::Deny);
+
+        let input = json!({ "command": "ls" });
+        let hit = evaluate(&rules, &ctx("Bash", Some(&input))).unwrap();
+        assert_eq!(hit.decision, Decision::Allow);
+        assert!(evaluate(&rules, &ctx("Edit", Some(&input))).is_none());
+    }
+
+    #[test]
+    fn project_rules_only_apply_to_their_project() {
+        let mut scoped = rule("*", None, Decision::Deny);
+        scoped.project_id = Some("p2".into());
+        assert!(!rule_matches(&scoped, &ctx("Bash", None)));
+        scoped.project_id = Some("p1".into());
+        assert!(rule_matches(&scoped, &ctx("Bash", None)));
+    }
+
+    #[test]
+    fn patterns_match_primary_named_or_any_field() {
+        let input = json!({
+            "file_path": "/work/proj/src/lib.rs",
+            "content": "TODO",
+            "edits": [{ "old_string": "secret_key" }],
+        });
+        let primary = rule("Write", Some("*.rs"), Decision::Allow);
+        assert!(rule_matches(&primary, &ctx("Write", Some(&input))));
+
+        let mut named = rule("Write", Some("TODO"), Decision::Deny);
+        named.field = Some("content".into());
+        assert!(rule_matches(&named, &ctx("Write", Some(&input))));
+        named.field = Some("missing".into());
+        assert!(!rule_matches(&named, &ctx("Write", Some(&input))));
+
+        let mut any = rule("*", Some("*secret*"), Decision::Deny);
+        any.field = Some("*".into());
+        assert!(rule_matches(&any, &ctx("MultiEdit", Some(&input))));
+
+        // A pattern needs input to match against
+        assert!(!rule_matches(&primary, &ctx("Write", None)));
+    }
+
+    #[test]
+    fn command_is_the_primary_field_before_paths() {
+        let input = json!({ "path": "/tmp", "command": "git push" });
+        let r = rule("*", Some("git *"), Decision::Ask);
+        assert!(rule_matches(&r, &ctx("Bash", Some(&input))));
+    }
+
+    #[test]
+    fn outside_project_rules_need_a_path_outside() {
+        let mut r = rule("Edit", None, Decision::Deny);
+        r.outside_project = true;
+        let inside = json!({ "file_path": "/work/proj/src/lib.rs" });
+        let outside = json!({ "file_path": "/work/proj/../secrets.txt" });
+        let no_path = json!({ "command": "ls" });
+        assert!(!rule_matches(&r, &ctx("Edit", Some(&inside))));
+        assert!(rule_matches(&r, &ctx("Edit", Some(&outside))));
+        assert!(!rule_matches(&r, &ctx("Edit", Some(&no_path))));
+
+        let no_project = PolicyContext {
+            project_path: None,
+            ..ctx("Edit", Some(&outside))
+        };
+        assert!(!rule_matches(&r, &no_project));
+    }
+
+    #[test]
+    fn allow_rules_skip_compound_bash_commands() {
+        let allow = rule("Bash", Some("git status*"), Decision::Allow);
+        let plain = json!({ "command": "git status --short" });
+        assert!(rule_matches(&allow, &ctx("Bash", Some(&plain))));
+
+        for command in [
+            "git status; rm -rf ~",
+            "git status && rm -rf ~",
+            "git status || rm -rf ~",
+            "git status | sh",
+            "git status & rm -rf ~",
+            "git status $(rm -rf ~)",
+            "git status `rm -rf ~`",
+            "git status\nrm -rf ~",
+            "git status <(rm -rf ~)",
+        ] {
+            let input = json!({ "command": command });
+            assert!(
+                !rule_matches(&allow, &ctx("Bash", Some(&input))),
+                "{command:?}"
+            );
+        }
+
+        // Not even a rule that allows every Bash command
+        let input = json!({ "command": "ls; rm -rf ~" });
+        assert!(!rule_matches(
+            &rule("Bash", None, Decision::Allow),
+            &ctx("Bash", Some(&input))
+        ));
+        assert!(!rule_matches(
+            &rule("*", None, Decision::Allow),
+            &ctx("Bash", Some(&input))
+        ));
+        // Deny and Ask rules still see the whole command
+        assert!(rule_matches(
+            &rule("Bash", Some("*rm -rf*"), Decision::Deny),
+            &ctx("Bash", Some(&input))
+        ));
+        assert!(rule_matches(
+            &rule("Bash", None, Decision::Ask),
+            &ctx("Bash", Some(&input))
+        ));
+    }
+
+    #[test]
+    fn compound_command_falls_through_to_the_next_rule() {
+        let rules = vec![
+            rule("Bash", Some("git status*"), Decision::Allow),
+            rule("Bash", None, Decision::Ask),
+        ];
+        let input = json!({ "command": "git status; rm -rf ~" });
+        let hit = evaluate(&rules, &ctx("Bash", Some(&input))).unwrap();
+        assert_eq!(hit.decision, Decision::Ask);
+        assert!(evaluate(&rules[..1], &ctx("Bash", Some(&input))).is_none());
+    }
+
+    #[test]
+    fn edited_rules_are_validated_like_new_ones() {
+        let mut edited = rule("Bash(git *)", None, Decision::Allow);
+        edited.field = Some(String::new());
+        edited.reason = Some(String::new());
+        let edited = validate_rule(edited).unwrap();
+        assert_eq!(edited.tool, "Bash");
+        assert_eq!(edited.pattern.as_deref(), Some("git *"));
+        assert_eq!(edited.field, None);
+        assert_eq!(edited.reason, None);
+
+        // An explicit pattern wins over the one in the tool spec
+        let explicit = validate_rule(rule("Bash(git *)", Some("ls*"), Decision::Allow)).unwrap();
+        assert_eq!(explicit.pattern.as_deref(), Some("ls*"));
+
+        assert!(validate_rule(rule("  ", None, Decision::Deny)).is_err());
+        assert!(validate_rule(rule("(x)", None, Decision::Deny)).is_err());
+    }
+
+    #[test]
+    fn describes_rules_in_permission_syntax() {
+        let mut r = rule("Bash", Some("git *"), Decision::Allow);
+        assert_eq!(describe(&r), "Bash(git *)");
+        r.field = Some("description".into());
+        r.outside_project = true;
+        assert_eq!(describe(&r), "Bash(description=git *)[outside project]");
+    }
+}
@@ -0,0 +1,438 @@
+use crate::process_tree;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often session process trees are checked for listening sockets.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Bytes of previous output kept so URLs split across PTY reads are still found.
+const URL_TAIL_LEN: usize = 64;
+
+const LOCAL_URL_PREFIXES: &[&str] = &[
+    "http://localhost:",
+    "https://localhost:",
+    "http://127.0.0.1:",
+    "https://127.0.0.1:",
+    "http://0.0.0.0:",
+    "https://0.0.0.0:",
+    "http://[::1]:",
+    "https://[::1]:",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionPort {
+    pub port: u16,
+    /// PID of the listening process, when known from the socket table
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    /// URL printed by the session, or http://localhost:{port}
+    pub url: String,
+    /// "socket" (found via /proc) | "output" (URL printed to the terminal)
+    pub source: String,
+}
+
+#[derive(Clone, Serialize)]
+struct PortEvent {
+    session_id: String,
+    port: SessionPort,
+}
+
+struct TrackedPort {
+    info: SessionPort,
+    /// Whether the port has been seen in the socket table. Output-only ports
+    /// that were never seen listening stay until the session ends.
+    seen_listening: bool,
+}
+
+#[derive(Default)]
+struct TrackedSession {
+    root_pid: Option<u32>,
+    ports: BTreeMap<u16, TrackedPort>,
+    url_tail: String,
+}
+
+/// Maps sessions to the TCP ports their process trees listen on, plus any
+/// localhost URLs they print (dev servers, preview links).
+pub struct PortTracker {
+    sessions: Mutex<HashMap<String, TrackedSession>>,
+}
+
+impl PortTracker {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn register(&self, session_id: &str, root_pid: Option<u32>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.insert(
+            session_id.to_string(),
+            TrackedSession {
+                root_pid,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Forget a session and emit `session-port-closed` for each of its ports.
+    pub fn unregister(&self, app: &AppHandle, session_id: &str) {
+        let removed = self.sessions.lock().unwrap().remove(session_id);
+        if let Some(session) = removed {
+            for (_, port) in session.ports {
+                emit_closed(app, session_id, port.info);
+            }
+        }
+    }
+
+    /// Scan a chunk of PTY output for localhost URLs.
+    pub fn scan_output(&self, app: &AppHandle, session_id: &str, data: &[u8]) {
+        let mut opened = Vec::new();
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(session) = sessions.get_mut(session_id) else {
+                return;
+            };
+            let text = format!("{}{}", session.url_tail, String::from_utf8_lossy(data));
+            for (port, url) in find_local_urls(&text) {
+                if session.ports.contains_key(&port) {
+                    continue;
+                }
+                let info = SessionPort {
+                    port,
+                    pid: None,
+                    process_name: None,
+                    url,
+                    source: "output".into(),
+                };
+                session.ports.insert(
+                    port,
+                    TrackedPort {
+                        info: info.clone(),
+                        seen_listening: false,
+                    },
+                );
+                opened.push(info);
+            }
+            let mut start = text.len().saturating_sub(URL_TAIL_LEN);
+            while !text.is_char_boundary(start) {
+                start += 1;
+            }
+            session.url_tail = text[start..].to_string();
+        }
+        for info in opened {
+            emit_opened(app, session_id, info);
+        }
+    }
+
+    fn ports(&self, session_id: &str) -> Vec<SessionPort> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(session_id)
+            .map(|s| s.ports.values().map(|p| p.info.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Reconcile tracked ports with the sockets currently held open by each
+    /// session's process tree.
+    fn poll(&self, app: &AppHandle) {
+        let roots: Vec<(String, u32)> = {
+            let sessions = self.sessions.lock().unwrap();
+            sessions
+                .iter()
+                .filter_map(|(id, s)| s.root_pid.map(|pid| (id.clone(), pid)))
+                .collect()
+        };
+        if roots.is_empty() {
+            return;
+        }
+
+        let listening = listening_inodes();
+        let table = process_tree::ProcessTable::snapshot();
+        let mut opened = Vec::new();
+        let mut closed = Vec::new();
+
+        for (session_id, root_pid) in roots {
+            let current = ports_for_tree(root_pid, &table, &listening);
+
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(session) = sessions.get_mut(&session_id) else {
+                continue;
+            };
+
+            for (&port, &pid) in &current {
+                match session.ports.get_mut(&port) {
+                    Some(tracked) => {
+                        tracked.seen_listening = true;
+                        if tracked.info.pid.is_none() {
+                            tracked.info.pid = Some(pid);
+                            tracked.info.process_name = process_tree::process_name(pid);
+                        }
+                    }
+                    None => {
+                        let info = SessionPort {
+                            port,
+                            pid: Some(pid),
+                            process_name: process_tree::process_name(pid),
+                            url: format!("http://localhost:{port}"),
+                            source: "socket".into(),
+                        };
+                        session.ports.insert(
+                            port,
+                            TrackedPort {
+                                info: info.clone(),
+                                seen_listening: true,
+                            },
+                        );
+                        opened.push((session_id.clone(), info));
+                    }
+                }
+            }
+
+            let gone: Vec<u16> = session
+                .ports
+                .iter()
+                .filter(|(port, p)| p.seen_listening && !current.contains_key(port))
+                .map(|(port, _)| *port)
+                .collect();
+            for port in gone {
+                if let Some(p) = session.ports.remove(&port) {
+                    closed.push((session_id.clone(), p.info));
+                }
+            }
+        }
+
+        for (session_id, info) in opened {
+            emit_opened(app, &session_id, info);
+        }
+        for (session_id, info) in closed {
+            emit_closed(app, &session_id, info);
+        }
+    }
+}
+
+/// Start the background thread that polls session process trees for
+/// listening sockets. Only Linux exposes the socket table via /proc; on other
+/// platforms ports are detected from terminal output alone.
+pub fn start_polling(app: AppHandle) {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let tracker = app.state::<PortTracker>();
+        tracker.poll(&app);
+    });
+}
+
+fn emit_opened(app: &AppHandle, session_id: &str, port: SessionPort) {
+    let _ = app.emit(
+        "session-port-opened",
+        PortEvent {
+            session_id: session_id.to_string(),
+            port,
+        },
+    );
+}
+
+fn emit_closed(app: &AppHandle, session_id: &str, port: SessionPort) {
+    let _ = app.emit(
+        "session-port-closed",
+        PortEvent {
+            session_id: session_id.to_string(),
+            port,
+        },
+    );
+}
+
+/// Find `http://localhost:NNNN`-style URLs. A port number that runs to the end
+/// of `text` is skipped since the rest may arrive in the next chunk.
+fn find_local_urls(text: &str) -> Vec<(u16, String)> {
+    let mut found = Vec::new();
+    for prefix in LOCAL_URL_PREFIXES {
+        let mut offset = 0;
+        while let Some(pos) = text[offset..].find(prefix) {
+            let start = offset + pos;
+            let digits_start = start + prefix.len();
+            let digits_len = text[digits_start..]
+                .bytes()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+            let digits_end = digits_start + digits_len;
+            offset = digits_end.max(start + 1);
+
+            if digits_len == 0 || digits_end == text.len() {
+                continue;
+            }
+            let Ok(port) = text[digits_start..digits_end].parse::<u16>() else {
+                continue;
+            };
+            if port == 0 || found.iter().any(|(p, _)| *p == port) {
+                continue;
+            }
+            let url_end = text[digits_end..]
+                .find(|c: char| {
+                    c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '<' | '>' | ')')
+                })
+                .map(|i| digits_end + i)
+                .unwrap_or(text.len());
+            found.push((port, text[start..url_end].to_string()));
+        }
+    }
+    found
+}
+
+/// socket inode -> port for every TCP socket in LISTEN state.
+#[cfg(target_os = "linux")]
+fn listening_inodes() -> HashMap<u64, u16> {
+    let mut map = HashMap::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        if let Ok(data) = std::fs::read_to_string(table) {
+            parse_tcp_table(&data, &mut map);
+        }
+    }
+    map
+}
+
+/// Add the listening sockets of a `/proc/net/tcp{,6}` table to `map`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_tcp_table(data: &str, map: &mut HashMap<u64, u16>) {
+    // sl local_address rem_address st tx:rx tr:when retrnsmt uid timeout inode
+    for line in data.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 || fields[3] != "0A" {
+            continue;
+        }
+        let Some(port) = fields[1]
+            .rsplit(':')
+            .next()
+            .and_then(|p| u16::from_str_radix(p, 16).ok())
+        else {
+            continue;
+        };
+        if let Ok(inode) = fields[9].parse::<u64>() {
+            if inode != 0 {
+                map.insert(inode, port);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn listening_inodes() -> HashMap<u64, u16> {
+    HashMap::new()
+}
+
+/// port -> pid for listening sockets held by `root_pid` or its descendants.
+fn ports_for_tree(
+    root_pid: u32,
+    table: &process_tree::ProcessTable,
+    listening: &HashMap<u64, u16>,
+) -> BTreeMap<u16, u32> {
+    let mut ports = BTreeMap::new();
+    if listening.is_empty() {
+        return ports;
+    }
+    for pid in table.descendants(root_pid) {
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(inode) = socket_inode(&target.to_string_lossy()) else {
+                continue;
+            };
+            if let Some(&port) = listening.get(&inode) {
+                ports.entry(port).or_insert(pid);
+            }
+        }
+    }
+    ports
+}
+
+/// Inode of an fd link target like `socket:[12345]`.
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+#[tauri::command]
+pub fn get_session_ports(tracker: State<'_, PortTracker>, session_id: String) -> Vec<SessionPort> {
+    tracker.ports(&session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_local_urls_with_paths() {
+        let found = find_local_urls("  ➜  Local:   http://localhost:5173/app/ \n");
+        assert_eq!(
+            found,
+            vec![(5173, "http://localhost:5173/app/".to_string())]
+        );
+    }
+
+    #[test]
+    fn finds_each_port_once_across_hosts() {
+        let found = find_local_urls(
+            "http://127.0.0.1:3000 and http://localhost:3000 and https://[::1]:8443/x\n",
+        );
+        let ports: Vec<u16> = found.iter().map(|(p, _)| *p).collect();
+        assert_eq!(ports.len(), 2);
+        assert!(ports.contains(&3000));
+        assert!(ports.contains(&8443));
+    }
+
+    #[test]
+    fn stops_urls_at_quotes_and_brackets() {
+        let found = find_local_urls("open \"http://0.0.0.0:8080/\" (http://localhost:9000)\n");
+        assert!(found.contains(&(8080, "http://0.0.0.0:8080/".to_string())));
+        assert!(found.contains(&(9000, "http://localhost:9000".to_string())));
+    }
+
+    #[test]
+    fn skips_ports_cut_off_at_the_end_of_a_chunk() {
+        assert!(find_local_urls("listening on http://localhost:30").is_empty());
+        assert_eq!(
+            find_local_urls("listening on http://localhost:3000\n"),
+            vec![(3000, "http://localhost:3000".to_string())]
+        );
+    }
+
+    #[test]
+    fn ignores_invalid_ports() {
+        assert!(find_local_urls(
+            "http://localhost:0/ http://localhost:99999/ http://localhost:/\n"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn parses_listening_sockets_only() {
+        let table = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1435 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 55501 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:0BB8 0100007F:D2A4 01 00000000:00000000 00:00000000 00000000  1000        0 55502 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 0 1 0000000000000000 100 0 0 10 0
+";
+        let mut map = HashMap::new();
+        parse_tcp_table(table, &mut map);
+        assert_eq!(map, HashMap::from([(55501, 0x1435)]));
+    }
+
+    #[test]
+    fn reads_socket_inodes_from_fd_links() {
+        assert_eq!(socket_inode("socket:[55501]"), Some(55501));
+        assert_eq!(socket_inode("pipe:[55501]"), None);
+        assert_eq!(socket_inode("/dev/null"), None);
+    }
+}
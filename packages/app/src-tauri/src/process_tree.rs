@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+/// Parent/child links of every visible process, read once so several trees
+/// can be walked without rescanning `/proc`.
+pub struct ProcessTable {
+    parents: HashMap<u32, u32>,
+    children: HashMap<u32, Vec<u32>>,
+}
+
+impl ProcessTable {
+    /// Read the current process table: `/proc` on Linux, `ps` elsewhere.
+    pub fn snapshot() -> Self {
+        Self::from_parents(parent_map())
+    }
+
+    fn from_parents(parents: HashMap<u32, u32>) -> Self {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (&pid, &ppid) in &parents {
+            children.entry(ppid).or_default().push(pid);
+        }
+        Self { parents, children }
+    }
+
+    /// `root` and all of its descendant PIDs; empty if `root` doesn't exist.
+    pub fn descendants(&self, root: u32) -> Vec<u32> {
+        if !self.parents.contains_key(&root) {
+            return Vec::new();
+        }
+        let mut result = vec![root];
+        let mut i = 0;
+        while i < result.len() {
+            if let Some(kids) = self.children.get(&result[i]) {
+                for &kid in kids {
+                    if !result.contains(&kid) {
+                        result.push(kid);
+                    }
+                }
+            }
+            i += 1;
+        }
+        result
+    }
+
+    /// The key of the tree each PID belongs to, for every PID under `roots`.
+    /// A root counts as its own tree even after it has exited.
+    pub fn tree_owners<K: Clone>(&self, roots: &[(K, u32)]) -> HashMap<u32, K> {
+        let mut owners = HashMap::new();
+        for (key, root) in roots {
+            owners.insert(*root, key.clone());
+            for pid in self.descendants(*root) {
+                owners.insert(pid, key.clone());
+            }
+        }
+        owners
+    }
+}
+
+/// Return `root` and all of its descendant PIDs.
+///
+/// Reads `/proc` on Linux and falls back to `ps` elsewhere. The result is
+/// empty if `root` no longer exists.
+pub fn descendants(root: u32) -> Vec<u32> {
+    ProcessTable::snapshot().descendants(root)
+}
+
+/// Short command name of a process (e.g. "node", "bun"), if it can be read.
+pub fn process_name(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string(format!("/proc/{pid}/comm"))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let output = std::process::Command::new("ps")
+            .args(["-o", "comm=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let name = name.rsplit('/').next().unwrap_or("").to_string();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+}
+
+/// pid -> ppid for every visible process.
+#[cfg(target_os = "linux")]
+fn parent_map() -> HashMap<u32, u32> {
+    let mut map = HashMap::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return map;
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+            continue;
+        };
+        if let Some(ppid) = stat_ppid(&stat) {
+            map.insert(pid, ppid);
+        }
+    }
+    map
+}
+
+/// Parent PID from a `/proc/{pid}/stat` line.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn stat_ppid(stat: &str) -> Option<u32> {
+    // Format: pid (comm) state ppid ... — comm may contain spaces or parens
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parent_map() -> HashMap<u32, u32> {
+    let mut map = HashMap::new();
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid="])
+        .output()
+    else {
+        return map;
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(Ok(pid)), Some(Ok(ppid))) = (
+            parts.next().map(str::parse::<u32>),
+            parts.next().map(str::parse::<u32>),
+        ) {
+            map.insert(pid, ppid);
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stat_ppid_skips_comm_with_spaces_and_parens() {
+        assert_eq!(stat_ppid("1234 (node) S 1 1234 1234 0 -1"), Some(1));
+        assert_eq!(
+            stat_ppid("4321 (my (weird) proc) R 99 4321 4321 0 -1"),
+            Some(99)
+        );
+        assert_eq!(stat_ppid("garbage"), None);
+        assert_eq!(stat_ppid("5 (x) S notanumber"), None);
+    }
+
+    #[test]
+    fn descendants_walks_the_whole_tree() {
+        let table = ProcessTable::from_parents(HashMap::from([
+            (10, 1),
+            (11, 10),
+            (12, 10),
+            (13, 11),
+            (20, 1),
+        ]));
+        let mut tree = table.descendants(10);
+        tree.sort();
+        assert_eq!(tree, vec![10, 11, 12, 13]);
+        assert_eq!(table.descendants(13), vec![13]);
+    }
+
+    #[test]
+    fn descendants_of_missing_root_is_empty() {
+        let table = ProcessTable::from_parents(HashMap::from([(10, 1)]));
+        assert!(table.descendants(99).is_empty());
+    }
+
+    #[test]
+    fn tree_owners_maps_each_pid_to_its_root() {
+        let table = ProcessTable::from_parents(HashMap::from([
+            (10, 1),
+            (11, 10),
+            (13, 11),
+            (20, 1),
+            (21, 20),
+            (30, 1),
+        ]));
+        let owners = table.tree_owners(&[("a", 10), ("b", 20), ("gone", 99)]);
+        assert_eq!(owners.len(), 6);
+        assert_eq!(owners[&13], "a");
+        assert_eq!(owners[&21], "b");
+        assert_eq!(owners[&99], "gone");
+        assert!(!owners.contains_key(&30));
+        assert!(!owners.contains_key(&1));
+    }
+
+    #[test]
+    fn snapshot_contains_this_process() {
+        let me = std::process::id();
+        assert!(ProcessTable::snapshot().descendants(me).contains(&me));
+    }
+}
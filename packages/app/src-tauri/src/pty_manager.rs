@@ -1,7 +1,15 @@
+use crate::away::AwayTracker;
 use crate::config;
 use crate::daemon;
+use crate::hook_adapters;
 use crate::hook_server::HookServer;
+use crate::notifications::{self, NotificationKind};
+use crate::ports::PortTracker;
+use crate::process_tree;
+use crate::remote_tab::{self, RemoteTab};
+use crate::session_state::{self, ExitScanner, SessionStates};
 use crate::state::{AppState, LastSession, SessionInfo};
+use crate::tool_sessions;
 use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
@@ -18,12 +26,15 @@ pub struct PtySession {
 
 pub struct PtyManager {
     pub(crate) sessions: HashMap<String, PtySession>,
+    /// Tabs following daemon sessions started outside the app
+    pub(crate) remote_tabs: HashMap<String, RemoteTab>,
 }
 
 impl PtyManager {
     pub fn new() -> Self {
         Self {
             sessions: HashMap::new(),
+            remote_tabs: HashMap::new(),
         }
     }
 }
@@ -61,19 +72,27 @@ pub fn spawn_session(
     } else {
         return Err("Cannot find touchgrass binary".to_string());
     };
+    // Codex has no hooks; its notify program reports finished turns instead
+    let tool_args = if command.split_whitespace().next() == Some("codex") {
+        hook_adapters::codex_notify_args()
+    } else {
+        String::new()
+    };
     let effective_command = if let Some(ref ch) = channel {
         // Strip "type:" prefix (e.g. "telegram:Dev2" → "Dev2") for the CLI --channel flag
         let ch_flag = if let Some(idx) = ch.find(':') { &ch[idx + 1..] } else { ch.as_str() };
-        format!("{tg_path} {command} --channel '{ch_flag}'")
+        format!("{tg_path} {command}{tool_args} --channel '{ch_flag}'")
     } else {
-        format!("{tg_path} {command}")
+        format!("{tg_path} {command}{tool_args}")
     };
 
     // Spawn an interactive shell that runs the command, then stays open
-    // so the user can continue typing after the process exits
+    // so the user can continue typing after the process exits. The shell
+    // outlives the tool, so it reports the tool's exit status on the way.
     let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+    let exit_report = session_state::exit_report_command(&shell);
     let mut cmd = CommandBuilder::new(&shell);
-    cmd.args(["-i", "-c", &format!("{}; exec {}", effective_command, shell)]);
+    cmd.args(["-i", "-c", &format!("{effective_command}; {exit_report}; exec {shell}")]);
     cmd.cwd(&cwd);
 
     // Strip CLAUDECODE to prevent
@@ -87,9 +106,13 @@ pub fn spawn_session(
         Some(false) => cmd.env("COLORFGBG", "0;15"),          // black on white
     };
 
-    // Pass hook server port so the Claude Code hook script can POST events to the app
+    // Pass hook server port and secret so the Claude Code hook script can POST events to the app
     if let Some(hook_server) = app.try_state::<HookServer>() {
         cmd.env("TOUCHGRASS_APP_PORT", hook_server.port.to_string());
+        cmd.env("TOUCHGRASS_APP_AUTH", &hook_server.secret);
+        if let Some(sock) = &hook_server.socket_path {
+            cmd.env("TOUCHGRASS_APP_SOCK", sock);
+        }
         cmd.env("TOUCHGRASS_SESSION_ID", &session_id);
     }
 
@@ -101,6 +124,10 @@ pub fn spawn_session(
     // Drop slave — we only need the master side
     drop(pair.slave);
 
+    app.state::<PortTracker>().register(&session_id, child.process_id());
+    app.state::<SessionStates>().register(&app, &session_id);
+    app.state::<AwayTracker>().register(&session_id);
+
     let writer = pair
         .master
         .take_writer()
@@ -117,20 +144,30 @@ pub fn spawn_session(
 
     let reader_handle = std::thread::spawn(move || {
         let mut buf = [0u8; 4096];
+        let mut exit_scanner = ExitScanner::default();
         loop {
             match reader.read(&mut buf) {
                 Ok(0) => {
                     // PTY closed — emit exit event
-                    let _ = app_for_reader.emit(&format!("pty-exit-{}", sid), ());
+                    on_pty_closed(&app_for_reader, &sid);
                     break;
                 }
                 Ok(n) => {
                     let data = &buf[..n];
+                    app_for_reader
+                        .state::<PortTracker>()
+                        .scan_output(&app_for_reader, &sid, data);
+                    app_for_reader
+                        .state::<SessionStates>()
+                        .on_output(&app_for_reader, &sid, n);
+                    if let Some(code) = exit_scanner.scan(data) {
+                        on_session_exit(&app_for_reader, &sid, Some(code));
+                    }
                     // Send as Vec<u8> which Tauri serializes as array of numbers
                     let _ = app_for_reader.emit(&event_name, data.to_vec());
                 }
                 Err(_) => {
-                    let _ = app_for_reader.emit(&format!("pty-exit-{}", sid), ());
+                    on_pty_closed(&app_for_reader, &sid);
                     break;
                 }
             }
@@ -144,6 +181,7 @@ pub fn spawn_session(
         command,
         channel,
         tool_session_id: None,
+        remote_id: None,
     };
 
     let session = PtySession {
@@ -176,15 +214,29 @@ pub fn spawn_session(
         config::save_state(&state);
     }
 
+    // Codex and Gemini don't send hook events; find their session file instead
+    tool_sessions::watch(app.clone(), info.id.clone(), info.command.clone(), cwd);
+
     Ok(info)
 }
 
 #[tauri::command]
 pub fn write_to_session(
+    app: AppHandle,
     pty_mgr: tauri::State<'_, PtyManagerMutex>,
     session_id: String,
     data: String,
 ) -> Result<(), String> {
+    if pty_mgr
+        .lock()
+        .unwrap()
+        .remote_tabs
+        .contains_key(&session_id)
+    {
+        return remote_tab::write_input(&app, &session_id, &data);
+    }
+    app.state::<SessionStates>().on_input(&session_id);
+    app.state::<AwayTracker>().note_input(&app, &session_id);
     let mut mgr = pty_mgr.lock().unwrap();
     if let Some(session) = mgr.sessions.get_mut(&session_id) {
         session
@@ -203,12 +255,18 @@ pub fn write_to_session(
 
 #[tauri::command]
 pub fn resize_session(
+    app: AppHandle,
     pty_mgr: tauri::State<'_, PtyManagerMutex>,
     session_id: String,
     cols: u16,
     rows: u16,
 ) -> Result<(), String> {
-    let mgr = pty_mgr.lock().unwrap();
+    let mut mgr = pty_mgr.lock().unwrap();
+    // Remote sessions keep the size of the terminal they were started in
+    if let Some(tab) = mgr.remote_tabs.get_mut(&session_id) {
+        tab.on_resize(&app);
+        return Ok(());
+    }
     if let Some(session) = mgr.sessions.get(&session_id) {
         session
             .master
@@ -232,6 +290,11 @@ pub fn kill_session(
     session_id: String,
 ) -> Result<(), String> {
     let mut mgr = pty_mgr.lock().unwrap();
+    // Closing a remote tab leaves the session itself running
+    if let Some(tab) = mgr.remote_tabs.remove(&session_id) {
+        tab.detach();
+        return Ok(());
+    }
     if let Some(mut session) = mgr.sessions.remove(&session_id) {
         // Send SIGTERM to the process group so touchgrass can cleanly exit
         // and call /remote/{id}/exit on the daemon
@@ -258,6 +321,12 @@ pub fn kill_session(
         });
     }
 
+    app.state::<PortTracker>().unregister(&app, &session_id);
+    let states = app.state::<SessionStates>();
+    states.on_exit(&app, &session_id, None);
+    states.remove(&session_id);
+    app.state::<AwayTracker>().remove(&session_id);
+
     // Remove from persisted sessions
     let app_state: tauri::State<'_, Mutex<AppState>> = app.state();
     {
@@ -277,8 +346,10 @@ pub fn list_sessions(
     let mgr = pty_mgr.lock().unwrap();
     mgr.sessions
         .values()
-        .filter(|s| s.info.project_id == project_id)
-        .map(|s| s.info.clone())
+        .map(|s| &s.info)
+        .chain(mgr.remote_tabs.values().map(|t| &t.info))
+        .filter(|info| info.project_id == project_id)
+        .cloned()
         .collect()
 }
 
@@ -320,6 +391,8 @@ pub fn rename_session(
     let mut mgr = pty_mgr.lock().unwrap();
     if let Some(session) = mgr.sessions.get_mut(&session_id) {
         session.info.label = label.clone();
+    } else if let Some(tab) = mgr.remote_tabs.get_mut(&session_id) {
+        tab.info.label = label.clone();
     }
     drop(mgr);
     // Update saved session
@@ -338,18 +411,35 @@ pub fn set_tool_session_id(
     session_id: String,
     tool_session_id: String,
 ) -> Result<(), String> {
+    record_tool_session_id(&app, &session_id, &tool_session_id);
+    Ok(())
+}
+
+/// Store the underlying tool's session ID (for resume) on the live session,
+/// its saved entry and the project's last session. Only saves when it changed.
+pub(crate) fn record_tool_session_id(app: &AppHandle, session_id: &str, tool_session_id: &str) {
+    {
+        let pty_mgr = app.state::<PtyManagerMutex>();
+        let mut mgr = pty_mgr.lock().unwrap();
+        if let Some(session) = mgr.sessions.get_mut(session_id) {
+            session.info.tool_session_id = Some(tool_session_id.to_string());
+        }
+    }
+
     let app_state: tauri::State<'_, Mutex<AppState>> = app.state();
     let mut state = app_state.lock().unwrap();
     if let Some(s) = state.saved_sessions.iter_mut().find(|s| s.id == session_id) {
-        s.tool_session_id = Some(tool_session_id.clone());
+        if s.tool_session_id.as_deref() == Some(tool_session_id) {
+            return;
+        }
+        s.tool_session_id = Some(tool_session_id.to_string());
         let project_id = s.project_id.clone();
         // Also update the last_sessions entry for this project
         if let Some(last) = state.last_sessions.get_mut(&project_id) {
-            last.tool_session_id = Some(tool_session_id);
+            last.tool_session_id = Some(tool_session_id.to_string());
         }
         config::save_state(&state);
     }
-    Ok(())
 }
 
 #[tauri::command]
@@ -361,3 +451,96 @@ pub fn get_last_session(
     let state = app_state.lock().unwrap();
     state.last_sessions.get(&project_id).cloned()
 }
+
+fn on_pty_closed(app: &AppHandle, session_id: &str) {
+    app.state::<PortTracker>().unregister(app, session_id);
+    app.state::<AwayTracker>().remove(session_id);
+    // Normally the tool's own exit was reported already; the shell's status
+    // only counts if the shell went first
+    let exit_code = wait_for_exit_code(app, session_id);
+    on_session_exit(app, session_id, exit_code);
+    let _ = app.emit(&format!("pty-exit-{}", session_id), ());
+}
+
+/// Record the session's exit and notify if it failed, once per session.
+fn on_session_exit(app: &AppHandle, session_id: &str, exit_code: Option<u32>) {
+    if !app.state::<SessionStates>().on_exit(app, session_id, exit_code) {
+        return;
+    }
+    if let Some(code) = exit_code.filter(|c| *c != 0) {
+        notifications::notify(
+            app,
+            session_id,
+            NotificationKind::Crashed,
+            &format!("Exited with status {code}"),
+        );
+    }
+}
+
+/// Exit code of a session whose PTY just closed. The shell may take a moment
+/// to be reaped, so poll briefly. None if it was killed from the app.
+fn wait_for_exit_code(app: &AppHandle, session_id: &str) -> Option<u32> {
+    for _ in 0..10 {
+        {
+            let pty_mgr = app.state::<PtyManagerMutex>();
+            let mut mgr = pty_mgr.lock().unwrap();
+            let session = mgr.sessions.get_mut(session_id)?;
+            if let Ok(Some(status)) = session.child.try_wait() {
+                return Some(status.exit_code());
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    None
+}
+
+/// App session whose process tree runs the daemon session `remote_id`
+/// (matched via the pid in the CLI's session manifest).
+pub(crate) fn session_for_remote(app: &AppHandle, remote_id: &str) -> Option<String> {
+    let pid = daemon::read_session_manifest(remote_id)?.pid;
+    let roots = session_roots(app);
+    let table = process_tree::ProcessTable::snapshot();
+    roots
+        .into_iter()
+        .find(|(_, root)| *root == pid || table.descendants(*root).contains(&pid))
+        .map(|(id, _)| id)
+}
+
+/// Daemon session -> app session for every daemon session running in an app
+/// session's process tree, from one process snapshot and manifest scan.
+pub(crate) fn sessions_by_remote(app: &AppHandle) -> HashMap<String, String> {
+    let roots = session_roots(app);
+    if roots.is_empty() {
+        return HashMap::new();
+    }
+    let owners = process_tree::ProcessTable::snapshot().tree_owners(&roots);
+    daemon::list_session_manifests()
+        .into_iter()
+        .filter_map(|m| Some((m.id, owners.get(&m.pid)?.clone())))
+        .collect()
+}
+
+/// Each app session with the PID of its root process.
+fn session_roots(app: &AppHandle) -> Vec<(String, u32)> {
+    let pty_mgr = app.state::<PtyManagerMutex>();
+    let mgr = pty_mgr.lock().unwrap();
+    mgr.sessions
+        .iter()
+        .filter_map(|(id, s)| s.child.process_id().map(|root| (id.clone(), root)))
+        .collect()
+}
+
+/// Daemon session ("r-...") running inside an app session's process tree.
+pub(crate) fn remote_for_session(app: &AppHandle, session_id: &str) -> Option<String> {
+    let root = {
+        let pty_mgr = app.state::<PtyManagerMutex>();
+        let mgr = pty_mgr.lock().unwrap();
+        mgr.sessions.get(session_id)?.child.process_id()?
+    };
+    let mut tree = process_tree::descendants(root);
+    tree.push(root);
+    daemon::list_session_manifests()
+        .into_iter()
+        .find(|m| tree.contains(&m.pid))
+        .map(|m| m.id)
+}
@@ -0,0 +1,311 @@
+use crate::daemon::{self, DaemonSessionsResponse, SimpleResponse};
+use crate::daemon_client::{DaemonHandle, DaemonStream};
+use crate::daemon_stream;
+use crate::pty_manager::{self, PtyManagerMutex};
+use crate::state::SessionInfo;
+use serde::Deserialize;
+use std::net::Shutdown;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Output held for a terminal that isn't listening yet is capped at this;
+/// the oldest lines go first.
+const MAX_PENDING_BYTES: usize = 256 * 1024;
+
+/// A tab following a daemon session that was started outside the app. It
+/// looks like a PTY tab to the frontend, but output comes from the daemon's
+/// transcript stream and input is sent a line at a time through the daemon.
+pub struct RemoteTab {
+    pub info: SessionInfo,
+    /// Input typed since the last Enter
+    line: String,
+    /// Output held back until the terminal is listening for it
+    pending: Option<Vec<u8>>,
+    /// The output stream's connection, shut down to detach
+    stream: DaemonStream,
+}
+
+impl RemoteTab {
+    /// The terminal sends its first resize once it's listening, so anything
+    /// received before then is flushed here.
+    pub(crate) fn on_resize(&mut self, app: &AppHandle) {
+        if let Some(pending) = self.pending.take() {
+            let _ = app.emit(&format!("pty-output-{}", self.info.id), pending);
+        }
+    }
+
+    /// Stop following the session. It keeps running wherever it was started.
+    pub(crate) fn detach(self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// One line of the session's transcript, as sent by `/session/:id/stream`.
+#[derive(Deserialize)]
+struct OutputEntry {
+    role: String,
+    text: String,
+}
+
+/// Open a tab following a daemon session started outside the app.
+#[tauri::command(async)]
+pub fn attach_remote_session(
+    app: AppHandle,
+    pty_mgr: State<'_, PtyManagerMutex>,
+    client: State<'_, DaemonHandle>,
+    project_id: String,
+    remote_id: String,
+) -> Result<SessionInfo, String> {
+    {
+        let mgr = pty_mgr.lock().unwrap();
+        if let Some(tab) = mgr
+            .remote_tabs
+            .values()
+            .find(|t| t.info.remote_id.as_deref() == Some(remote_id.as_str()))
+        {
+            return Ok(tab.info.clone());
+        }
+    }
+    if pty_manager::session_for_remote(&app, &remote_id).is_some() {
+        return Err("This session already runs in one of the app's terminals".into());
+    }
+
+    let sessions: DaemonSessionsResponse = client.api().get("/sessions")?;
+    let session = sessions
+        .sessions
+        .into_iter()
+        .find(|s| s.id == remote_id)
+        .ok_or_else(|| format!("Daemon session {remote_id} not found"))?;
+
+    let stream = daemon_stream::open(&format!(
+        "/session/{}/stream",
+        daemon::urlencoding(&remote_id)
+    ))?;
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let info = SessionInfo {
+        id: session_id.clone(),
+        project_id,
+        label: session.name.unwrap_or_else(|| session.command.clone()),
+        command: session.command,
+        channel: session.chat_id,
+        tool_session_id: None,
+        remote_id: Some(remote_id.clone()),
+    };
+    let banner = format!(
+        "\x1b[90mFollowing {remote_id} through the touchgrass daemon. Type a line and press Enter to send it.\x1b[0m\r\n"
+    );
+    let tab = RemoteTab {
+        info: info.clone(),
+        line: String::new(),
+        pending: Some(banner.into_bytes()),
+        stream: stream.closer()?,
+    };
+    pty_mgr
+        .lock()
+        .unwrap()
+        .remote_tabs
+        .insert(session_id.clone(), tab);
+
+    std::thread::spawn(move || {
+        let result = stream.read_events(|event_name, _, data| {
+            if event_name == "live" {
+                return;
+            }
+            match serde_json::from_str::<OutputEntry>(data) {
+                Ok(entry) => output(&app, &session_id, &format_entry(&entry)),
+                Err(e) => log::warn!("Invalid session output from daemon: {e}"),
+            }
+        });
+        if let Err(e) = result {
+            log::debug!("Remote session stream for {remote_id}: {e}");
+        }
+        // Still open means the daemon ended the stream, not the user
+        output(
+            &app,
+            &session_id,
+            "\r\n\x1b[90m[Disconnected from the daemon]\x1b[0m\r\n",
+        );
+        let tab = app
+            .state::<PtyManagerMutex>()
+            .lock()
+            .unwrap()
+            .remote_tabs
+            .remove(&session_id);
+        if tab.is_some() {
+            let _ = app.emit(&format!("pty-exit-{session_id}"), ());
+        }
+    });
+
+    Ok(info)
+}
+
+/// Daemon session followed by a remote tab.
+pub(crate) fn remote_id(app: &AppHandle, session_id: &str) -> Option<String> {
+    let pty_mgr = app.state::<PtyManagerMutex>();
+    let mgr = pty_mgr.lock().unwrap();
+    mgr.remote_tabs.get(session_id)?.info.remote_id.clone()
+}
+
+/// Handle keystrokes for a remote tab: echo them, and send each completed
+/// line to the session as input.
+pub(crate) fn write_input(app: &AppHandle, session_id: &str, data: &str) -> Result<(), String> {
+    // Arrow keys and terminal queries can't be edited into a line
+    if data.starts_with('\x1b') {
+        return Ok(());
+    }
+    let (echo, lines, remote_id) = {
+        let pty_mgr = app.state::<PtyManagerMutex>();
+        let mut mgr = pty_mgr.lock().unwrap();
+        let tab = mgr
+            .remote_tabs
+            .get_mut(session_id)
+            .ok_or("Session not found")?;
+        let mut echo = String::new();
+        let mut lines = Vec::new();
+        let mut after_cr = false;
+        for c in data.chars() {
+            match c {
+                // Pasted text may end lines with CRLF
+                '\n' if after_cr => {}
+                '\r' | '\n' => {
+                    echo.push_str("\r\n");
+                    let line = std::mem::take(&mut tab.line);
+                    if !line.trim().is_empty() {
+                        lines.push(line);
+                    }
+                }
+                '\x7f' | '\x08' => {
+                    if tab.line.pop().is_some() {
+                        echo.push_str("\x08 \x08");
+                    }
+                }
+                '\x03' => {
+                    tab.line.clear();
+                    echo.push_str("^C\r\n");
+                }
+                c if c.is_control() => {}
+                c => {
+                    tab.line.push(c);
+                    echo.push(c);
+                }
+            }
+            after_cr = c == '\r';
+        }
+        (echo, lines, tab.info.remote_id.clone().unwrap_or_default())
+    };
+    if !echo.is_empty() {
+        output(app, session_id, &echo);
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    // Keystrokes shouldn't wait on the daemon, which may need starting first
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    std::thread::spawn(move || send_lines(&app, &session_id, &remote_id, lines));
+    Ok(())
+}
+
+/// Send completed lines to the daemon session in order, stopping at the first
+/// one that isn't accepted.
+fn send_lines(app: &AppHandle, session_id: &str, remote_id: &str, lines: Vec<String>) {
+    let path = format!("/remote/{}/send-input", daemon::urlencoding(remote_id));
+    for line in lines {
+        let sent: Result<SimpleResponse, String> = app
+            .state::<DaemonHandle>()
+            .api()
+            .post(&path, &serde_json::json!({ "text": line }))
+            .map_err(String::from);
+        let error = match sent {
+            Ok(resp) if resp.ok => continue,
+            Ok(_) => "Session did not accept the input".to_string(),
+            Err(e) => e,
+        };
+        output(
+            app,
+            session_id,
+            &format!("\x1b[31m[Not sent: {error}]\x1b[0m\r\n"),
+        );
+        return;
+    }
+}
+
+/// Show text in the tab, or hold it until the terminal is ready.
+fn output(app: &AppHandle, session_id: &str, text: &str) {
+    let pty_mgr = app.state::<PtyManagerMutex>();
+    let mut mgr = pty_mgr.lock().unwrap();
+    let Some(tab) = mgr.remote_tabs.get_mut(session_id) else {
+        return;
+    };
+    match &mut tab.pending {
+        Some(pending) => hold(pending, text.as_bytes()),
+        None => {
+            let _ = app.emit(
+                &format!("pty-output-{session_id}"),
+                text.as_bytes().to_vec(),
+            );
+        }
+    }
+}
+
+/// Add output to what's held for the terminal, dropping the oldest lines
+/// beyond `MAX_PENDING_BYTES`.
+fn hold(pending: &mut Vec<u8>, text: &[u8]) {
+    pending.extend_from_slice(text);
+    if pending.len() <= MAX_PENDING_BYTES {
+        return;
+    }
+    // Cut at a line start so no escape sequence or character is split
+    let over = pending.len() - MAX_PENDING_BYTES;
+    let cut = pending[over..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(pending.len(), |i| over + i + 1);
+    pending.drain(..cut);
+}
+
+/// Render a transcript entry for the terminal.
+fn format_entry(entry: &OutputEntry) -> String {
+    let text = entry.text.trim_end().replace('\n', "\r\n");
+    match entry.role.as_str() {
+        "assistant" => format!("\r\n{text}\r\n"),
+        "user" => format!("\r\n\x1b[36m> {text}\x1b[0m\r\n"),
+        "tool" => format!("\x1b[33m● {text}\x1b[0m\r\n"),
+        "thinking" => format!("\x1b[2;3m{text}\x1b[0m\r\n"),
+        _ => format!("\x1b[90m[{text}]\x1b[0m\r\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hold_keeps_output_under_the_limit() {
+        let mut pending = b"banner\r\n".to_vec();
+        hold(&mut pending, b"one\r\n");
+        assert_eq!(pending, b"banner\r\none\r\n");
+    }
+
+    #[test]
+    fn hold_drops_the_oldest_whole_lines() {
+        let line = format!("{}\r\n", "x".repeat(1022));
+        let mut pending = Vec::new();
+        for _ in 0..MAX_PENDING_BYTES / line.len() {
+            hold(&mut pending, line.as_bytes());
+        }
+        assert_eq!(pending.len(), MAX_PENDING_BYTES);
+
+        hold(&mut pending, b"last\r\n");
+        assert!(pending.len() <= MAX_PENDING_BYTES);
+        assert!(pending.starts_with(line.as_bytes()));
+        assert!(pending.ends_with(b"last\r\n"));
+    }
+
+    #[test]
+    fn hold_drops_everything_for_one_huge_line() {
+        let mut pending = b"banner\r\n".to_vec();
+        hold(&mut pending, &vec![b'x'; MAX_PENDING_BYTES + 1]);
+        assert!(pending.is_empty());
+    }
+}
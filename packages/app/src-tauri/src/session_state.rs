@@ -0,0 +1,616 @@
+use crate::hook_event::{HookEvent, HookPayload};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often sessions are checked for having gone quiet.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A running session with no output for this long is considered idle.
+const IDLE_AFTER: Duration = Duration::from_secs(3);
+
+// Sessions without hook events are judged by their output. Only sustained
+// output counts as work: TUI redraws finish in well under a second.
+const BUSY_WINDOW: Duration = Duration::from_secs(5);
+const BUSY_MIN_SPAN: Duration = Duration::from_millis(2500);
+const BUSY_MIN_CHUNKS: usize = 10;
+const BUSY_MIN_BYTES: usize = 2000;
+
+/// Output right after the user typed is mostly echo.
+const INPUT_SUPPRESS: Duration = Duration::from_millis(1500);
+
+/// The PTY's shell keeps running after the tool exits, so the wrapper prints
+/// the tool's exit status as this OSC sequence, ended by BEL. Terminals drop
+/// OSC codes they don't know, so it never shows.
+const EXIT_REPORT_OSC: &[u8] = b"\x1b]7717;touchgrass-exit=";
+/// Room for the status digits and BEL after the prefix.
+const EXIT_REPORT_MAX_LEN: usize = EXIT_REPORT_OSC.len() + 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleState {
+    Starting,
+    Running,
+    AwaitingPermission,
+    AwaitingInput,
+    Idle,
+    Exited,
+    Crashed,
+}
+
+impl LifecycleState {
+    fn is_terminal(self) -> bool {
+        matches!(self, LifecycleState::Exited | LifecycleState::Crashed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionLifecycle {
+    pub session_id: String,
+    pub state: LifecycleState,
+    pub previous: Option<LifecycleState>,
+    /// Unix epoch milliseconds when the current state was entered
+    pub since: u64,
+    /// Milliseconds spent in each state, including the current one
+    pub durations: BTreeMap<LifecycleState, u64>,
+    /// Exit code once the session has ended, if known
+    pub exit_code: Option<u32>,
+}
+
+struct Tracked {
+    state: LifecycleState,
+    previous: Option<LifecycleState>,
+    entered: Instant,
+    entered_ms: u64,
+    durations: BTreeMap<LifecycleState, u64>,
+    exit_code: Option<u32>,
+    /// Hook events drive this session; output alone doesn't change its state
+    hook_driven: bool,
+    last_output: Option<Instant>,
+    last_input: Option<Instant>,
+    recent_chunks: VecDeque<(Instant, usize)>,
+}
+
+impl Tracked {
+    fn new(now: Instant) -> Self {
+        Self {
+            state: LifecycleState::Starting,
+            previous: None,
+            entered: now,
+            entered_ms: now_ms(),
+            durations: BTreeMap::new(),
+            exit_code: None,
+            hook_driven: false,
+            last_output: None,
+            last_input: None,
+            recent_chunks: VecDeque::new(),
+        }
+    }
+
+    /// Move to `next`, returning true if the state changed.
+    fn transition(&mut self, next: LifecycleState, now: Instant) -> bool {
+        if self.state == next || self.state.is_terminal() {
+            return false;
+        }
+        let elapsed = now.saturating_duration_since(self.entered).as_millis() as u64;
+        *self.durations.entry(self.state).or_insert(0) += elapsed;
+        self.previous = Some(self.state);
+        self.state = next;
+        self.entered = now;
+        self.entered_ms = now_ms();
+        true
+    }
+
+    fn snapshot(&self, session_id: &str, now: Instant) -> SessionLifecycle {
+        let mut durations = self.durations.clone();
+        *durations.entry(self.state).or_insert(0) +=
+            now.saturating_duration_since(self.entered).as_millis() as u64;
+        SessionLifecycle {
+            session_id: session_id.to_string(),
+            state: self.state,
+            previous: self.previous,
+            since: self.entered_ms,
+            durations,
+            exit_code: self.exit_code,
+        }
+    }
+
+    /// Whether recent output looks like the tool working rather than echo or
+    /// a redraw.
+    fn sustained_output(&mut self, now: Instant) -> bool {
+        while let Some(&(ts, _)) = self.recent_chunks.front() {
+            if now.duration_since(ts) > BUSY_WINDOW {
+                self.recent_chunks.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self
+            .last_input
+            .is_some_and(|t| now.duration_since(t) < INPUT_SUPPRESS)
+        {
+            return false;
+        }
+        let (Some(first), Some(last)) = (self.recent_chunks.front(), self.recent_chunks.back())
+        else {
+            return false;
+        };
+        let bytes: usize = self.recent_chunks.iter().map(|(_, n)| n).sum();
+        last.0.duration_since(first.0) >= BUSY_MIN_SPAN
+            && self.recent_chunks.len() >= BUSY_MIN_CHUNKS
+            && bytes >= BUSY_MIN_BYTES
+    }
+
+    fn hook_event(
+        &mut self,
+        next: Option<LifecycleState>,
+        hook_driven: bool,
+        now: Instant,
+    ) -> bool {
+        if hook_driven && !self.hook_driven {
+            self.hook_driven = true;
+            self.recent_chunks.clear();
+        }
+        next.is_some_and(|state| self.transition(state, now))
+    }
+
+    fn output(&mut self, bytes: usize, now: Instant) -> bool {
+        self.last_output = Some(now);
+        // Only output-driven sessions look at recent chunks
+        if !self.hook_driven {
+            self.recent_chunks.push_back((now, bytes));
+        }
+        if self.state == LifecycleState::Starting {
+            return self.transition(LifecycleState::Running, now);
+        }
+        if self.hook_driven {
+            return false;
+        }
+        self.sustained_output(now) && self.transition(LifecycleState::Running, now)
+    }
+
+    /// The first exit wins: a later one (the shell closing after the tool
+    /// exited) doesn't replace its state or code.
+    fn exit(&mut self, exit_code: Option<u32>, now: Instant) -> bool {
+        let next = match exit_code {
+            Some(code) if code != 0 => LifecycleState::Crashed,
+            _ => LifecycleState::Exited,
+        };
+        if !self.transition(next, now) {
+            return false;
+        }
+        self.exit_code = exit_code;
+        true
+    }
+
+    /// Running without hooks and without output for a while.
+    fn is_quiet(&self, now: Instant) -> bool {
+        self.state == LifecycleState::Running
+            && !self.hook_driven
+            && self
+                .last_output
+                .map_or(true, |o| now.saturating_duration_since(o) >= IDLE_AFTER)
+    }
+}
+
+/// Lifecycle state a hook event moves its session to, if any.
+fn state_for_hook(payload: &HookPayload) -> Option<LifecycleState> {
+    match payload {
+        HookPayload::SessionStart(_) => Some(LifecycleState::Idle),
+        HookPayload::UserPromptSubmit(_)
+        | HookPayload::PreToolUse(_)
+        | HookPayload::PostToolUse(_)
+        | HookPayload::PreCompact(_) => Some(LifecycleState::Running),
+        HookPayload::PermissionRequest(_) => Some(LifecycleState::AwaitingPermission),
+        HookPayload::Notification(n) => {
+            let permission = n.notification_type.as_deref() == Some("permission_prompt")
+                || n.message.to_lowercase().contains("permission");
+            Some(if permission {
+                LifecycleState::AwaitingPermission
+            } else {
+                LifecycleState::AwaitingInput
+            })
+        }
+        HookPayload::Stop(_) => Some(LifecycleState::Idle),
+        // A subagent finishing doesn't end the main turn
+        HookPayload::SubagentStop(_) | HookPayload::Unknown(_) => None,
+    }
+}
+
+/// Shell command that prints the previous command's exit status as the exit
+/// report `ExitScanner` looks for.
+pub fn exit_report_command(shell: &str) -> String {
+    let fish = std::path::Path::new(shell)
+        .file_name()
+        .is_some_and(|name| name == "fish");
+    let status = if fish { "$status" } else { "$?" };
+    format!(r"printf '\033]7717;touchgrass-exit=%d\007' {status}")
+}
+
+/// Finds the exit report in PTY output, which may split it across reads.
+#[derive(Default)]
+pub struct ExitScanner {
+    /// End of the previous read, in case a report starts there
+    carry: Vec<u8>,
+}
+
+impl ExitScanner {
+    /// Exit status reported in `data`, if a complete report ends in it.
+    pub fn scan(&mut self, data: &[u8]) -> Option<u32> {
+        if self.carry.is_empty() && !data.contains(&0x1b) {
+            return None;
+        }
+        let mut buf = std::mem::take(&mut self.carry);
+        buf.extend_from_slice(data);
+
+        let mut from = 0;
+        while let Some(i) = find(&buf[from..], EXIT_REPORT_OSC) {
+            let start = from + i;
+            let digits = &buf[start + EXIT_REPORT_OSC.len()..];
+            match digits.iter().position(|&b| b == 0x07) {
+                Some(end) => {
+                    let code = std::str::from_utf8(&digits[..end])
+                        .ok()
+                        .and_then(|d| d.parse().ok());
+                    if code.is_some() {
+                        return code;
+                    }
+                    from = start + 1;
+                }
+                None if buf.len() - start <= EXIT_REPORT_MAX_LEN => {
+                    self.carry = buf[start..].to_vec();
+                    return None;
+                }
+                None => from = start + 1,
+            }
+        }
+        // Keep a tail long enough to hold the start of a split report
+        let keep = buf.len().min(EXIT_REPORT_OSC.len() - 1);
+        self.carry = buf[buf.len() - keep..].to_vec();
+        None
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Per-session lifecycle, driven by hook events, PTY output and process exit.
+pub struct SessionStates {
+    sessions: Mutex<HashMap<String, Tracked>>,
+}
+
+impl SessionStates {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn register(&self, app: &AppHandle, session_id: &str) {
+        let snapshot = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let now = Instant::now();
+            let tracked = Tracked::new(now);
+            let snapshot = tracked.snapshot(session_id, now);
+            sessions.insert(session_id.to_string(), tracked);
+            snapshot
+        };
+        let _ = app.emit("session-state-changed", snapshot);
+    }
+
+    pub fn remove(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// Apply `f` to a session and emit `session-state-changed` if it moved.
+    /// Returns whether it did.
+    fn update(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        f: impl FnOnce(&mut Tracked, Instant) -> bool,
+    ) -> bool {
+        let snapshot = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let Some(tracked) = sessions.get_mut(session_id) else {
+                return false;
+            };
+            let now = Instant::now();
+            if !f(tracked, now) {
+                return false;
+            }
+            tracked.snapshot(session_id, now)
+        };
+        log::debug!(
+            "Session {session_id}: {:?} -> {:?}",
+            snapshot.previous,
+            snapshot.state
+        );
+        let _ = app.emit("session-state-changed", snapshot);
+        true
+    }
+
+    pub fn on_hook_event(&self, app: &AppHandle, event: &HookEvent) {
+        let next = state_for_hook(&event.payload);
+        let hook_driven = event.source.reports_lifecycle();
+        self.update(app, &event.session_id, |t, now| {
+            t.hook_event(next, hook_driven, now)
+        });
+    }
+
+    /// Held in the app waiting for the user to approve a tool call.
+    pub fn set_awaiting_permission(&self, app: &AppHandle, session_id: &str, waiting: bool) {
+        let next = if waiting {
+            LifecycleState::AwaitingPermission
+        } else {
+            LifecycleState::Running
+        };
+        self.update(app, session_id, |t, now| t.transition(next, now));
+    }
+
+    pub fn on_output(&self, app: &AppHandle, session_id: &str, bytes: usize) {
+        self.update(app, session_id, |t, now| t.output(bytes, now));
+    }
+
+    pub fn on_input(&self, session_id: &str) {
+        if let Some(t) = self.sessions.lock().unwrap().get_mut(session_id) {
+            t.last_input = Some(Instant::now());
+        }
+    }
+
+    /// The tool (or, failing that, the PTY's shell) ended. `None` means it
+    /// was closed from the app. Returns whether this ended the session, so
+    /// only the first exit is reported.
+    pub fn on_exit(&self, app: &AppHandle, session_id: &str, exit_code: Option<u32>) -> bool {
+        self.update(app, session_id, |t, now| t.exit(exit_code, now))
+    }
+
+    /// Running sessions that have gone quiet become idle. Hook-driven sessions
+    /// wait for Stop instead, since tools can run silently for a long time.
+    fn tick(&self, app: &AppHandle) {
+        let quiet: Vec<String> = {
+            let now = Instant::now();
+            let sessions = self.sessions.lock().unwrap();
+            sessions
+                .iter()
+                .filter(|(_, t)| t.is_quiet(now))
+                .map(|(id, _)| id.clone())
+                .collect()
+        };
+        for id in quiet {
+            self.update(app, &id, |t, now| t.transition(LifecycleState::Idle, now));
+        }
+    }
+
+    fn list(&self) -> Vec<SessionLifecycle> {
+        let sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+        sessions.iter().map(|(id, t)| t.snapshot(id, now)).collect()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Start the background thread that moves quiet sessions to idle.
+pub fn start_ticking(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+        app.state::<SessionStates>().tick(&app);
+    });
+}
+
+#[tauri::command]
+pub fn get_session_states(states: State<'_, SessionStates>) -> Vec<SessionLifecycle> {
+    states.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hook_event::{Notification, Stop, ToolUse};
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    fn notification(message: &str, notification_type: Option<&str>) -> HookPayload {
+        HookPayload::Notification(Notification {
+            message: message.into(),
+            notification_type: notification_type.map(String::from),
+        })
+    }
+
+    #[test]
+    fn durations_add_up_per_state() {
+        let t0 = Instant::now();
+        let mut t = Tracked::new(t0);
+        assert!(t.transition(LifecycleState::Running, t0 + secs(2)));
+        assert!(t.transition(LifecycleState::Idle, t0 + secs(5)));
+        assert!(t.transition(LifecycleState::Running, t0 + secs(6)));
+        assert!(!t.transition(LifecycleState::Running, t0 + secs(7)));
+
+        let snapshot = t.snapshot("s1", t0 + secs(10));
+        assert_eq!(snapshot.state, LifecycleState::Running);
+        assert_eq!(snapshot.previous, Some(LifecycleState::Idle));
+        assert_eq!(snapshot.durations[&LifecycleState::Starting], 2000);
+        assert_eq!(snapshot.durations[&LifecycleState::Idle], 1000);
+        // 3s earlier plus 4s in the current stretch
+        assert_eq!(snapshot.durations[&LifecycleState::Running], 7000);
+    }
+
+    #[test]
+    fn the_first_exit_is_final() {
+        let t0 = Instant::now();
+        let mut t = Tracked::new(t0);
+        assert!(t.exit(Some(2), t0));
+        assert_eq!(t.state, LifecycleState::Crashed);
+        // The shell closing later doesn't overwrite the tool's status
+        assert!(!t.exit(Some(0), t0 + secs(1)));
+        assert!(!t.exit(None, t0 + secs(1)));
+        assert_eq!(t.exit_code, Some(2));
+        assert!(!t.transition(LifecycleState::Running, t0 + secs(1)));
+        assert!(!t.output(100, t0 + secs(1)));
+        assert!(!t.hook_event(Some(LifecycleState::Idle), true, t0 + secs(1)));
+        assert_eq!(t.state, LifecycleState::Crashed);
+
+        let mut clean = Tracked::new(t0);
+        assert!(clean.exit(Some(0), t0));
+        assert_eq!(clean.state, LifecycleState::Exited);
+        let mut closed = Tracked::new(t0);
+        assert!(closed.exit(None, t0));
+        assert_eq!(closed.state, LifecycleState::Exited);
+        assert_eq!(closed.exit_code, None);
+    }
+
+    #[test]
+    fn only_sustained_output_counts_as_running() {
+        let t0 = Instant::now();
+        let mut t = Tracked::new(t0);
+        // Any first output means the tool started
+        assert!(t.output(10, t0));
+        assert!(t.transition(LifecycleState::Idle, t0));
+
+        // A redraw: plenty of bytes but over too short a span
+        for i in 0..20 {
+            assert!(!t.output(500, t0 + Duration::from_millis(i * 10)));
+        }
+        assert_eq!(t.state, LifecycleState::Idle);
+
+        let start = t0 + secs(10);
+        let mut changed = false;
+        for i in 0..=10 {
+            changed = t.output(250, start + Duration::from_millis(i * 250));
+        }
+        assert!(changed);
+        assert_eq!(t.state, LifecycleState::Running);
+    }
+
+    #[test]
+    fn output_right_after_input_is_echo() {
+        let t0 = Instant::now();
+        let mut t = Tracked::new(t0);
+        t.output(10, t0);
+        t.transition(LifecycleState::Idle, t0);
+        for i in 0..10 {
+            t.output(250, t0 + Duration::from_millis(i * 250));
+        }
+        t.last_input = Some(t0 + Duration::from_millis(2400));
+        assert!(!t.output(250, t0 + Duration::from_millis(2500)));
+        assert_eq!(t.state, LifecycleState::Idle);
+    }
+
+    #[test]
+    fn hook_driven_sessions_ignore_output() {
+        let t0 = Instant::now();
+        let mut t = Tracked::new(t0);
+        assert!(t.hook_event(Some(LifecycleState::Idle), true, t0));
+        for i in 0..=20 {
+            assert!(!t.output(500, t0 + Duration::from_millis(i * 250)));
+        }
+        assert_eq!(t.state, LifecycleState::Idle);
+        assert!(t.recent_chunks.is_empty());
+
+        // Running until Stop, however quiet
+        assert!(t.hook_event(Some(LifecycleState::Running), true, t0 + secs(6)));
+        assert!(!t.is_quiet(t0 + secs(60)));
+        assert!(t.hook_event(Some(LifecycleState::Idle), true, t0 + secs(60)));
+    }
+
+    #[test]
+    fn output_driven_sessions_go_quiet() {
+        let t0 = Instant::now();
+        let mut t = Tracked::new(t0);
+        assert!(!t.is_quiet(t0 + secs(60)));
+        t.output(10, t0);
+        assert!(!t.is_quiet(t0 + IDLE_AFTER - Duration::from_millis(1)));
+        assert!(t.is_quiet(t0 + IDLE_AFTER));
+
+        // Codex reports turn ends but stays output-driven
+        assert!(t.hook_event(Some(LifecycleState::Idle), false, t0 + secs(4)));
+        assert!(!t.hook_driven);
+    }
+
+    #[test]
+    fn hook_events_map_to_states() {
+        let tool_use = || ToolUse {
+            tool_name: "Bash".into(),
+            tool_input: serde_json::json!({}),
+            tool_use_id: None,
+        };
+        let stop = || Stop {
+            stop_hook_active: false,
+        };
+        assert_eq!(
+            state_for_hook(&HookPayload::PreToolUse(tool_use())),
+            Some(LifecycleState::Running)
+        );
+        assert_eq!(
+            state_for_hook(&HookPayload::PermissionRequest(tool_use())),
+            Some(LifecycleState::AwaitingPermission)
+        );
+        assert_eq!(
+            state_for_hook(&notification(
+                "Claude is waiting",
+                Some("permission_prompt")
+            )),
+            Some(LifecycleState::AwaitingPermission)
+        );
+        assert_eq!(
+            state_for_hook(&notification(
+                "Claude needs your permission to use Bash",
+                None
+            )),
+            Some(LifecycleState::AwaitingPermission)
+        );
+        assert_eq!(
+            state_for_hook(&notification("Claude is waiting for your input", None)),
+            Some(LifecycleState::AwaitingInput)
+        );
+        assert_eq!(
+            state_for_hook(&HookPayload::Stop(stop())),
+            Some(LifecycleState::Idle)
+        );
+        assert_eq!(state_for_hook(&HookPayload::SubagentStop(stop())), None);
+        assert_eq!(
+            state_for_hook(&HookPayload::Unknown(serde_json::json!({}))),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_exit_reports_split_across_reads() {
+        let output = b"bye\r\n\x1b]7717;touchgrass-exit=130\x07$ ";
+        assert_eq!(ExitScanner::default().scan(output), Some(130));
+        for split in 1..output.len() {
+            let mut scanner = ExitScanner::default();
+            let first = scanner.scan(&output[..split]);
+            let second = scanner.scan(&output[split..]);
+            assert_eq!(first.or(second), Some(130), "split at {split}");
+        }
+
+        let mut scanner = ExitScanner::default();
+        assert_eq!(scanner.scan(b"\x1b[0m plain output"), None);
+        assert_eq!(scanner.scan(b"\x1b]7717;touchgrass-exit=x\x07"), None);
+        assert_eq!(scanner.scan(b"\x1b]7717;touchgrass-exit=0\x07"), Some(0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_reports_the_exit_status() {
+        let command = format!("(exit 3); {}", exit_report_command("/bin/sh"));
+        let out = std::process::Command::new("/bin/sh")
+            .args(["-c", &command])
+            .output()
+            .unwrap();
+        assert_eq!(ExitScanner::default().scan(&out.stdout), Some(3));
+        assert!(exit_report_command("/usr/local/bin/fish").ends_with("$status"));
+    }
+}
@@ -155,6 +155,7 @@ pub fn spawn_setup_pty(
         command,
         channel: None,
         tool_session_id: None,
+        remote_id: None,
     };
 
     let session = PtySession {
@@ -21,6 +21,19 @@ fn default_workspace_id() -> String {
     "personal".into()
 }
 
+fn default_away_minutes() -> u32 {
+    5
+}
+
+fn default_event_retention_days() -> u32 {
+    30
+}
+
+fn default_approval_timeout() -> u64 {
+    // Claude Code kills hook commands after 60s by default
+    50
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Workspace {
     pub id: String,
@@ -59,6 +72,9 @@ pub struct SessionInfo {
     /// The underlying tool's session ID (e.g. Claude Code's session ID for --resume)
     #[serde(default)]
     pub tool_session_id: Option<String>,
+    /// Daemon session shown in a read-only remote tab; None for local PTYs
+    #[serde(default)]
+    pub remote_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +85,190 @@ pub struct LastSession {
     pub tool_session_id: Option<String>,
 }
 
+/// Permission decision returned to a Claude Code hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    Deny,
+    Ask,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalSettings {
+    /// Hold PreToolUse/PermissionRequest hooks open until answered in the app
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_approval_timeout")]
+    pub timeout_secs: u64,
+    /// Applied when nobody answers within the timeout
+    #[serde(default = "default_decision")]
+    pub default_decision: Decision,
+}
+
+fn default_decision() -> Decision {
+    Decision::Ask
+}
+
+impl Default for ApprovalSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: default_approval_timeout(),
+            default_decision: default_decision(),
+        }
+    }
+}
+
+/// Declarative rule that decides PreToolUse hooks without asking anyone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub id: String,
+    /// If Some, only applies to this project
+    pub project_id: Option<String>,
+    /// Glob on tool_name (e.g. "Bash", "Edit", "*")
+    pub tool: String,
+    /// tool_input field the pattern applies to. None = the tool's primary
+    /// field (command, file_path, ...), "*" = any string in tool_input
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Glob on the field value; None matches any input
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Only match when the tool's path resolves outside the project directory
+    #[serde(default)]
+    pub outside_project: bool,
+    pub decision: Decision,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLogSettings {
+    /// Session event logs untouched for longer than this are deleted (0 = keep forever)
+    #[serde(default = "default_event_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for EventLogSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: default_event_retention_days(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationEvents {
+    /// Claude Notification hook (permission prompt, waiting for input)
+    #[serde(default = "default_true")]
+    pub attention: bool,
+    /// Claude Stop hook (turn finished)
+    #[serde(default = "default_true")]
+    pub finished: bool,
+    /// Session exited with a non-zero status
+    #[serde(default = "default_true")]
+    pub crashed: bool,
+    /// Daemon reports a session waiting for an answer
+    #[serde(default = "default_true")]
+    pub input_needed: bool,
+}
+
+impl Default for NotificationEvents {
+    fn default() -> Self {
+        Self {
+            attention: true,
+            finished: true,
+            crashed: true,
+            input_needed: true,
+        }
+    }
+}
+
+/// Local time range ("HH:MM", 24h) during which notifications are suppressed.
+/// `start` after `end` spans midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub events: NotificationEvents,
+    /// Projects that never notify
+    #[serde(default)]
+    pub muted_projects: Vec<String>,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            events: NotificationEvents::default(),
+            muted_projects: Vec::new(),
+            quiet_hours: None,
+        }
+    }
+}
+
+/// Forward prompts to the project's default channel while the user is away
+/// from the keyboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwaySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Minutes without local input before forwarding starts
+    #[serde(default = "default_away_minutes")]
+    pub idle_minutes: u32,
+}
+
+impl Default for AwaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: default_away_minutes(),
+        }
+    }
+}
+
+/// Background supervision of the touchgrass daemon.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonSettings {
+    /// Restart the daemon if it stops while sessions have channels attached
+    #[serde(default)]
+    pub keep_alive: bool,
+}
+
+/// Outbound webhook receiving hook and daemon events as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub name: String,
+    /// http:// or https:// endpoint that is POSTed to
+    pub url: String,
+    /// HMAC-SHA256 key for the X-Touchgrass-Signature header; unsigned if None
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Hook event names ("Stop", "PreToolUse", ...) or daemon event types to
+    /// send; empty sends everything
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Only events from these projects; empty sends all. Daemon events have
+    /// no project and are only sent when this is empty.
+    #[serde(default)]
+    pub project_ids: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppState {
     pub projects: Vec<Project>,
@@ -95,6 +295,27 @@ pub struct AppState {
     /// project_id -> last session info (for quick resume when all sessions are closed)
     #[serde(default)]
     pub last_sessions: HashMap<String, LastSession>,
+    /// Desktop approval mode for tool-use hooks
+    #[serde(default)]
+    pub approvals: ApprovalSettings,
+    /// Tool-use rules, evaluated in order; first match wins
+    #[serde(default)]
+    pub policy_rules: Vec<PolicyRule>,
+    /// Retention for ~/.touchgrass/events
+    #[serde(default)]
+    pub event_log: EventLogSettings,
+    /// Desktop notifications for agent events
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Away-mode escalation to the project's default channel
+    #[serde(default)]
+    pub away: AwaySettings,
+    /// Outbound webhooks for hook and daemon events
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// Daemon supervisor settings
+    #[serde(default)]
+    pub daemon: DaemonSettings,
 }
 
 /// IDs of built-in default presets (used for migration on load).
@@ -167,6 +388,13 @@ impl Default for AppState {
             color_scheme: "default".into(),
             code_editor: "code".into(),
             last_sessions: HashMap::new(),
+            approvals: ApprovalSettings::default(),
+            policy_rules: Vec::new(),
+            event_log: EventLogSettings::default(),
+            notifications: NotificationSettings::default(),
+            away: AwaySettings::default(),
+            webhooks: Vec::new(),
+            daemon: DaemonSettings::default(),
         }
     }
 }
@@ -0,0 +1,427 @@
+use crate::claude_hooks::{self, HookTool};
+use crate::hook_adapters::GeminiLogCursor;
+use crate::hook_event::HookSource;
+use crate::hook_server::HookServer;
+use crate::project::AppStateMutex;
+use crate::pty_manager::{self, PtyManagerMutex};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// How often session directories are scanned for a new session file, at
+/// first. The interval doubles up to `MAX_POLL_INTERVAL` while nothing shows
+/// up.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Stop looking if no session file appears. The tools write one once the
+/// first prompt is sent, so this also covers a layout we don't recognise.
+const GIVE_UP_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// Session files are created a moment after the tool starts; allow for clock
+/// granularity and filesystems that round timestamps.
+const START_SLACK: Duration = Duration::from_secs(2);
+
+/// How often a Gemini chat log is checked for new messages.
+const GEMINI_LOG_POLL: Duration = Duration::from_secs(2);
+
+/// Tools that don't report their session ID through hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Codex,
+    Gemini,
+}
+
+impl Tool {
+    fn from_command(command: &str) -> Option<Self> {
+        match command.split_whitespace().next()? {
+            "codex" => Some(Tool::Codex),
+            "gemini" => Some(Tool::Gemini),
+            _ => None,
+        }
+    }
+}
+
+/// Find the tool's own session ID for a Codex or Gemini session and record it
+/// on the saved session, so it can be resumed later. Claude reports its ID
+/// through hook events instead. Gemini's chat log is then turned into hook
+/// events, unless touchgrass's Gemini hooks already report them.
+pub fn watch(app: AppHandle, session_id: String, command: String, cwd: String) {
+    let Some(tool) = Tool::from_command(&command) else {
+        return;
+    };
+    let Some(home) = dirs::home_dir() else {
+        return;
+    };
+    let ingest_log =
+        tool == Tool::Gemini && !claude_hooks::hooks_registered(HookTool::Gemini, Path::new(&cwd));
+
+    // Resuming: the ID is already on the command line
+    if let Some(id) = resume_id(tool, &command) {
+        pty_manager::record_tool_session_id(&app, &session_id, &id);
+        if ingest_log {
+            std::thread::spawn(move || {
+                let Some(path) = find_gemini_session_by_id(&home, &cwd, &id) else {
+                    return;
+                };
+                let cursor = read_json(&path)
+                    .map(|chat| GeminiLogCursor::at_end(&chat))
+                    .unwrap_or_default();
+                tail_gemini_log(&app, &session_id, &path, cursor);
+            });
+        }
+        return;
+    }
+
+    let started = SystemTime::now() - START_SLACK;
+    std::thread::spawn(move || {
+        let mut interval = POLL_INTERVAL;
+        let mut waited = Duration::ZERO;
+        while waited < GIVE_UP_AFTER {
+            std::thread::sleep(interval);
+            waited += interval;
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+
+            if !session_alive(&app, &session_id) {
+                return;
+            }
+
+            let claimed = claimed_ids(&app);
+            let found = match tool {
+                Tool::Codex => {
+                    find_codex_session(&home, &cwd, started, &claimed).map(|id| (id, None))
+                }
+                Tool::Gemini => find_gemini_session(&home, &cwd, started, &claimed)
+                    .map(|(id, path)| (id, Some(path))),
+            };
+            if let Some((id, path)) = found {
+                log::info!("Captured {tool:?} session {id} for {session_id}");
+                pty_manager::record_tool_session_id(&app, &session_id, &id);
+                if let Some(path) = path.filter(|_| ingest_log) {
+                    tail_gemini_log(&app, &session_id, &path, GeminiLogCursor::default());
+                }
+                return;
+            }
+        }
+        log::debug!("No {tool:?} session file found for {session_id}; not resumable");
+    });
+}
+
+fn session_alive(app: &AppHandle, session_id: &str) -> bool {
+    let pty_mgr = app.state::<PtyManagerMutex>();
+    let mgr = pty_mgr.lock().unwrap();
+    mgr.sessions.contains_key(session_id)
+}
+
+/// Post a Gemini chat log's entries to the hook server until the session
+/// ends. The file is only re-read when it changes; an unchanged file lets the
+/// cursor close the current turn.
+fn tail_gemini_log(app: &AppHandle, session_id: &str, path: &Path, mut cursor: GeminiLogCursor) {
+    let Some(server) = app.try_state::<HookServer>() else {
+        return;
+    };
+    let mut seen: Option<SystemTime> = None;
+    let mut chat = serde_json::Value::Null;
+    loop {
+        std::thread::sleep(GEMINI_LOG_POLL);
+        if !session_alive(app, session_id) {
+            return;
+        }
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let settled = seen.is_some() && modified == seen;
+        if !settled {
+            // Gemini may be halfway through rewriting it; retry next time
+            let Some(next) = read_json(path) else {
+                seen = None;
+                continue;
+            };
+            chat = next;
+            seen = modified;
+        }
+        for body in cursor.advance(&chat, settled) {
+            if let Err(e) = server.post_hook(HookSource::Gemini, session_id, &body) {
+                log::debug!("Skipping Gemini log entry: {e}");
+            }
+        }
+    }
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+/// `codex resume <id>` / `gemini --resume <id>`
+fn resume_id(tool: Tool, command: &str) -> Option<String> {
+    let flag = match tool {
+        Tool::Codex => "resume",
+        Tool::Gemini => "--resume",
+    };
+    let mut args = command.split_whitespace().skip(1);
+    args.find(|a| *a == flag)?;
+    args.next()
+        .filter(|id| !id.starts_with('-') && *id != "latest")
+        .map(String::from)
+}
+
+/// Tool session IDs already attached to some saved session. Two sessions in
+/// the same directory must not pick up the same file.
+fn claimed_ids(app: &AppHandle) -> Vec<String> {
+    let state = app.state::<AppStateMutex>();
+    let s = state.lock().unwrap();
+    s.saved_sessions
+        .iter()
+        .filter_map(|s| s.tool_session_id.clone())
+        .collect()
+}
+
+/// Creation time where the filesystem reports it, else last modification.
+fn created_at(path: &Path) -> Option<SystemTime> {
+    let meta = std::fs::metadata(path).ok()?;
+    meta.created().or_else(|_| meta.modified()).ok()
+}
+
+/// Files in `dir` with the given extension created at or after `since`,
+/// oldest first.
+fn new_files(dir: &Path, extension: &str, since: SystemTime) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(extension))
+        .filter_map(|p| created_at(&p).map(|t| (t, p)))
+        .filter(|(t, _)| *t >= since)
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, p)| p).collect()
+}
+
+/// Codex: ~/.codex/sessions/YYYY/MM/DD/rollout-<timestamp>-<uuid>.jsonl,
+/// whose first line is a `session_meta` record with the id and cwd.
+fn find_codex_session(
+    home: &Path,
+    cwd: &str,
+    since: SystemTime,
+    claimed: &[String],
+) -> Option<String> {
+    let root = home.join(".codex").join("sessions");
+    // Today's directory, plus yesterday's for sessions started around midnight
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for days_ago in [1u64, 0] {
+        let day = SystemTime::now() - Duration::from_secs(days_ago * 24 * 60 * 60);
+        if let Some((y, m, d)) = local_date(day) {
+            let dir = root
+                .join(format!("{y:04}"))
+                .join(format!("{m:02}"))
+                .join(format!("{d:02}"));
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+
+    dirs.iter()
+        .flat_map(|dir| new_files(dir, "jsonl", since))
+        .find_map(|path| {
+            let file = std::fs::File::open(&path).ok()?;
+            let mut first = String::new();
+            BufReader::new(file).read_line(&mut first).ok()?;
+            let json: serde_json::Value = serde_json::from_str(&first).ok()?;
+            let meta = json.get("payload").unwrap_or(&json);
+            if meta.get("cwd").and_then(|v| v.as_str()) != Some(cwd) {
+                return None;
+            }
+            let id = meta.get("id").and_then(|v| v.as_str())?.to_string();
+            (!claimed.contains(&id)).then_some(id)
+        })
+}
+
+/// Gemini: ~/.gemini/tmp/<sha256(cwd) hex>/chats/session-*.json with a
+/// top-level `sessionId` and `projectHash`. A directory named after the
+/// project's basename is checked too, using the hash to tell same-named
+/// projects apart. Returns the ID and the chat file.
+fn find_gemini_session(
+    home: &Path,
+    cwd: &str,
+    since: SystemTime,
+    claimed: &[String],
+) -> Option<(String, PathBuf)> {
+    gemini_chats(home, cwd, since).find(|(id, _)| !claimed.contains(id))
+}
+
+/// The chat file of a session being resumed, whenever it was created.
+fn find_gemini_session_by_id(home: &Path, cwd: &str, id: &str) -> Option<PathBuf> {
+    gemini_chats(home, cwd, SystemTime::UNIX_EPOCH)
+        .find(|(found, _)| found == id)
+        .map(|(_, path)| path)
+}
+
+/// This project's Gemini chat files created since `since`, with their
+/// session IDs, oldest first.
+fn gemini_chats<'a>(
+    home: &Path,
+    cwd: &'a str,
+    since: SystemTime,
+) -> impl Iterator<Item = (String, PathBuf)> + 'a {
+    let project_hash = hex::encode(Sha256::digest(cwd.as_bytes()));
+    let tmp = home.join(".gemini").join("tmp");
+    let mut dirs = vec![tmp.join(&project_hash)];
+    if let Some(name) = Path::new(cwd).file_name() {
+        dirs.push(tmp.join(name));
+    }
+
+    dirs.into_iter()
+        .flat_map(move |dir| new_files(&dir.join("chats"), "json", since))
+        .filter_map(move |path| {
+            let json = read_json(&path)?;
+            // A basename directory may be shared by projects with the same name
+            if json
+                .get("projectHash")
+                .and_then(|v| v.as_str())
+                .is_some_and(|h| h != project_hash)
+            {
+                return None;
+            }
+            let id = json.get("sessionId").and_then(|v| v.as_str())?.to_string();
+            Some((id, path))
+        })
+}
+
+/// Local calendar date, matching the directories Codex writes to.
+#[cfg(unix)]
+fn local_date(time: SystemTime) -> Option<(i32, u32, u32)> {
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs() as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::localtime_r(&secs, &mut tm) };
+    if result.is_null() {
+        return None;
+    }
+    Some((tm.tm_year + 1900, (tm.tm_mon + 1) as u32, tm.tm_mday as u32))
+}
+
+#[cfg(not(unix))]
+fn local_date(_time: SystemTime) -> Option<(i32, u32, u32)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_home(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tg-tools-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn recognises_tools_by_command() {
+        assert_eq!(Tool::from_command("codex --full-auto"), Some(Tool::Codex));
+        assert_eq!(Tool::from_command("  gemini"), Some(Tool::Gemini));
+        assert_eq!(Tool::from_command("claude"), None);
+        assert_eq!(Tool::from_command(""), None);
+    }
+
+    #[test]
+    fn reads_resume_ids_from_the_command_line() {
+        assert_eq!(
+            resume_id(Tool::Codex, "codex resume 0199-abc").as_deref(),
+            Some("0199-abc")
+        );
+        assert_eq!(
+            resume_id(Tool::Gemini, "gemini -y --resume 5f2c").as_deref(),
+            Some("5f2c")
+        );
+        assert_eq!(resume_id(Tool::Gemini, "gemini --resume latest"), None);
+        assert_eq!(resume_id(Tool::Gemini, "gemini --resume --yolo"), None);
+        assert_eq!(resume_id(Tool::Codex, "codex resume"), None);
+        assert_eq!(resume_id(Tool::Codex, "codex"), None);
+    }
+
+    #[test]
+    fn finds_gemini_sessions_under_the_project_hash() {
+        let home = temp_home("gemini-hash");
+        let cwd = "/work/app";
+        let hash = hex::encode(Sha256::digest(cwd.as_bytes()));
+        let chats = home.join(".gemini/tmp").join(&hash).join("chats");
+        write(
+            &chats.join("session-2026-01-01T00-00-aaaa.json"),
+            &json!({ "sessionId": "aaaa-1", "projectHash": hash }).to_string(),
+        );
+        let since = SystemTime::now() - Duration::from_secs(60);
+
+        let (id, path) = find_gemini_session(&home, cwd, since, &[]).unwrap();
+        assert_eq!(id, "aaaa-1");
+        assert_eq!(find_gemini_session_by_id(&home, cwd, "aaaa-1"), Some(path));
+        assert_eq!(
+            find_gemini_session(&home, cwd, since, &["aaaa-1".into()]),
+            None
+        );
+        // Files from before the session started belong to someone else
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(find_gemini_session(&home, cwd, later, &[]), None);
+        let _ = std::fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn gemini_basename_dirs_must_match_the_project_hash() {
+        let home = temp_home("gemini-name");
+        let chats = home.join(".gemini/tmp/app/chats");
+        let other_hash = hex::encode(Sha256::digest(b"/elsewhere/app"));
+        write(
+            &chats.join("session-1.json"),
+            &json!({ "sessionId": "other", "projectHash": other_hash }).to_string(),
+        );
+        let since = SystemTime::now() - Duration::from_secs(60);
+        assert_eq!(find_gemini_session(&home, "/work/app", since, &[]), None);
+
+        write(
+            &chats.join("session-2.json"),
+            &json!({ "sessionId": "mine" }).to_string(),
+        );
+        assert_eq!(
+            find_gemini_session(&home, "/work/app", since, &[]).map(|(id, _)| id),
+            Some("mine".to_string())
+        );
+        let _ = std::fs::remove_dir_all(&home);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_codex_sessions_by_cwd_in_todays_directory() {
+        let home = temp_home("codex");
+        let (y, m, d) = local_date(SystemTime::now()).unwrap();
+        let day = home
+            .join(".codex/sessions")
+            .join(format!("{y:04}/{m:02}/{d:02}"));
+        let meta = |id: &str, cwd: &str| {
+            json!({ "type": "session_meta", "payload": { "id": id, "cwd": cwd } }).to_string()
+                + "\n{}\n"
+        };
+        write(&day.join("rollout-1-a.jsonl"), &meta("a", "/other"));
+        write(&day.join("rollout-2-b.jsonl"), &meta("b", "/work/app"));
+        write(&day.join("notes.txt"), &meta("c", "/work/app"));
+        let since = SystemTime::now() - Duration::from_secs(60);
+
+        assert_eq!(
+            find_codex_session(&home, "/work/app", since, &[]).as_deref(),
+            Some("b")
+        );
+        assert_eq!(
+            find_codex_session(&home, "/work/app", since, &["b".into()]),
+            None
+        );
+        let _ = std::fs::remove_dir_all(&home);
+    }
+}
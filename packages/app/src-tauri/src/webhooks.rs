@@ -0,0 +1,687 @@
+use crate::config;
+use crate::project::AppStateMutex;
+use crate::state::Webhook;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+/// Attempts per delivery before it is logged as failed.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; doubles after each failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries waiting to be sent or retried. The oldest are dropped beyond
+/// this, so an unreachable endpoint can't grow the queue without bound.
+const MAX_QUEUED: usize = 500;
+
+/// Entries returned by `get_webhook_deliveries` when no limit is given.
+const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// The delivery log is trimmed to its most recent lines past this size.
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const TRIMMED_LOG_LINES: usize = 2000;
+
+/// One line of ~/.touchgrass/webhooks.log: the outcome of a delivery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryRecord {
+    /// Unix epoch milliseconds when the delivery finished
+    pub timestamp: u64,
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event_type: String,
+    pub attempts: u32,
+    pub delivered: bool,
+    /// HTTP status of the last attempt, if the endpoint answered
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewWebhook {
+    pub name: String,
+    pub url: String,
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub project_ids: Vec<String>,
+}
+
+struct Pending {
+    delivery_id: String,
+    webhook_id: String,
+    event_type: String,
+    body: String,
+    attempts: u32,
+    due: Instant,
+}
+
+struct Failure {
+    retry: bool,
+    status: Option<u16>,
+    error: String,
+}
+
+/// What follows an attempt.
+enum Next {
+    /// Try again after the backoff
+    Retry(Duration, String),
+    Done(DeliveryRecord),
+}
+
+/// Queue of outbound webhook deliveries, drained by one background thread.
+pub struct WebhookDispatcher {
+    queue: Mutex<VecDeque<Pending>>,
+    wake: Condvar,
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            wake: Condvar::new(),
+        }
+    }
+
+    fn enqueue(&self, pending: Pending) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() >= MAX_QUEUED {
+            if let Some(dropped) = queue.pop_front() {
+                log::warn!(
+                    "Webhook queue full; dropping delivery {} to {}",
+                    dropped.delivery_id,
+                    dropped.webhook_id
+                );
+            }
+        }
+        queue.push_back(pending);
+        self.wake.notify_one();
+    }
+
+    /// Block until the earliest delivery is due and take it.
+    fn next_due(&self) -> Pending {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let earliest = queue
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, p)| p.due)
+                .map(|(i, p)| (i, p.due));
+            match earliest {
+                None => queue = self.wake.wait(queue).unwrap(),
+                Some((i, due)) => {
+                    let now = Instant::now();
+                    if due <= now {
+                        if let Some(pending) = queue.remove(i) {
+                            return pending;
+                        }
+                    } else {
+                        queue = self.wake.wait_timeout(queue, due - now).unwrap().0;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Queue an event for every enabled webhook whose filters match.
+/// `kind` is "hook" or "daemon".
+pub fn publish(
+    app: &AppHandle,
+    kind: &str,
+    event_type: &str,
+    session_id: Option<&str>,
+    project_id: Option<&str>,
+    data: serde_json::Value,
+) {
+    let targets: Vec<String> = {
+        let state = app.state::<AppStateMutex>();
+        let s = state.lock().unwrap();
+        s.webhooks
+            .iter()
+            .filter(|w| matches(w, event_type, project_id))
+            .map(|w| w.id.clone())
+            .collect()
+    };
+    if targets.is_empty() {
+        return;
+    }
+
+    let body = serde_json::json!({
+        "kind": kind,
+        "event_type": event_type,
+        "session_id": session_id,
+        "project_id": project_id,
+        "timestamp": now_ms(),
+        "data": data,
+    })
+    .to_string();
+
+    let dispatcher = app.state::<WebhookDispatcher>();
+    for webhook_id in targets {
+        dispatcher.enqueue(Pending {
+            delivery_id: uuid::Uuid::new_v4().to_string(),
+            webhook_id,
+            event_type: event_type.to_string(),
+            body: body.clone(),
+            attempts: 0,
+            due: Instant::now(),
+        });
+    }
+}
+
+fn matches(webhook: &Webhook, event_type: &str, project_id: Option<&str>) -> bool {
+    if !webhook.enabled {
+        return false;
+    }
+    if !webhook.event_types.is_empty() && !webhook.event_types.iter().any(|t| t == event_type) {
+        return false;
+    }
+    webhook.project_ids.is_empty()
+        || project_id.is_some_and(|id| webhook.project_ids.iter().any(|p| p == id))
+}
+
+/// Hex HMAC-SHA256 of the body, sent as `X-Touchgrass-Signature: sha256=<hex>`.
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn send(webhook: &Webhook, pending: &Pending) -> Result<u16, Failure> {
+    let mut request = ureq::post(&webhook.url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .set("User-Agent", "touchgrass-webhooks")
+        .set("X-Touchgrass-Event", &pending.event_type)
+        .set("X-Touchgrass-Delivery", &pending.delivery_id);
+    if let Some(secret) = webhook.secret.as_deref().filter(|s| !s.is_empty()) {
+        request = request.set(
+            "X-Touchgrass-Signature",
+            &format!("sha256={}", sign(secret, &pending.body)),
+        );
+    }
+    match request.send_string(&pending.body) {
+        Ok(response) => Ok(response.status()),
+        Err(ureq::Error::Status(status, _)) => Err(Failure {
+            // Client errors won't succeed on a retry, except rate limiting
+            retry: status == 429 || status >= 500,
+            status: Some(status),
+            error: format!("HTTP {status}"),
+        }),
+        Err(e) => Err(Failure {
+            retry: true,
+            status: None,
+            error: e.to_string(),
+        }),
+    }
+}
+
+/// Send one attempt, then queue a retry or log the outcome.
+fn attempt(app: &AppHandle, mut pending: Pending) {
+    let webhook = {
+        let state = app.state::<AppStateMutex>();
+        let s = state.lock().unwrap();
+        s.webhooks
+            .iter()
+            .find(|w| w.id == pending.webhook_id)
+            .cloned()
+    };
+    // Removed or disabled while queued
+    let Some(webhook) = webhook.filter(|w| w.enabled) else {
+        return;
+    };
+
+    match deliver(&webhook, &mut pending) {
+        Next::Retry(backoff, error) => {
+            log::debug!(
+                "Webhook {} delivery {} failed ({error}); retrying in {backoff:?}",
+                webhook.name,
+                pending.delivery_id
+            );
+            pending.due = Instant::now() + backoff;
+            app.state::<WebhookDispatcher>().enqueue(pending);
+        }
+        Next::Done(record) => {
+            if !record.delivered {
+                log::warn!(
+                    "Webhook {} gave up on {} after {} attempts",
+                    webhook.name,
+                    record.delivery_id,
+                    record.attempts
+                );
+            }
+            append_log(&record);
+        }
+    }
+}
+
+/// Send the next attempt of a delivery, deciding whether to retry it.
+fn deliver(webhook: &Webhook, pending: &mut Pending) -> Next {
+    pending.attempts += 1;
+    let (delivered, status, error) = match send(webhook, pending) {
+        Ok(status) => (true, Some(status), None),
+        Err(failure) if failure.retry && pending.attempts < MAX_ATTEMPTS => {
+            let backoff = INITIAL_BACKOFF * 2u32.pow(pending.attempts - 1);
+            return Next::Retry(backoff, failure.error);
+        }
+        Err(failure) => (false, failure.status, Some(failure.error)),
+    };
+    Next::Done(DeliveryRecord {
+        timestamp: now_ms(),
+        delivery_id: pending.delivery_id.clone(),
+        webhook_id: pending.webhook_id.clone(),
+        event_type: pending.event_type.clone(),
+        attempts: pending.attempts,
+        delivered,
+        status,
+        error,
+    })
+}
+
+/// Start the background thread that sends queued deliveries.
+pub fn start_delivery(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let pending = app.state::<WebhookDispatcher>().next_due();
+        attempt(&app, pending);
+    });
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn log_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".touchgrass").join("webhooks.log"))
+}
+
+fn append_log(record: &DeliveryRecord) {
+    let Some(path) = log_path() else { return };
+    let Ok(line) = serde_json::to_string(record) else {
+        return;
+    };
+    let too_big = std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES);
+    if too_big {
+        let data = std::fs::read_to_string(&path).unwrap_or_default();
+        let lines: Vec<&str> = data.lines().collect();
+        let keep = &lines[lines.len().saturating_sub(TRIMMED_LOG_LINES)..];
+        let _ = std::fs::write(&path, format!("{}\n", keep.join("\n")));
+    }
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+    {
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+fn validate(name: &str, url: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Webhook name cannot be empty.".into());
+    }
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err("Webhook URL must start with http:// or https://.".into());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_webhooks(state: State<'_, AppStateMutex>) -> Vec<Webhook> {
+    let s = state.lock().unwrap();
+    s.webhooks.clone()
+}
+
+#[tauri::command]
+pub fn add_webhook(
+    state: State<'_, AppStateMutex>,
+    webhook: NewWebhook,
+) -> Result<Webhook, String> {
+    validate(&webhook.name, &webhook.url)?;
+    let webhook = Webhook {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: webhook.name.trim().to_string(),
+        url: webhook.url.trim().to_string(),
+        secret: webhook.secret.filter(|s| !s.is_empty()),
+        event_types: webhook.event_types,
+        project_ids: webhook.project_ids,
+        enabled: true,
+    };
+    let mut s = state.lock().unwrap();
+    s.webhooks.push(webhook.clone());
+    config::save_state(&s);
+    Ok(webhook)
+}
+
+#[tauri::command]
+pub fn update_webhook(
+    state: State<'_, AppStateMutex>,
+    webhook: Webhook,
+) -> Result<Webhook, String> {
+    validate(&webhook.name, &webhook.url)?;
+    let mut s = state.lock().unwrap();
+    let existing = s
+        .webhooks
+        .iter_mut()
+        .find(|w| w.id == webhook.id)
+        .ok_or_else(|| format!("Webhook not found: {}", webhook.id))?;
+    *existing = webhook.clone();
+    config::save_state(&s);
+    Ok(webhook)
+}
+
+#[tauri::command]
+pub fn remove_webhook(state: State<'_, AppStateMutex>, webhook_id: String) -> Result<(), String> {
+    let mut s = state.lock().unwrap();
+    s.webhooks.retain(|w| w.id != webhook_id);
+    config::save_state(&s);
+    Ok(())
+}
+
+/// Send a `ping` event right away, once, ignoring the webhook's filters, and
+/// return the outcome. Useful against a local listener such as
+/// `nc -l 8080` or a small HTTP server.
+#[tauri::command]
+pub fn test_webhook(
+    state: State<'_, AppStateMutex>,
+    webhook_id: String,
+) -> Result<DeliveryRecord, String> {
+    let webhook = {
+        let s = state.lock().unwrap();
+        s.webhooks
+            .iter()
+            .find(|w| w.id == webhook_id)
+            .cloned()
+            .ok_or_else(|| format!("Webhook not found: {webhook_id}"))?
+    };
+    let pending = Pending {
+        delivery_id: uuid::Uuid::new_v4().to_string(),
+        webhook_id: webhook.id.clone(),
+        event_type: "ping".into(),
+        body: serde_json::json!({
+            "kind": "ping",
+            "event_type": "ping",
+            "session_id": null,
+            "project_id": null,
+            "timestamp": now_ms(),
+            "data": { "webhook_id": webhook.id },
+        })
+        .to_string(),
+        attempts: 1,
+        due: Instant::now(),
+    };
+
+    let (delivered, status, error) = match send(&webhook, &pending) {
+        Ok(status) => (true, Some(status), None),
+        Err(failure) => (false, failure.status, Some(failure.error)),
+    };
+    let record = DeliveryRecord {
+        timestamp: now_ms(),
+        delivery_id: pending.delivery_id,
+        webhook_id: pending.webhook_id,
+        event_type: pending.event_type,
+        attempts: pending.attempts,
+        delivered,
+        status,
+        error,
+    };
+    append_log(&record);
+    Ok(record)
+}
+
+/// Most recent delivery outcomes, optionally for one webhook.
+#[tauri::command]
+pub fn get_webhook_deliveries(
+    webhook_id: Option<String>,
+    limit: Option<usize>,
+) -> Vec<DeliveryRecord> {
+    let Some(path) = log_path() else {
+        return Vec::new();
+    };
+    let data = std::fs::read_to_string(path).unwrap_or_default();
+    let mut records: Vec<DeliveryRecord> = data
+        .lines()
+        .filter_map(|line| serde_json::from_str::<DeliveryRecord>(line).ok())
+        .filter(|r| webhook_id.as_ref().map_or(true, |id| &r.webhook_id == id))
+        .collect();
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    if records.len() > limit {
+        records.drain(..records.len() - limit);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Answer one request per status on a local port, returning the raw
+    /// requests (headers and body) once all have been served.
+    fn endpoint(statuses: Vec<u16>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+                requests.push(request);
+
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn webhook(url: &str, secret: Option<&str>) -> Webhook {
+        Webhook {
+            id: "w1".into(),
+            name: "test".into(),
+            url: url.into(),
+            secret: secret.map(String::from),
+            event_types: Vec::new(),
+            project_ids: Vec::new(),
+            enabled: true,
+        }
+    }
+
+    fn pending(delivery_id: &str, due: Instant) -> Pending {
+        Pending {
+            delivery_id: delivery_id.into(),
+            webhook_id: "w1".into(),
+            event_type: "Stop".into(),
+            body: r#"{"kind":"hook","event_type":"Stop"}"#.into(),
+            attempts: 0,
+            due,
+        }
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (n, v) = line.split_once(':')?;
+            n.eq_ignore_ascii_case(name).then(|| v.trim())
+        })
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // The well-known HMAC-SHA256 example
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn sends_the_signature_header_with_a_secret() {
+        let (url, server) = endpoint(vec![200, 200]);
+        let mut signed = pending("d1", Instant::now());
+        let Next::Done(record) = deliver(&webhook(&url, Some("s3cret")), &mut signed) else {
+            panic!("expected a delivery");
+        };
+        assert!(record.delivered);
+        assert_eq!(record.status, Some(200));
+        let mut unsigned = pending("d2", Instant::now());
+        deliver(&webhook(&url, None), &mut unsigned);
+
+        let requests = server.join().unwrap();
+        let expected = format!("sha256={}", sign("s3cret", &signed.body));
+        assert_eq!(
+            header(&requests[0], "x-touchgrass-signature"),
+            Some(expected.as_str())
+        );
+        assert_eq!(header(&requests[0], "x-touchgrass-event"), Some("Stop"));
+        assert_eq!(header(&requests[0], "x-touchgrass-delivery"), Some("d1"));
+        assert!(requests[0].ends_with(&signed.body));
+        assert_eq!(header(&requests[1], "x-touchgrass-signature"), None);
+    }
+
+    #[test]
+    fn retries_server_errors_with_doubling_backoff() {
+        let (url, server) = endpoint(vec![503, 500, 200]);
+        let webhook = webhook(&url, None);
+        let mut delivery = pending("d1", Instant::now());
+
+        let mut backoffs = Vec::new();
+        let record = loop {
+            match deliver(&webhook, &mut delivery) {
+                Next::Retry(backoff, error) => {
+                    assert!(error.contains("HTTP 5"));
+                    backoffs.push(backoff);
+                }
+                Next::Done(record) => break record,
+            }
+        };
+        assert_eq!(backoffs, [INITIAL_BACKOFF, INITIAL_BACKOFF * 2]);
+        assert!(record.delivered);
+        assert_eq!(record.attempts, 3);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let (url, server) = endpoint(vec![500; MAX_ATTEMPTS as usize]);
+        let webhook = webhook(&url, None);
+        let mut delivery = pending("d1", Instant::now());
+
+        let mut retries = 0;
+        let record = loop {
+            match deliver(&webhook, &mut delivery) {
+                Next::Retry(..) => retries += 1,
+                Next::Done(record) => break record,
+            }
+        };
+        assert_eq!(retries, MAX_ATTEMPTS - 1);
+        assert!(!record.delivered);
+        assert_eq!(record.attempts, MAX_ATTEMPTS);
+        assert_eq!(record.status, Some(500));
+        assert_eq!(record.error.as_deref(), Some("HTTP 500"));
+        assert_eq!(server.join().unwrap().len(), MAX_ATTEMPTS as usize);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried_except_rate_limits() {
+        let (url, server) = endpoint(vec![404, 429]);
+        let webhook = webhook(&url, None);
+        let Next::Done(record) = deliver(&webhook, &mut pending("d1", Instant::now())) else {
+            panic!("404 should not be retried");
+        };
+        assert_eq!((record.delivered, record.status), (false, Some(404)));
+        assert!(matches!(
+            deliver(&webhook, &mut pending("d2", Instant::now())),
+            Next::Retry(..)
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn unreachable_endpoints_are_retried() {
+        // Bind and drop to get a port nothing listens on
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let webhook = webhook(&format!("http://127.0.0.1:{port}/"), None);
+        let Next::Retry(backoff, _) = deliver(&webhook, &mut pending("d1", Instant::now())) else {
+            panic!("connection errors should be retried");
+        };
+        assert_eq!(backoff, INITIAL_BACKOFF);
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_delivery() {
+        let dispatcher = WebhookDispatcher::new();
+        let now = Instant::now();
+        for i in 0..=MAX_QUEUED {
+            dispatcher.enqueue(pending(&i.to_string(), now));
+        }
+        let queue = dispatcher.queue.lock().unwrap();
+        assert_eq!(queue.len(), MAX_QUEUED);
+        assert_eq!(queue.front().unwrap().delivery_id, "1");
+        assert_eq!(queue.back().unwrap().delivery_id, MAX_QUEUED.to_string());
+    }
+
+    #[test]
+    fn takes_the_earliest_due_delivery() {
+        let dispatcher = WebhookDispatcher::new();
+        let now = Instant::now();
+        dispatcher.enqueue(pending("later", now + Duration::from_millis(50)));
+        dispatcher.enqueue(pending("now", now));
+        assert_eq!(dispatcher.next_due().delivery_id, "now");
+        assert_eq!(dispatcher.next_due().delivery_id, "later");
+        assert!(Instant::now() >= now + Duration::from_millis(50));
+    }
+
+    #[test]
+    fn filters_by_event_type_and_project() {
+        let mut hook = webhook("http://localhost/", None);
+        assert!(matches(&hook, "Stop", None));
+        hook.event_types = vec!["Stop".into()];
+        assert!(!matches(&hook, "PreToolUse", None));
+        hook.project_ids = vec!["p1".into()];
+        assert!(matches(&hook, "Stop", Some("p1")));
+        assert!(!matches(&hook, "Stop", Some("p2")));
+        assert!(!matches(&hook, "Stop", None));
+        hook.enabled = false;
+        assert!(!matches(&hook, "Stop", Some("p1")));
+    }
+}
@@ -1,5 +1,6 @@
 import { writable } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 // --- Types ---
 
@@ -40,18 +41,107 @@ export interface RuntimeChannel {
   busyLabel: string | null;
 }
 
+export type OutputMode = 'simple' | 'thinking' | 'verbose';
+
+/** Per-chat bridge preferences, as set by /output_mode and /mute. */
+export interface ChatPreferences {
+  chatId: string;
+  outputMode: OutputMode;
+  /** Thinking previews are sent only in thinking mode */
+  thinking: boolean;
+  muted: boolean;
+}
+
+export interface DaemonSession {
+  id: string;
+  command: string;
+  name: string | null;
+  cwd: string;
+  chatId: string | null;
+  lastSeenAt: number | null;
+  /** App tab running this session, if any */
+  appSessionId: string | null;
+}
+
+export interface ChatLink {
+  chatId: string;
+  /** Daemon session ID ("r-...") */
+  sessionId: string;
+  label: string;
+  /** Linked group or topic title; null for DMs */
+  title: string | null;
+  ownerDm: boolean;
+  /** App tab running this session, if any */
+  appSessionId: string | null;
+}
+
+export interface PeekEntry {
+  role: 'assistant' | 'user' | 'tool';
+  text: string;
+}
+
+export interface DaemonSupervisorStatus {
+  state: 'unknown' | 'up' | 'down' | 'restarting';
+  pid: number | null;
+  uptime_secs: number | null;
+  last_error: string | null;
+  restarts: number;
+}
+
+export interface DaemonSettings {
+  keep_alive: boolean;
+}
+
+/** Error from a daemon_* command. */
+export interface DaemonErrorInfo {
+  kind: 'not_running' | 'unauthorized' | 'timeout' | 'protocol' | 'api' | 'invalid';
+  message: string;
+  /** HTTP status, for errors the daemon reported */
+  status?: number;
+}
+
+/** Thrown by the daemon functions below; `toString()` is the message to show. */
+export class DaemonError extends Error {
+  readonly kind: DaemonErrorInfo['kind'];
+  readonly status: number | null;
+
+  constructor(info: DaemonErrorInfo) {
+    super(info.message);
+    this.name = 'DaemonError';
+    this.kind = info.kind;
+    this.status = info.status ?? null;
+  }
+
+  toString(): string {
+    return this.message;
+  }
+}
+
+function isDaemonErrorInfo(e: unknown): e is DaemonErrorInfo {
+  return typeof e === 'object' && e !== null && 'kind' in e && 'message' in e;
+}
+
+async function invokeDaemon<T>(cmd: string, args?: Record<string, unknown>): Promise<T> {
+  try {
+    return await invoke<T>(cmd, args);
+  } catch (e) {
+    throw isDaemonErrorInfo(e) ? new DaemonError(e) : e;
+  }
+}
+
 // --- Stores ---
 
 export const daemonStatus = writable<'unknown' | 'running' | 'stopped'>('unknown');
 export const channels = writable<ChannelSummary[]>([]);
 export const runtimeChannels = writable<RuntimeChannel[]>([]);
 export const selectedChannel = writable<ChannelDetails | null>(null);
+export const daemonSupervisorStatus = writable<DaemonSupervisorStatus | null>(null);
 
 // --- Functions ---
 
 export async function checkDaemonHealth(): Promise<boolean> {
   try {
-    await invoke('daemon_health');
+    await invokeDaemon('daemon_health');
     daemonStatus.set('running');
     return true;
   } catch {
@@ -60,22 +150,71 @@ export async function checkDaemonHealth(): Promise<boolean> {
   }
 }
 
+function applySupervisorStatus(status: DaemonSupervisorStatus): void {
+  daemonSupervisorStatus.set(status);
+  daemonStatus.set(status.state === 'up' ? 'running' : status.state === 'down' ? 'stopped' : 'unknown');
+}
+
+/** Follow the background supervisor's daemon-status-changed events. */
+export async function watchDaemonStatus(): Promise<void> {
+  await listen<DaemonSupervisorStatus>('daemon-status-changed', (event) => {
+    applySupervisorStatus(event.payload);
+  });
+  applySupervisorStatus(await invoke<DaemonSupervisorStatus>('get_daemon_status'));
+}
+
+export async function getDaemonSettings(): Promise<DaemonSettings> {
+  return invoke<DaemonSettings>('get_daemon_settings');
+}
+
+export async function setDaemonSettings(settings: DaemonSettings): Promise<void> {
+  await invoke('set_daemon_settings', { settings });
+}
+
 export async function loadChannels(): Promise<void> {
-  const resp = await invoke<{ ok: boolean; channels: ChannelSummary[] }>(
+  const resp = await invokeDaemon<{ ok: boolean; channels: ChannelSummary[] }>(
     'daemon_list_channels'
   );
   channels.set(resp.channels);
 }
 
 export async function loadRuntimeChannels(): Promise<void> {
-  const resp = await invoke<{ ok: boolean; channels: RuntimeChannel[] }>(
+  const resp = await invokeDaemon<{ ok: boolean; channels: RuntimeChannel[] }>(
     'daemon_runtime_channels'
   );
   runtimeChannels.set(resp.channels);
 }
 
+export async function listChatPreferences(): Promise<ChatPreferences[]> {
+  const resp = await invokeDaemon<{ ok: boolean; preferences: ChatPreferences[] }>(
+    'daemon_list_chat_preferences'
+  );
+  return resp.preferences;
+}
+
+export async function getChatPreferences(chatId: string): Promise<ChatPreferences> {
+  return invokeDaemon<ChatPreferences>('daemon_get_chat_preferences', { chatId });
+}
+
+/** Change some of a chat's preferences; omitted fields are kept. */
+export async function setChatPreferences(
+  chatId: string,
+  changes: { outputMode?: OutputMode; thinking?: boolean; muted?: boolean }
+): Promise<ChatPreferences> {
+  const resp = await invokeDaemon<{ ok: boolean; preferences: ChatPreferences }>(
+    'daemon_set_chat_preferences',
+    {
+      chatId,
+      outputMode: changes.outputMode ?? null,
+      thinking: changes.thinking ?? null,
+      muted: changes.muted ?? null,
+    }
+  );
+  return resp.preferences;
+}
+
 export async function loadChannelDetails(name: string): Promise<ChannelDetails> {
-  const resp = await invoke<{ ok: boolean; channel: ChannelDetails }>(
+  const resp = await invokeDaemon<{ ok: boolean; channel: ChannelDetails }>(
     'daemon_get_channel',
     { name }
   );
@@ -88,7 +227,7 @@ export async function addChannel(
   channelType: string,
   botToken: string
 ): Promise<{ botUsername: string | null; needsRestart: boolean }> {
-  const resp = await invoke<{
+  const resp = await invokeDaemon<{
     ok: boolean;
     botUsername: string | null;
     needsRestart: boolean | null;
@@ -104,7 +243,7 @@ export async function addChannel(
 }
 
 export async function removeChannel(name: string): Promise<{ needsRestart: boolean }> {
-  const resp = await invoke<{ ok: boolean; needsRestart: boolean | null }>(
+  const resp = await invokeDaemon<{ ok: boolean; needsRestart: boolean | null }>(
     'daemon_remove_channel',
     { name }
   );
@@ -113,20 +252,109 @@ export async function removeChannel(name: string): Promise<{ needsRestart: boole
 }
 
 export async function removeUser(channelName: string, userId: string): Promise<void> {
-  await invoke('daemon_remove_user', { channelName, userId });
+  await invokeDaemon('daemon_remove_user', { channelName, userId });
 }
 
 export async function removeGroup(channelName: string, chatId: string): Promise<void> {
-  await invoke('daemon_remove_group', { channelName, chatId });
+  await invokeDaemon('daemon_remove_group', { channelName, chatId });
 }
 
 export async function generatePairingCode(): Promise<string> {
-  const resp = await invoke<{ ok: boolean; code: string | null }>('daemon_generate_code');
+  const resp = await invokeDaemon<{ ok: boolean; code: string | null }>('daemon_generate_code');
   return resp.code ?? '';
 }
 
 export async function restartDaemon(): Promise<void> {
   daemonStatus.set('unknown');
-  await invoke('daemon_restart');
+  await invokeDaemon('daemon_restart');
   daemonStatus.set('running');
 }
+
+// --- Daemon sessions ---
+
+export async function listDaemonSessions(): Promise<DaemonSession[]> {
+  const resp = await invokeDaemon<{ ok: boolean; sessions: DaemonSession[] }>('daemon_list_sessions');
+  return resp.sessions;
+}
+
+export async function sendSessionMessage(sessionId: string, text: string): Promise<void> {
+  await invokeDaemon('daemon_send_message', { sessionId, text });
+}
+
+export async function sendSessionInput(sessionId: string, text: string): Promise<void> {
+  await invokeDaemon('daemon_send_input', { sessionId, text });
+}
+
+/** Send a file to the session's channel; it may be anywhere on disk. */
+export async function sendSessionFile(sessionId: string, path: string, caption?: string): Promise<void> {
+  await invokeDaemon('daemon_send_file', { sessionId, path, caption: caption ?? null });
+}
+
+/**
+ * Send a snapshot of an app terminal to its session's channel: a PNG of the
+ * rendered terminal, or its buffer as text.
+ */
+export async function sendTerminalSnapshot(
+  appSessionId: string,
+  snapshot: { png: Uint8Array } | { text: string },
+  caption?: string
+): Promise<void> {
+  const [format, bytes]: ['png' | 'text', Uint8Array] =
+    'png' in snapshot
+      ? ['png', snapshot.png]
+      : ['text', new TextEncoder().encode(snapshot.text)];
+  await invokeDaemon('daemon_send_snapshot', {
+    appSessionId,
+    format,
+    data: Array.from(bytes),
+    caption: caption ?? null,
+  });
+}
+
+export async function peekDaemonSession(sessionId: string, count?: number): Promise<PeekEntry[]> {
+  const resp = await invokeDaemon<{ ok: boolean; entries: PeekEntry[] }>('daemon_peek_session', {
+    sessionId,
+    count,
+  });
+  return resp.entries;
+}
+
+export async function stopDaemonSession(sessionId: string): Promise<void> {
+  await invokeDaemon('daemon_stop_session', { sessionId });
+}
+
+export async function killDaemonSession(sessionId: string): Promise<void> {
+  await invokeDaemon('daemon_kill_session', { sessionId });
+}
+
+// --- Chat links ---
+
+export const chatLinks = writable<ChatLink[]>([]);
+
+export async function loadChatLinks(): Promise<ChatLink[]> {
+  const resp = await invokeDaemon<{ ok: boolean; links: ChatLink[] }>('daemon_list_links');
+  chatLinks.set(resp.links);
+  return resp.links;
+}
+
+/** Keep chatLinks current as sessions are linked from the app or from chats. */
+export async function watchChatLinks(): Promise<void> {
+  const refresh = () => {
+    loadChatLinks().catch(() => chatLinks.set([]));
+  };
+  await listen('daemon-session-linked', refresh);
+  await listen('daemon-session-unlinked', refresh);
+  refresh();
+}
+
+export async function linkSession(sessionId: string, chatId: string): Promise<void> {
+  await invokeDaemon('daemon_link_session', { sessionId, chatId });
+}
+
+export async function unlinkSession(sessionId: string): Promise<void> {
+  await invokeDaemon('daemon_unlink_session', { sessionId });
+}
+
+export async function moveSession(sessionId: string, chatId: string): Promise<void> {
+  await invokeDaemon('daemon_move_session', { sessionId, chatId });
+}
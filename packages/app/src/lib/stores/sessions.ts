@@ -10,6 +10,8 @@ export interface SessionInfo {
   command: string;
   channel?: string | null;
   tool_session_id?: string | null;
+  /** Daemon session followed by a remote tab; absent for local terminals */
+  remote_id?: string | null;
 }
 
 export interface LastSession {
@@ -129,6 +131,23 @@ export async function spawnSession(
   return session;
 }
 
+/** Open a tab following a daemon session started outside the app. */
+export async function attachRemoteSession(projectId: string, remoteId: string): Promise<SessionInfo> {
+  const session = await invoke<SessionInfo>('attach_remote_session', { projectId, remoteId });
+
+  liveSessions.add(session.id);
+
+  sessionsByProject.update((m) => {
+    const next = new Map(m);
+    const existing = (next.get(projectId) ?? []).filter((s) => s.id !== session.id);
+    next.set(projectId, [...existing, session]);
+    return next;
+  });
+
+  setActiveTab(projectId, session.id);
+  return session;
+}
+
 export async function resumeSession(
   savedSession: SessionInfo,
   cwd: string,
@@ -174,6 +193,9 @@ export async function resumeSession(
     } else if (baseCmd === 'codex' && !command.includes('resume')) {
       parts.splice(1, 0, 'resume', toolSid);
       command = parts.join(' ');
+    } else if (baseCmd === 'gemini' && !command.includes('--resume')) {
+      parts.splice(1, 0, '--resume', toolSid);
+      command = parts.join(' ');
     } else if ((baseCmd === 'pi' || baseCmd === 'kimi') && !command.includes('--session')) {
       parts.splice(1, 0, '--session', toolSid);
       command = parts.join(' ');
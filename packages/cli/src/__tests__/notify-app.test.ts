@@ -0,0 +1,70 @@
+import { afterEach, describe, expect, it } from "bun:test";
+import {
+  APP_EVENT_BOOT_ID,
+  APP_EVENT_VERSION,
+  notifyApp,
+  resetAppEvents,
+  subscribeAppEvents,
+  type AppEventRecord,
+} from "../daemon/notify-app";
+
+describe("app event stream", () => {
+  afterEach(() => resetAppEvents());
+
+  it("delivers events to subscribers in order with the protocol version", () => {
+    const received: AppEventRecord[] = [];
+    const { replay } = subscribeAppEvents(0, APP_EVENT_BOOT_ID, (r) => received.push(r));
+    expect(replay).toEqual([]);
+
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:1" });
+    notifyApp({ type: "session-linked", sessionId: "r-abc", chatId: "telegram:2" });
+
+    expect(received.map((r) => r.seq)).toEqual([1, 2]);
+    expect(received[1].body).toEqual({
+      type: "session-linked",
+      sessionId: "r-abc",
+      chatId: "telegram:2",
+      version: APP_EVENT_VERSION,
+      seq: 2,
+      bootId: APP_EVENT_BOOT_ID,
+    });
+  });
+
+  it("doesn't replay an event while it is being pushed", () => {
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:1" });
+    const { replay, lastSeq } = subscribeAppEvents(0, APP_EVENT_BOOT_ID, () => {});
+    expect(replay).toEqual([]);
+    expect(lastSeq).toBe(1);
+  });
+
+  it("replays events after the subscriber's last sequence number", () => {
+    const first = subscribeAppEvents(0, APP_EVENT_BOOT_ID, () => {});
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:1" });
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:2" });
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:3" });
+    first.unsubscribe();
+
+    const { replay, lastSeq } = subscribeAppEvents(1, APP_EVENT_BOOT_ID, () => {});
+    expect(replay.map((r) => r.seq)).toEqual([2, 3]);
+    expect(lastSeq).toBe(3);
+  });
+
+  it("replays everything buffered for a subscriber from another daemon run", () => {
+    const first = subscribeAppEvents(0, APP_EVENT_BOOT_ID, () => {});
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:1" });
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:2" });
+    first.unsubscribe();
+
+    const { replay } = subscribeAppEvents(40, "1234-old", () => {});
+    expect(replay.map((r) => r.seq)).toEqual([1, 2]);
+  });
+
+  it("stops delivering after unsubscribe", () => {
+    const received: AppEventRecord[] = [];
+    const { unsubscribe } = subscribeAppEvents(0, APP_EVENT_BOOT_ID, (r) => received.push(r));
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:1" });
+    unsubscribe();
+    notifyApp({ type: "channel-unlinked", chatId: "telegram:2" });
+    expect(received).toHaveLength(1);
+  });
+});
@@ -1,5 +1,5 @@
 import { describe, it, expect } from "bun:test";
-import { SessionManager } from "../session/manager";
+import { SessionManager, type SessionLinkChange } from "../session/manager";
 import { defaultSettings } from "../config/schema";
 import type { ChannelChatId, ChannelUserId } from "../channel/types";
 
@@ -395,3 +395,109 @@ describe("getBoundChat", () => {
     expect(mgr.getBoundChat(remote.id)).toBe("telegram:-200:7");
   });
 });
+
+describe("setForwardChat / getPromptChat", () => {
+  it("routes prompts to the forward chat when no chat is bound", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    expect(mgr.getPromptChat(remote.id)).toBeNull();
+    expect(mgr.setForwardChat(remote.id, "telegram:-200" as ChannelChatId)).toBe(true);
+    expect(mgr.getPromptChat(remote.id)).toBe("telegram:-200");
+  });
+
+  it("prefers the bound chat over the forward chat", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:100" as ChannelChatId, remote.id);
+    mgr.setForwardChat(remote.id, "telegram:-200" as ChannelChatId);
+    expect(mgr.getPromptChat(remote.id)).toBe("telegram:100");
+  });
+
+  it("clears forwarding with null and on session removal", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.setForwardChat(remote.id, "telegram:-200" as ChannelChatId);
+    mgr.setForwardChat(remote.id, null);
+    expect(mgr.getForwardChat(remote.id)).toBeNull();
+    mgr.setForwardChat(remote.id, "telegram:-200" as ChannelChatId);
+    mgr.removeRemote(remote.id);
+    expect(mgr.getForwardChat(remote.id)).toBeNull();
+  });
+
+  it("returns false for unknown sessions", () => {
+    const mgr = createManager();
+    expect(mgr.setForwardChat("r-unknown", "telegram:-200" as ChannelChatId)).toBe(false);
+  });
+});
+
+describe("onLinkChange", () => {
+  function record(mgr: SessionManager): SessionLinkChange[] {
+    const changes: SessionLinkChange[] = [];
+    mgr.onLinkChange((change) => changes.push(change));
+    return changes;
+  }
+
+  it("reports attach and detach", () => {
+    const mgr = createManager();
+    const changes = record(mgr);
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:-200" as ChannelChatId, remote.id);
+    mgr.detach("telegram:-200" as ChannelChatId);
+    expect(changes).toEqual([
+      { linked: true, sessionId: remote.id, chatId: "telegram:-200" },
+      { linked: false, sessionId: remote.id, chatId: "telegram:-200" },
+    ]);
+  });
+
+  it("reports the chat a session moved away from", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:100" as ChannelChatId, remote.id);
+    const changes = record(mgr);
+    mgr.attach("telegram:-200" as ChannelChatId, remote.id);
+    expect(changes).toEqual([
+      { linked: false, sessionId: remote.id, chatId: "telegram:100" },
+      { linked: true, sessionId: remote.id, chatId: "telegram:-200" },
+    ]);
+  });
+
+  it("does not report re-attaching the same session", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:100" as ChannelChatId, remote.id);
+    const changes = record(mgr);
+    mgr.attach("telegram:100" as ChannelChatId, remote.id);
+    expect(changes).toEqual([]);
+  });
+
+  it("reports unlinks when a session is removed", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:100" as ChannelChatId, remote.id);
+    const changes = record(mgr);
+    mgr.removeRemote(remote.id);
+    expect(changes).toEqual([{ linked: false, sessionId: remote.id, chatId: "telegram:100" }]);
+  });
+});
+
+describe("listLinks", () => {
+  it("lists chats attached to running sessions", () => {
+    const mgr = createManager();
+    const a = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    const b = mgr.registerRemote("codex", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:-200" as ChannelChatId, a.id);
+    mgr.attach("telegram:-300" as ChannelChatId, b.id);
+    expect(mgr.listLinks()).toEqual([
+      { chatId: "telegram:-200", sessionId: a.id },
+      { chatId: "telegram:-300", sessionId: b.id },
+    ]);
+  });
+
+  it("drops links of removed sessions", () => {
+    const mgr = createManager();
+    const remote = mgr.registerRemote("claude", "telegram:100" as ChannelChatId, "telegram:100" as ChannelUserId);
+    mgr.attach("telegram:-200" as ChannelChatId, remote.id);
+    mgr.removeRemote(remote.id);
+    expect(mgr.listLinks()).toEqual([]);
+  });
+});
@@ -0,0 +1,40 @@
+import { describe, expect, it } from "bun:test";
+import { publishSessionOutput, subscribeSessionOutput, type SessionOutputEntry } from "../daemon/session-output";
+
+describe("session output", () => {
+  it("delivers entries only to the session's subscribers", () => {
+    const a: SessionOutputEntry[] = [];
+    const b: SessionOutputEntry[] = [];
+    const unsubA = subscribeSessionOutput("r-aaa", (e) => a.push(e));
+    const unsubB = subscribeSessionOutput("r-bbb", (e) => b.push(e));
+
+    publishSessionOutput("r-aaa", { role: "assistant", text: "hello" });
+    publishSessionOutput("r-bbb", { role: "tool", text: "Bash: ls" });
+
+    expect(a).toEqual([{ role: "assistant", text: "hello" }]);
+    expect(b).toEqual([{ role: "tool", text: "Bash: ls" }]);
+    unsubA();
+    unsubB();
+  });
+
+  it("stops delivering after unsubscribe", () => {
+    const seen: SessionOutputEntry[] = [];
+    const unsubscribe = subscribeSessionOutput("r-ccc", (e) => seen.push(e));
+    publishSessionOutput("r-ccc", { role: "status", text: "one" });
+    unsubscribe();
+    publishSessionOutput("r-ccc", { role: "status", text: "two" });
+    expect(seen).toHaveLength(1);
+  });
+
+  it("keeps delivering to other subscribers when one throws", () => {
+    const seen: SessionOutputEntry[] = [];
+    const unsubBad = subscribeSessionOutput("r-ddd", () => {
+      throw new Error("stream closed");
+    });
+    const unsubGood = subscribeSessionOutput("r-ddd", (e) => seen.push(e));
+    publishSessionOutput("r-ddd", { role: "user", text: "hi" });
+    expect(seen).toHaveLength(1);
+    unsubBad();
+    unsubGood();
+  });
+});
@@ -0,0 +1,25 @@
+import { describe, expect, it } from "bun:test";
+import { uploadFileName } from "../daemon/control-server";
+
+describe("uploadFileName", () => {
+  it("keeps ordinary names", () => {
+    expect(uploadFileName("report-v2.pdf")).toBe("report-v2.pdf");
+  });
+
+  it("drops directories from either separator", () => {
+    expect(uploadFileName("/Users/me/shot.png")).toBe("shot.png");
+    expect(uploadFileName("C:\\Users\\me\\shot.png")).toBe("shot.png");
+  });
+
+  it("replaces unsafe characters and leading dots", () => {
+    expect(uploadFileName("my notes (1).txt")).toBe("my_notes_1_.txt");
+    expect(uploadFileName(".env")).toBe("env");
+    expect(uploadFileName("..")).toBe("");
+  });
+
+  it("keeps the end of long names so the extension survives", () => {
+    const name = uploadFileName(`${"a".repeat(200)}.png`);
+    expect(name).toHaveLength(100);
+    expect(name.endsWith(".png")).toBe(true);
+  });
+});
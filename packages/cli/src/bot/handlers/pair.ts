@@ -42,7 +42,7 @@ export async function handlePair(
 
   await addPairedUser(ctx.config, userId, username, ctx.channelName);
   await logger.info("User paired", { userId, username });
-  notifyApp({ type: "user-paired", username: username || undefined });
+  notifyApp({ type: "user-paired", userId, username: username || undefined, channel: ctx.channelName });
   await ctx.channel.send(
     chatId,
     `Paired successfully! Welcome${username ? `, @${username}` : ""}.\n\nSend ${fmt.code(`touchgrass ${fmt.escape("<command>")}`)} to run a command.\nSend /start to see available commands.`
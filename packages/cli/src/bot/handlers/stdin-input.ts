@@ -2,6 +2,7 @@ import type { InboundMessage } from "../../channel/types";
 import type { RouterContext } from "../command-router";
 import type { RemoteSession } from "../../session/manager";
 import { isLinkedGroup } from "../../config/schema";
+import { notifyApp } from "../../daemon/notify-app";
 
 // If a session has a pending poll, close it and push the text as a free-form "Other" answer
 function handleTextWhilePoll(remote: RemoteSession, text: string, ctx: RouterContext): boolean {
@@ -45,6 +46,9 @@ export async function handleStdinInput(
   const appendOriginTag = (remoteSession: RemoteSession, rawText: string): string => {
     return `${rawText}\n[sent from channel_id="${chatId}" session_id="${remoteSession.id}"]`;
   };
+  const notifyRemoteInput = (remoteSession: RemoteSession) => {
+    notifyApp({ type: "remote-input", sessionId: remoteSession.id, chatId, userId, username: msg.username, text });
+  };
 
   // 1. Check attached remote sessions
   const remote = ctx.sessionManager.getAttachedRemote(chatId);
@@ -56,6 +60,7 @@ export async function handleStdinInput(
     if (!handleTextWhilePoll(remote, text, ctx)) {
       remote.inputQueue.push(appendOriginTag(remote, applyPendingFileMentions(remote, text)));
     }
+    notifyRemoteInput(remote);
     maybeSubscribeGroup(remote.id);
     return;
   }
@@ -66,6 +71,7 @@ export async function handleStdinInput(
     if (!handleTextWhilePoll(remotes[0], text, ctx)) {
       remotes[0].inputQueue.push(appendOriginTag(remotes[0], applyPendingFileMentions(remotes[0], text)));
     }
+    notifyRemoteInput(remotes[0]);
     return;
   }
 
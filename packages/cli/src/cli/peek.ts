@@ -187,7 +187,7 @@ function extractEntries(msg: Record<string, unknown>): DisplayEntry[] {
   return entries;
 }
 
-function summarizeToolInput(name: string, input: Record<string, unknown>): string {
+export function summarizeToolInput(name: string, input: Record<string, unknown>): string {
   switch (name) {
     case "Bash":
     case "bash":
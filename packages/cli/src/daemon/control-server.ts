@@ -1,11 +1,16 @@
 import type { ChannelChatId, ChannelUserId, InboundMessage } from "../channel/types";
 import type { InternalChannel } from "../channels/internal/channel";
-import { CONTROL_HOST, paths, useTcpControlServer } from "../config/paths";
+import { CONTROL_HOST, ensureDirs, paths, useTcpControlServer } from "../config/paths";
 import { logger } from "./logger";
+import { APP_EVENT_BOOT_ID, subscribeAppEvents, type AppEventRecord } from "./notify-app";
+import { collectEntriesFromRaw, summarizeToolInput } from "../cli/peek";
+import { publishSessionOutput, subscribeSessionOutput, type SessionOutputEntry } from "./session-output";
 import { removeControlPortFile, removeSocket, onShutdown } from "./lifecycle";
 import { timingSafeEqual } from "crypto";
-import { chmod, writeFile } from "fs/promises";
+import { chmod, unlink, writeFile } from "fs/promises";
+import { join } from "path";
 import type { RemoteControlAction } from "../session/remote-control";
+import type { OutputMode } from "../config/schema";
 
 export interface ChannelInfo {
   chatId: string;
@@ -32,16 +37,60 @@ export interface ConfigChannelDetails {
   linkedGroups: Array<{ chatId: string; title?: string; linkedAt: string }>;
 }
 
+export interface SessionSummary {
+  id: string;
+  command: string;
+  name?: string;
+  cwd: string;
+  /** Chat the session's output goes to, if any */
+  chatId: string | null;
+  lastSeenAt: number;
+}
+
+/** A chat connected to a running session. */
+export interface ChatLink {
+  chatId: string;
+  sessionId: string;
+  /** Session label, as shown in chats */
+  label: string;
+  /** Linked group or topic title; absent for DMs */
+  title?: string;
+  /** Whether the chat is the session owner's DM */
+  ownerDm: boolean;
+}
+
+/** Bridge preferences of one chat, as set by `/output_mode` and `/mute`. */
+export interface ChatPreferenceSummary {
+  chatId: string;
+  /** "simple" is stored as "compact" in config */
+  outputMode: "simple" | "thinking" | "verbose";
+  /** Thinking previews are sent only in thinking mode */
+  thinking: boolean;
+  muted: boolean;
+}
+
+export interface ChatPreferenceUpdate {
+  outputMode?: OutputMode;
+  /** On switches simple to thinking mode; off switches thinking back to simple */
+  thinking?: boolean;
+  muted?: boolean;
+}
+
 export interface DaemonContext {
   authToken: string;
   startedAt: number;
   getStatus: () => Record<string, unknown>;
+  listSessions: () => SessionSummary[];
   getInputNeeded: () => Array<{ sessionId: string; command: string; type: 'approval' | 'question' }>;
   shutdown: () => Promise<void>;
   generatePairingCode: () => string;
   getChannels: () => Promise<ChannelInfo[]>;
   registerRemote: (command: string, chatId: ChannelChatId, ownerUserId: ChannelUserId, cwd: string, sessionId?: string, subscribedGroups?: string[], name?: string) => Promise<{ sessionId: string; dmBusy: boolean; linkedGroups: Array<{ chatId: string; title?: string }>; allLinkedGroups: Array<{ chatId: string; title?: string }> }>;
   bindChat: (sessionId: string, chatId: ChannelChatId) => Promise<{ ok: boolean; error?: string }>;
+  unbindChat: (sessionId: string) => { ok: boolean; error?: string; chatIds?: string[] };
+  listLinks: () => ChatLink[];
+  getChatPreferences: (chatId: ChannelChatId) => { outputMode: OutputMode; muted: boolean };
+  setChatPreferences: (chatId: ChannelChatId, update: ChatPreferenceUpdate) => Promise<void>;
   canUserAccessSession: (userId: ChannelUserId, sessionId: string) => boolean;
   drainRemoteInput: (sessionId: string) => string[];
   drainRemoteControl: (sessionId: string) => RemoteControlAction | null;
@@ -50,6 +99,7 @@ export interface DaemonContext {
   endRemote: (sessionId: string, exitCode: number | null) => void;
   getSubscribedGroups: (sessionId: string) => string[];
   getBoundChat: (sessionId: string) => string | null;
+  setForwarding: (sessionId: string, chatId: ChannelChatId | null) => Promise<{ ok: boolean; error?: string }>;
   handleQuestion: (sessionId: string, questions: unknown[]) => void;
   handleToolCall: (sessionId: string, name: string, input: Record<string, unknown>) => void;
   handleTyping: (sessionId: string, active: boolean) => void;
@@ -87,6 +137,13 @@ export interface DaemonContext {
 }
 
 const MAX_BODY_SIZE = 1_048_576; // 1 MB
+const MAX_UPLOAD_SIZE = 50 * 1024 * 1024; // Largest file channels accept
+
+/** File name safe to use inside the uploads directory, or "" if nothing is left. */
+export function uploadFileName(name: string): string {
+  const base = name.split(/[\\/]/).pop() || "";
+  return base.replace(/[^\w.-]+/g, "_").replace(/^\.+/, "").slice(-100);
+}
 
 async function readJsonBody(req: Request): Promise<Record<string, unknown>> {
   const text = await req.text();
@@ -117,6 +174,109 @@ function isAuthorized(req: Request, expectedToken: string): boolean {
   return constantTimeEqual(provided, expectedToken);
 }
 
+// Transcript entries a new session stream starts with
+const SESSION_STREAM_HISTORY = 30;
+// Longest tool result shown to session stream viewers
+const SESSION_STREAM_RESULT_CHARS = 500;
+
+// Keeps idle event streams under the server's idle timeout
+const EVENT_STREAM_PING_MS = 5_000;
+
+/**
+ * Server-sent event response. `open` gets a send function and returns the
+ * cleanup to run when the client disconnects.
+ */
+function eventStream(open: (send: (chunk: string) => void) => () => void): Response {
+  const encoder = new TextEncoder();
+  let cleanup = () => {};
+  const stream = new ReadableStream<Uint8Array>({
+    start(controller) {
+      const send = (chunk: string) => controller.enqueue(encoder.encode(chunk));
+      const close = open(send);
+      const ping = setInterval(() => {
+        try {
+          send(": ping\n\n");
+        } catch {
+          cleanup();
+        }
+      }, EVENT_STREAM_PING_MS);
+      cleanup = () => {
+        clearInterval(ping);
+        close();
+      };
+    },
+    cancel() {
+      cleanup();
+    },
+  });
+  return new Response(stream, {
+    headers: {
+      "Content-Type": "text/event-stream",
+      "Cache-Control": "no-cache",
+    },
+  });
+}
+
+function chatPreferenceSummary(ctx: DaemonContext, chatId: string): ChatPreferenceSummary {
+  const { outputMode, muted } = ctx.getChatPreferences(chatId);
+  return {
+    chatId,
+    outputMode: outputMode === "compact" ? "simple" : outputMode,
+    thinking: outputMode === "thinking",
+    muted,
+  };
+}
+
+function formatAppEvent(record: AppEventRecord): string {
+  return `id: ${record.seq}\ndata: ${JSON.stringify(record.body)}\n\n`;
+}
+
+function appEventStream(since: number, bootId: string | null): Response {
+  return eventStream((send) => {
+    const { replay, lastSeq, unsubscribe } = subscribeAppEvents(
+      Number.isFinite(since) ? since : 0,
+      bootId,
+      (record) => send(formatAppEvent(record))
+    );
+    send(`event: hello\ndata: ${JSON.stringify({ bootId: APP_EVENT_BOOT_ID, lastSeq })}\n\n`);
+    for (const record of replay) send(formatAppEvent(record));
+    logger.info("App subscribed to events", { replayed: replay.length });
+    return unsubscribe;
+  });
+}
+
+function formatSessionOutput(entry: SessionOutputEntry): string {
+  return `data: ${JSON.stringify(entry)}\n\n`;
+}
+
+/** Recent transcript entries, then the session's output as it happens. */
+function sessionOutputStream(sessionId: string, history: SessionOutputEntry[]): Response {
+  return eventStream((send) => {
+    const unsubscribe = subscribeSessionOutput(sessionId, (entry) => send(formatSessionOutput(entry)));
+    for (const entry of history) send(formatSessionOutput(entry));
+    send("event: live\ndata: {}\n\n");
+    return unsubscribe;
+  });
+}
+
+async function readTranscript(sessionId: string, count: number): Promise<SessionOutputEntry[] | null> {
+  let jsonlFile: string | null;
+  try {
+    const manifest = JSON.parse(await Bun.file(join(paths.sessionsDir, `${sessionId}.json`)).text());
+    jsonlFile = typeof manifest.jsonlFile === "string" ? manifest.jsonlFile : null;
+  } catch {
+    return null;
+  }
+  if (!jsonlFile) return [];
+  let raw: string;
+  try {
+    raw = await Bun.file(jsonlFile).text();
+  } catch {
+    return [];
+  }
+  return collectEntriesFromRaw(raw, count);
+}
+
 export async function startControlServer(ctx: DaemonContext): Promise<void> {
   // Remove stale control endpoints
   await removeSocket();
@@ -155,11 +315,56 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
         return Response.json({ ok: true, pid: process.pid, startedAt: ctx.startedAt });
       }
 
+      // GET /app/events?since=<seq>&boot=<id> — server-sent event stream for the
+      // desktop app. Replays what it missed since `since`, then stays open.
+      if (path === "/app/events" && req.method === "GET") {
+        return appEventStream(
+          Number(url.searchParams.get("since") || "0"),
+          url.searchParams.get("boot")
+        );
+      }
+
       if (path === "/channels") {
         const channels = await ctx.getChannels();
         return Response.json({ ok: true, channels });
       }
 
+      // GET /chat-preferences — preferences of every runtime channel
+      if (path === "/chat-preferences" && req.method === "GET") {
+        const channels = await ctx.getChannels();
+        return Response.json({ ok: true, preferences: channels.map((c) => chatPreferenceSummary(ctx, c.chatId)) });
+      }
+
+      // POST /chat-preferences {chatId, outputMode?, thinking?, muted?}
+      if (path === "/chat-preferences" && req.method === "POST") {
+        const body = await readJsonBody(req);
+        const chatId = body.chatId as string;
+        if (!chatId) {
+          return Response.json({ ok: false, error: "Missing chatId" }, { status: 400 });
+        }
+        const channels = await ctx.getChannels();
+        if (!channels.some((c) => c.chatId === chatId)) {
+          return Response.json({ ok: false, error: "Unknown chat" }, { status: 404 });
+        }
+        const update: ChatPreferenceUpdate = {};
+        if (body.outputMode !== undefined) {
+          const mode = body.outputMode === "simple" ? "compact" : body.outputMode;
+          if (mode !== "compact" && mode !== "thinking" && mode !== "verbose") {
+            return Response.json({ ok: false, error: "outputMode must be simple, thinking or verbose" }, { status: 400 });
+          }
+          update.outputMode = mode;
+        }
+        for (const key of ["thinking", "muted"] as const) {
+          if (body[key] === undefined) continue;
+          if (typeof body[key] !== "boolean") {
+            return Response.json({ ok: false, error: `${key} must be a boolean` }, { status: 400 });
+          }
+          update[key] = body[key] as boolean;
+        }
+        await ctx.setChatPreferences(chatId, update);
+        return Response.json({ ok: true, preferences: chatPreferenceSummary(ctx, chatId) });
+      }
+
       if (path === "/input-needed") {
         return Response.json({ ok: true, sessions: ctx.getInputNeeded() });
       }
@@ -223,6 +428,63 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
         return Response.json({ ok: true, sessions });
       }
 
+      if (path === "/sessions" && req.method === "GET") {
+        return Response.json({ ok: true, sessions: ctx.listSessions() });
+      }
+
+      // GET /session/:id/peek?count=N — last N transcript entries, as `touchgrass peek`
+      const peekMatch = path.match(/^\/session\/(r-[a-f0-9]+)\/peek$/);
+      if (peekMatch && req.method === "GET") {
+        const [, sessionId] = peekMatch;
+        const count = Number(url.searchParams.get("count") || "10");
+        if (!Number.isInteger(count) || count <= 0) {
+          return Response.json({ ok: false, error: "count must be a positive integer" }, { status: 400 });
+        }
+        const entries = await readTranscript(sessionId, count);
+        if (!entries) {
+          return Response.json({ ok: false, error: "Session not found" }, { status: 404 });
+        }
+        return Response.json({ ok: true, entries });
+      }
+
+      // GET /session/:id/stream — recent transcript, then live output (SSE)
+      const streamMatch = path.match(/^\/session\/(r-[a-f0-9]+)\/stream$/);
+      if (streamMatch && req.method === "GET") {
+        const [, sessionId] = streamMatch;
+        if (!ctx.hasRemote(sessionId)) {
+          return Response.json({ ok: false, error: "Session not found" }, { status: 404 });
+        }
+        const history = (await readTranscript(sessionId, SESSION_STREAM_HISTORY)) ?? [];
+        return sessionOutputStream(sessionId, history);
+      }
+
+      if (path === "/links" && req.method === "GET") {
+        return Response.json({ ok: true, links: ctx.listLinks() });
+      }
+
+      // POST /session/:id/link {chatId} — connect to a chat, moving it off any other
+      // POST /session/:id/unlink — disconnect it from every chat
+      const linkMatch = path.match(/^\/session\/(r-[a-f0-9]+)\/(link|unlink)$/);
+      if (linkMatch && req.method === "POST") {
+        const [, sessionId, action] = linkMatch;
+        let result: { ok: boolean; error?: string; chatIds?: string[] };
+        if (action === "link") {
+          const body = await readJsonBody(req);
+          const chatId = body.chatId as string;
+          if (!chatId) {
+            return Response.json({ ok: false, error: "Missing chatId" }, { status: 400 });
+          }
+          result = await ctx.bindChat(sessionId, chatId);
+        } else {
+          result = ctx.unbindChat(sessionId);
+        }
+        if (!result.ok) {
+          const status = result.error === "Session not found" ? 404 : 400;
+          return Response.json({ ok: false, error: result.error }, { status });
+        }
+        return Response.json({ ok: true, ...(result.chatIds ? { chatIds: result.chatIds } : {}) });
+      }
+
       const sessionMatch = path.match(/^\/session\/(r-[a-f0-9]+)\/(stop|kill|restart)$/);
       if (sessionMatch && req.method === "POST") {
         const [, sessionId, action] = sessionMatch;
@@ -367,7 +629,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
       }
 
       // Match /remote/:id/* actions
-      const remoteMatch = path.match(/^\/remote\/(r-[a-f0-9]+)\/(input|exit|subscribed-groups|question|tool-call|thinking|assistant|tool-result|approval-needed|typing|background-job|send-input|send-file|send-message)$/);
+      const remoteMatch = path.match(/^\/remote\/(r-[a-f0-9]+)\/(input|exit|subscribed-groups|question|tool-call|thinking|assistant|tool-result|approval-needed|typing|background-job|send-input|send-file|upload-file|send-message|forwarding)$/);
       if (remoteMatch) {
         const [, sessionId, action] = remoteMatch;
         if (action === "assistant" && req.method === "POST") {
@@ -377,6 +639,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
             return Response.json({ ok: false, error: "Missing text" }, { status: 400 });
           }
           ctx.handleAssistantText(sessionId, text);
+          publishSessionOutput(sessionId, { role: "assistant", text });
           return Response.json({ ok: true });
         }
         if (action === "tool-result" && req.method === "POST") {
@@ -388,6 +651,11 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
             return Response.json({ ok: false, error: "Missing toolName or content" }, { status: 400 });
           }
           ctx.handleToolResult(sessionId, toolName, content, isError);
+          const shown =
+            content.length > SESSION_STREAM_RESULT_CHARS
+              ? content.slice(0, SESSION_STREAM_RESULT_CHARS) + "..."
+              : content;
+          publishSessionOutput(sessionId, { role: "tool", text: `${toolName}${isError ? " (error)" : ""} → ${shown}` });
           return Response.json({ ok: true });
         }
         if (action === "background-job" && req.method === "POST") {
@@ -413,6 +681,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
             return Response.json({ ok: false, error: "Missing text" }, { status: 400 });
           }
           ctx.handleThinking(sessionId, text);
+          publishSessionOutput(sessionId, { role: "thinking", text });
           return Response.json({ ok: true });
         }
         if (action === "tool-call" && req.method === "POST") {
@@ -423,6 +692,8 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
             return Response.json({ ok: false, error: "Missing name" }, { status: 400 });
           }
           ctx.handleToolCall(sessionId, name, input);
+          const summary = summarizeToolInput(name, input);
+          publishSessionOutput(sessionId, { role: "tool", text: summary ? `${name}: ${summary}` : name });
           return Response.json({ ok: true });
         }
         if (action === "typing" && req.method === "POST") {
@@ -441,6 +712,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
           const promptText = (body.promptText as string) || undefined;
           const pollOptions = Array.isArray(body.pollOptions) ? body.pollOptions as string[] : undefined;
           ctx.handleApprovalNeeded(sessionId, name, input, promptText, pollOptions);
+          publishSessionOutput(sessionId, { role: "status", text: `Waiting for approval: ${promptText || name}` });
           return Response.json({ ok: true });
         }
         if (action === "question" && req.method === "POST") {
@@ -450,6 +722,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
             return Response.json({ ok: false, error: "Missing questions" }, { status: 400 });
           }
           ctx.handleQuestion(sessionId, questions);
+          publishSessionOutput(sessionId, { role: "status", text: "Waiting for an answer" });
           return Response.json({ ok: true });
         }
         if (action === "input" && req.method === "GET") {
@@ -465,6 +738,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
           const body = await readJsonBody(req);
           const exitCode = (body.exitCode as number) ?? null;
           ctx.endRemote(sessionId, exitCode);
+          publishSessionOutput(sessionId, { role: "status", text: `Session exited (code ${exitCode ?? "unknown"})` });
           return Response.json({ ok: true });
         }
         if (action === "subscribed-groups" && req.method === "GET") {
@@ -482,6 +756,7 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
             return Response.json({ ok: false, error: "Missing text" }, { status: 400 });
           }
           const pushed = ctx.pushRemoteInput(sessionId, text);
+          if (pushed) publishSessionOutput(sessionId, { role: "user", text });
           return Response.json({ ok: pushed });
         }
         if (action === "send-file" && req.method === "POST") {
@@ -497,6 +772,48 @@ export async function startControlServer(ctx: DaemonContext): Promise<void> {
           }
           return Response.json({ ok: true });
         }
+        if (action === "upload-file" && req.method === "POST") {
+          // Raw file body from the desktop app, which may send files from anywhere;
+          // stored in uploads so sendFileToSession's path check accepts it
+          if (!ctx.hasRemote(sessionId)) {
+            return Response.json({ ok: false, error: "Session not found" }, { status: 404 });
+          }
+          const params = new URL(req.url, "http://localhost").searchParams;
+          const name = uploadFileName(params.get("name") || "");
+          if (!name) {
+            return Response.json({ ok: false, error: "Missing name" }, { status: 400 });
+          }
+          if (Number(req.headers.get("content-length") || "0") > MAX_UPLOAD_SIZE) {
+            return Response.json({ ok: false, error: "File exceeds 50MB channel upload limit" }, { status: 413 });
+          }
+          const data = new Uint8Array(await req.arrayBuffer());
+          if (data.byteLength === 0) {
+            return Response.json({ ok: false, error: "File is empty" }, { status: 400 });
+          }
+          if (data.byteLength > MAX_UPLOAD_SIZE) {
+            return Response.json({ ok: false, error: "File exceeds 50MB channel upload limit" }, { status: 413 });
+          }
+          await ensureDirs();
+          const filePath = join(paths.uploadsDir, `${Date.now()}-${name}`);
+          await writeFile(filePath, data, { mode: 0o600 });
+          const result = await ctx.sendFileToSession(sessionId, filePath, params.get("caption") || name);
+          if (!result.ok) {
+            await unlink(filePath).catch(() => {});
+            return Response.json({ ok: false, error: result.error || "Failed to send file" }, { status: 400 });
+          }
+          return Response.json({ ok: true });
+        }
+        if (action === "forwarding" && req.method === "POST") {
+          // Away mode: route prompts to chatId (or stop with null) while unbound
+          const body = await readJsonBody(req);
+          const chatId = typeof body.chatId === "string" && body.chatId ? body.chatId : null;
+          const result = await ctx.setForwarding(sessionId, chatId);
+          if (!result.ok) {
+            const status = result.error === "Session not found" ? 404 : 400;
+            return Response.json({ ok: false, error: result.error }, { status });
+          }
+          return Response.json({ ok: true });
+        }
         if (action === "send-message" && req.method === "POST") {
           const body = await readJsonBody(req);
           const text = body.text as string;
@@ -8,6 +8,7 @@ import {
   getTelegramBotToken,
   isLinkedGroup,
   removeLinkedGroup,
+  setChatMuted,
   setChatOutputMode,
   type OutputMode,
 } from "../config/schema";
@@ -24,10 +25,11 @@ import {
   removeSocket,
   writePidFile,
 } from "./lifecycle";
-import { startControlServer, type ChannelInfo, type ConfigChannelSummary, type ConfigChannelDetails } from "./control-server";
+import { startControlServer, type ChannelInfo, type ChatPreferenceUpdate, type ConfigChannelSummary, type ConfigChannelDetails } from "./control-server";
 import { routeMessage } from "../bot/command-router";
 import type { BackgroundJobSessionSummary } from "../bot/handlers/background-jobs";
 import { SessionManager } from "../session/manager";
+import { notifyApp } from "./notify-app";
 import { formatSimpleToolResult, formatToolCall } from "./tool-display";
 import { paths } from "../config/paths";
 import { generatePairingCode } from "../security/pairing";
@@ -112,6 +114,9 @@ export async function startDaemon(): Promise<void> {
   const daemonAuthToken = await rotateDaemonAuthToken();
 
   const sessionManager = new SessionManager(config.settings);
+  sessionManager.onLinkChange(({ linked, sessionId, chatId }) => {
+    notifyApp({ type: linked ? "session-linked" : "session-unlinked", sessionId, chatId });
+  });
 
   // Create channel instances from config
   const configuredChannels = Object.entries(config.channels);
@@ -1035,6 +1040,7 @@ export async function startDaemon(): Promise<void> {
     const previousStatus = backgroundJobAnnouncements.get(dedupeKey);
     if (previousStatus === status) return;
     backgroundJobAnnouncements.set(dedupeKey, status);
+    notifyApp({ type: "job-updated", sessionId, status, ...job });
 
     const remote = sessionManager.getRemote(sessionId);
     const tool = remote ? getSessionTool(remote.command) : "";
@@ -1807,6 +1813,16 @@ export async function startDaemon(): Promise<void> {
         })),
       };
     },
+    listSessions() {
+      return sessionManager.listRemotes().map((r) => ({
+        id: r.id,
+        command: r.command,
+        name: r.name,
+        cwd: r.cwd,
+        chatId: sessionManager.getBoundChat(r.id),
+        lastSeenAt: r.lastSeenAt,
+      }));
+    },
     getInputNeeded() {
       return sessionManager.getInputNeeded();
     },
@@ -1980,6 +1996,59 @@ export async function startDaemon(): Promise<void> {
       sendToChat(chatId, `${fmt.escape("⛳️")} ${fmt.bold(fmt.escape(sessionLabel(remote.command, remote.cwd, remote.name)))} connected`);
       return { ok: true };
     },
+    unbindChat(sessionId: string): { ok: boolean; error?: string; chatIds?: string[] } {
+      const remote = sessionManager.getRemote(sessionId);
+      if (!remote) return { ok: false, error: "Session not found" };
+      const chatIds = sessionManager.listLinks()
+        .filter((link) => link.sessionId === sessionId)
+        .map((link) => link.chatId);
+      if (chatIds.length === 0) return { ok: false, error: "Session is not linked to a chat" };
+      const label = sessionLabel(remote.command, remote.cwd, remote.name);
+      for (const chatId of chatIds) {
+        sessionManager.detach(chatId);
+        syncCommandMenuForChat(chatId, remote.ownerUserId);
+        const fmt = getFormatterForChat(chatId);
+        sendToChat(chatId, `${fmt.escape("⛳️")} ${fmt.bold(fmt.escape(label))} disconnected`);
+      }
+      return { ok: true, chatIds };
+    },
+    listLinks() {
+      const titles = new Map(getAllLinkedGroups(config).map((g) => [g.chatId, g.title]));
+      return sessionManager.listLinks().flatMap(({ chatId, sessionId }) => {
+        const remote = sessionManager.getRemote(sessionId);
+        if (!remote) return [];
+        return [{
+          chatId,
+          sessionId,
+          label: sessionLabel(remote.command, remote.cwd, remote.name),
+          title: titles.get(chatId) || undefined,
+          ownerDm: chatId === remote.chatId,
+        }];
+      });
+    },
+    getChatPreferences(chatId: ChannelChatId) {
+      return { outputMode: getChatOutputMode(config, chatId), muted: getChatMuted(config, chatId) };
+    },
+    async setChatPreferences(chatId: ChannelChatId, update: ChatPreferenceUpdate): Promise<void> {
+      await refreshConfig();
+      let changed = false;
+      if (update.outputMode) {
+        changed = setChatOutputMode(config, chatId, update.outputMode) || changed;
+      }
+      if (update.thinking !== undefined) {
+        const current = getChatOutputMode(config, chatId);
+        if (update.thinking && current === "compact") {
+          changed = setChatOutputMode(config, chatId, "thinking") || changed;
+        } else if (!update.thinking && current === "thinking") {
+          changed = setChatOutputMode(config, chatId, "compact") || changed;
+        }
+      }
+      if (update.muted !== undefined) {
+        changed = setChatMuted(config, chatId, update.muted) || changed;
+        if (update.muted) getChannelForChat(chatId)?.setTyping(chatId, false);
+      }
+      if (changed) await saveConfig(config);
+    },
     canUserAccessSession(userId: ChannelUserId, sessionId: string): boolean {
       return sessionManager.canUserAccessSession(userId, sessionId);
     },
@@ -2029,6 +2098,27 @@ export async function startDaemon(): Promise<void> {
     getBoundChat(sessionId: string): string | null {
       return sessionManager.getBoundChat(sessionId);
     },
+    async setForwarding(sessionId: string, chatId: ChannelChatId | null): Promise<{ ok: boolean; error?: string }> {
+      const remote = sessionManager.getRemote(sessionId);
+      if (!remote) return { ok: false, error: "Session not found" };
+      if (chatId && chatId !== remote.chatId) {
+        await refreshConfig();
+        if (!isLinkedGroup(config, chatId)) return { ok: false, error: "Group is not linked" };
+      }
+      const previous = sessionManager.getForwardChat(sessionId);
+      sessionManager.setForwardChat(sessionId, chatId);
+      // Nothing changes for a session that is already bound to a chat
+      if (sessionManager.getBoundChat(sessionId)) return { ok: true };
+      const label = sessionLabel(remote.command, remote.cwd, remote.name);
+      if (chatId && chatId !== previous) {
+        const fmt = getFormatterForChat(chatId);
+        sendToChat(chatId, `${fmt.escape("🌿")} ${fmt.bold(fmt.escape(label))} ${fmt.escape("— you're away, forwarding prompts here")}`);
+      } else if (!chatId && previous) {
+        const fmt = getFormatterForChat(previous);
+        sendToChat(previous, `${fmt.escape("💻")} ${fmt.bold(fmt.escape(label))} ${fmt.escape("— back at the desktop, forwarding stopped")}`);
+      }
+      return { ok: true };
+    },
     getBackgroundJobs(sessionId: string): Array<{ taskId: string; status: string; command?: string; urls?: string[]; updatedAt: number }> {
       const jobs = backgroundJobsBySession.get(sessionId);
       if (!jobs || jobs.size === 0) return [];
@@ -2215,7 +2305,7 @@ export async function startDaemon(): Promise<void> {
     handleApprovalNeeded(sessionId: string, name: string, input: Record<string, unknown>, promptText?: string, pollOptions?: string[]): void {
       const remote = sessionManager.getRemote(sessionId);
       if (!remote) { logger.info("handleApprovalNeeded: no remote", { sessionId }); return; }
-      const targetChat = sessionManager.getBoundChat(sessionId);
+      const targetChat = sessionManager.getPromptChat(sessionId);
       if (!targetChat) { logger.info("handleApprovalNeeded: no bound chat", { sessionId, chatId: remote.chatId }); return; }
       // Use the prompt text from Claude Code's terminal if available
       let question: string;
@@ -2393,7 +2483,7 @@ export async function startDaemon(): Promise<void> {
           multiSelect: (raw.multiSelect as boolean) || false,
         };
       });
-      const targetChat = sessionManager.getBoundChat(sessionId);
+      const targetChat = sessionManager.getPromptChat(sessionId);
       sessionManager.setPendingQuestions(sessionId, parsed, targetChat);
       sendNextPoll(sessionId);
     },
@@ -1,44 +1,180 @@
+import { existsSync } from "fs";
 import { join } from "path";
 import { paths } from "../config/paths";
 import { logger } from "./logger";
 
 const appPortFile = join(paths.dir, "app.port");
+const appAuthFile = join(paths.dir, "app.auth");
+const appSocketFile = join(paths.dir, "app.sock");
 
-interface AppEvent {
-  type: string;
-  [key: string]: unknown;
+/**
+ * Sent as `version` with every event. Bump when an existing event's fields
+ * change incompatibly; adding events or optional fields doesn't need it.
+ */
+export const APP_EVENT_VERSION = 1;
+
+export type AppEvent =
+  | { type: "channel-linked"; title: string; chatId: string }
+  | { type: "channel-unlinked"; chatId: string }
+  | { type: "user-paired"; userId?: string; username?: string; channel?: string }
+  | { type: "remote-input"; sessionId: string; chatId: string; userId: string; username?: string; text: string }
+  | { type: "session-linked"; sessionId: string; chatId: string }
+  | { type: "session-unlinked"; sessionId: string; chatId: string }
+  | {
+      type: "job-updated";
+      sessionId: string;
+      taskId: string;
+      status: string;
+      command?: string;
+      summary?: string;
+      urls?: string[];
+    };
+
+/**
+ * An event as sent to the app, numbered in the order it was raised. The body
+ * carries `seq` and `bootId` too, so the app can drop an event it gets both
+ * pushed and replayed.
+ */
+export interface AppEventRecord {
+  seq: number;
+  body: AppEvent & { version: number; seq: number; bootId: string };
+  /** Pushed to app.sock/app.port (or being pushed), so a subscriber needn't replay it */
+  pushed: boolean;
+}
+
+export type AppEventListener = (record: AppEventRecord) => void;
+
+// Recent events kept for subscribers that reconnect (or start after the daemon)
+const MAX_BUFFERED_EVENTS = 500;
+const buffered: AppEventRecord[] = [];
+const subscribers = new Set<AppEventListener>();
+let lastSeq = 0;
+
+/** Identifies this daemon run; sequence numbers restart with it. */
+export const APP_EVENT_BOOT_ID = `${process.pid}-${Date.now().toString(36)}`;
+
+function deliver(record: AppEventRecord): void {
+  for (const listener of subscribers) {
+    try {
+      listener(record);
+    } catch {
+      // A closed stream is cleaned up by its own cancel handler
+    }
+  }
 }
 
 /**
- * Push an event to the desktop app's hook server (fire-and-forget).
- * Reads ~/.touchgrass/app.port to discover the app. If the app isn't
- * running or the port file doesn't exist, silently does nothing.
+ * Send an event to the desktop app. Apps subscribed to the event stream get it
+ * there; otherwise it's pushed to the app's hook server (fire-and-forget).
  */
 export function notifyApp(event: AppEvent): void {
+  const seq = ++lastSeq;
+  const record: AppEventRecord = {
+    seq,
+    body: { ...event, version: APP_EVENT_VERSION, seq, bootId: APP_EVENT_BOOT_ID },
+    pushed: false,
+  };
+  buffered.push(record);
+  if (buffered.length > MAX_BUFFERED_EVENTS) buffered.shift();
+
+  if (subscribers.size > 0) {
+    deliver(record);
+    return;
+  }
+  // Counted as pushed while in flight, so an app subscribing meanwhile isn't
+  // sent it twice. If the push fails, subscribers that arrived since get it
+  // now and later ones get it replayed.
+  record.pushed = true;
+  const failed = () => {
+    record.pushed = false;
+    deliver(record);
+  };
   // Async but we don't await — fire and forget
-  doNotify(event).catch(() => {});
+  doNotify(record.body)
+    .then((ok) => {
+      if (!ok) failed();
+    })
+    .catch(failed);
 }
 
-async function doNotify(event: AppEvent): Promise<void> {
+/**
+ * Subscribe to app events after `since`. Events from another daemon run
+ * (`bootId` differs) are all replayed. Returns the buffered events the
+ * subscriber missed, oldest first, and an unsubscribe function.
+ */
+export function subscribeAppEvents(
+  since: number,
+  bootId: string | null,
+  listener: AppEventListener
+): { replay: AppEventRecord[]; lastSeq: number; unsubscribe: () => void } {
+  const from = bootId === APP_EVENT_BOOT_ID ? since : 0;
+  const replay = buffered.filter((r) => r.seq > from && !r.pushed);
+  subscribers.add(listener);
+  return {
+    replay,
+    lastSeq,
+    unsubscribe: () => {
+      subscribers.delete(listener);
+    },
+  };
+}
+
+/** Clear buffered events and subscribers (tests). */
+export function resetAppEvents(): void {
+  buffered.length = 0;
+  subscribers.clear();
+  lastSeq = 0;
+}
+
+/**
+ * Push an event to the desktop app's hook server.
+ * Prefers ~/.touchgrass/app.sock and falls back to the TCP port in app.port;
+ * app.auth holds the per-launch secret. Resolves false if the app isn't
+ * running or the files don't exist.
+ */
+async function doNotify(event: AppEventRecord["body"]): Promise<boolean> {
+  let auth: string;
+  try {
+    auth = (await Bun.file(appAuthFile).text()).trim();
+  } catch {
+    return false; // App not running
+  }
+
+  const body = JSON.stringify(event);
+  const init = {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+      "Content-Length": String(Buffer.byteLength(body)),
+      "x-touchgrass-auth": auth,
+    },
+    body,
+    signal: AbortSignal.timeout(2000),
+  };
+
+  if (process.platform !== "win32" && existsSync(appSocketFile)) {
+    try {
+      const res = await fetch("http://localhost/event", { ...init, unix: appSocketFile });
+      return res.ok;
+    } catch {
+      // Stale socket — fall back to TCP
+    }
+  }
+
   let portStr: string;
   try {
     portStr = await Bun.file(appPortFile).text();
   } catch {
-    return; // App not running
+    return false; // App not running
   }
 
   const port = parseInt(portStr.trim(), 10);
-  if (!port || port < 1 || port > 65535) return;
+  if (!port || port < 1 || port > 65535) return false;
 
-  const body = JSON.stringify(event);
   try {
-    await fetch(`http://127.0.0.1:${port}/event`, {
-      method: "POST",
-      headers: { "Content-Type": "application/json", "Content-Length": String(Buffer.byteLength(body)) },
-      body,
-      signal: AbortSignal.timeout(2000),
-    });
+    const res = await fetch(`http://127.0.0.1:${port}/event`, init);
+    return res.ok;
   } catch {
-    // App not reachable — ignore
+    return false; // App not reachable
   }
 }
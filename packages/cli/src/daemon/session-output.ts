@@ -0,0 +1,39 @@
+/**
+ * Live transcript of bridged sessions for viewers outside the session's own
+ * terminal (the desktop app's remote tabs). Entries use the same roles as
+ * `touchgrass peek`, plus thinking and status lines.
+ */
+export interface SessionOutputEntry {
+  role: "assistant" | "user" | "tool" | "thinking" | "status";
+  text: string;
+}
+
+export type SessionOutputListener = (entry: SessionOutputEntry) => void;
+
+const listeners = new Map<string, Set<SessionOutputListener>>();
+
+export function publishSessionOutput(sessionId: string, entry: SessionOutputEntry): void {
+  const subscribers = listeners.get(sessionId);
+  if (!subscribers) return;
+  for (const listener of subscribers) {
+    try {
+      listener(entry);
+    } catch {
+      // A closed stream is cleaned up by its own cancel handler
+    }
+  }
+}
+
+/** Follow a session's output. Returns an unsubscribe function. */
+export function subscribeSessionOutput(sessionId: string, listener: SessionOutputListener): () => void {
+  let subscribers = listeners.get(sessionId);
+  if (!subscribers) {
+    subscribers = new Set();
+    listeners.set(sessionId, subscribers);
+  }
+  subscribers.add(listener);
+  return () => {
+    subscribers.delete(listener);
+    if (subscribers.size === 0) listeners.delete(sessionId);
+  };
+}
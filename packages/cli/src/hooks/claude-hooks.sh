@@ -24,10 +24,34 @@ if [ -n "$TG_SESSION_ID" ]; then
   ) &
 fi
 
-# App mode: forward to local HTTP server
-if [ -n "$TOUCHGRASS_APP_PORT" ] && [ -n "$TOUCHGRASS_SESSION_ID" ]; then
-  printf '%s' "$INPUT" | curl -sS --max-time 2 -X POST -H "Content-Type: application/json" \
-    -d @- "http://127.0.0.1:$TOUCHGRASS_APP_PORT/hook/$TOUCHGRASS_SESSION_ID" >/dev/null 2>&1 &
+# App mode: forward to the app's hook server (Unix socket, TCP fallback)
+app_post() {
+  # $1 = curl --max-time; request body on stdin, response body on stdout
+  if [ -n "$TOUCHGRASS_APP_SOCK" ] && [ -S "$TOUCHGRASS_APP_SOCK" ]; then
+    curl -sS --max-time "$1" --unix-socket "$TOUCHGRASS_APP_SOCK" \
+      -X POST -H "Content-Type: application/json" -H "x-touchgrass-auth: $TOUCHGRASS_APP_AUTH" \
+      -d @- "http://localhost/hook/$TOUCHGRASS_SESSION_ID" 2>/dev/null
+  elif [ -n "$TOUCHGRASS_APP_PORT" ]; then
+    curl -sS --max-time "$1" -X POST -H "Content-Type: application/json" \
+      -H "x-touchgrass-auth: $TOUCHGRASS_APP_AUTH" \
+      -d @- "http://127.0.0.1:$TOUCHGRASS_APP_PORT/hook/$TOUCHGRASS_SESSION_ID" 2>/dev/null
+  fi
+}
+
+if [ -n "$TOUCHGRASS_SESSION_ID" ]; then
+  case "$INPUT" in
+    *'"hook_event_name":"PreToolUse"'*|*'"hook_event_name": "PreToolUse"'*|\
+    *'"hook_event_name":"PermissionRequest"'*|*'"hook_event_name": "PermissionRequest"'*)
+      # The app may hold these open for an approval decision — wait and relay it
+      RESPONSE=$(printf '%s' "$INPUT" | app_post 58)
+      case "$RESPONSE" in
+        *hookSpecificOutput*) printf '%s' "$RESPONSE" ;;
+      esac
+      ;;
+    *)
+      printf '%s' "$INPUT" | app_post 2 >/dev/null &
+      ;;
+  esac
 fi
 
 exit 0
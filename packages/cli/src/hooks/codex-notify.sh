@@ -0,0 +1,21 @@
+#!/bin/bash
+# touchgrass notify program for Codex.
+# Codex runs it with a JSON event as the last argument (e.g. agent-turn-complete);
+# it is POSTed to the app, which maps it onto the Claude Code hook event model.
+# Written by the touchgrass app into ~/.touchgrass/hooks/ and passed to Codex with -c notify=[...].
+INPUT="${!#}"
+
+if [ -n "$TOUCHGRASS_SESSION_ID" ] && [ -n "$INPUT" ]; then
+  if [ -n "$TOUCHGRASS_APP_SOCK" ] && [ -S "$TOUCHGRASS_APP_SOCK" ]; then
+    printf '%s' "$INPUT" | curl -sS --max-time 2 --unix-socket "$TOUCHGRASS_APP_SOCK" \
+      -X POST -H "Content-Type: application/json" -H "x-touchgrass-auth: $TOUCHGRASS_APP_AUTH" \
+      -H "x-touchgrass-source: codex" \
+      -d @- "http://localhost/hook/$TOUCHGRASS_SESSION_ID" >/dev/null 2>&1
+  elif [ -n "$TOUCHGRASS_APP_PORT" ]; then
+    printf '%s' "$INPUT" | curl -sS --max-time 2 -X POST -H "Content-Type: application/json" \
+      -H "x-touchgrass-auth: $TOUCHGRASS_APP_AUTH" -H "x-touchgrass-source: codex" \
+      -d @- "http://127.0.0.1:$TOUCHGRASS_APP_PORT/hook/$TOUCHGRASS_SESSION_ID" >/dev/null 2>&1
+  fi
+fi
+
+exit 0
@@ -0,0 +1,23 @@
+#!/bin/bash
+# touchgrass hook for Gemini CLI lifecycle events.
+# Reads hook JSON from stdin and POSTs it to the app, which maps it onto the
+# Claude Code hook event model. Never blocks or changes Gemini's behaviour.
+# Installed by the touchgrass app into ~/.touchgrass/hooks/ and referenced in ~/.gemini/settings.json.
+INPUT=$(cat)
+
+if [ -n "$TOUCHGRASS_SESSION_ID" ]; then
+  (
+    if [ -n "$TOUCHGRASS_APP_SOCK" ] && [ -S "$TOUCHGRASS_APP_SOCK" ]; then
+      printf '%s' "$INPUT" | curl -sS --max-time 2 --unix-socket "$TOUCHGRASS_APP_SOCK" \
+        -X POST -H "Content-Type: application/json" -H "x-touchgrass-auth: $TOUCHGRASS_APP_AUTH" \
+        -H "x-touchgrass-source: gemini" \
+        -d @- "http://localhost/hook/$TOUCHGRASS_SESSION_ID" >/dev/null 2>&1
+    elif [ -n "$TOUCHGRASS_APP_PORT" ]; then
+      printf '%s' "$INPUT" | curl -sS --max-time 2 -X POST -H "Content-Type: application/json" \
+        -H "x-touchgrass-auth: $TOUCHGRASS_APP_AUTH" -H "x-touchgrass-source: gemini" \
+        -d @- "http://127.0.0.1:$TOUCHGRASS_APP_PORT/hook/$TOUCHGRASS_SESSION_ID" >/dev/null 2>&1
+    fi
+  ) &
+fi
+
+exit 0
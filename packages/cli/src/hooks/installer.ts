@@ -8,18 +8,39 @@ const CLAUDE_SETTINGS_PATH = join(homedir(), ".claude", "settings.json");
 // Superset wrapper overrides settings via --settings flag; check for its config too
 const SUPERSET_SETTINGS_PATH = join(homedir(), ".superset", "hooks", "claude-settings.json");
 
-const HOOK_EVENTS = ["UserPromptSubmit", "Stop", "PermissionRequest"] as const;
+const HOOK_EVENTS = ["UserPromptSubmit", "Stop", "PreToolUse", "PermissionRequest"] as const;
 
-function buildHookEntry(event: string): { matcher?: string; hooks: { type: string; command: string; async: boolean; timeout: number }[] } {
-  const entry: { matcher?: string; hooks: { type: string; command: string; async: boolean; timeout: number }[] } = {
-    hooks: [{ type: "command", command: HOOK_SCRIPT_PATH, async: true, timeout: 5 }],
-  };
-  if (event === "PermissionRequest") {
+// Claude waits for these so the desktop app can answer approvals. The app holds
+// a request for at most 55s and the script gives up at 58s, so allow 60s.
+const SYNC_EVENTS: readonly string[] = ["PreToolUse", "PermissionRequest"];
+const SYNC_TIMEOUT = 60;
+const ASYNC_TIMEOUT = 5;
+
+type HookCommand = { type: string; command: string; async?: boolean; timeout: number };
+
+function buildHook(event: string): HookCommand {
+  if (SYNC_EVENTS.includes(event)) {
+    return { type: "command", command: HOOK_SCRIPT_PATH, timeout: SYNC_TIMEOUT };
+  }
+  return { type: "command", command: HOOK_SCRIPT_PATH, async: true, timeout: ASYNC_TIMEOUT };
+}
+
+function buildHookEntry(event: string): { matcher?: string; hooks: HookCommand[] } {
+  const entry: { matcher?: string; hooks: HookCommand[] } = { hooks: [buildHook(event)] };
+  if (SYNC_EVENTS.includes(event)) {
     entry.matcher = "*";
   }
   return entry;
 }
 
+/** Make an installed hook sync if an older install registered it as async. Returns true if changed. */
+function upgradeHook(event: string, hook: Partial<HookCommand>): boolean {
+  if (!SYNC_EVENTS.includes(event) || (!hook.async && hook.timeout === SYNC_TIMEOUT)) return false;
+  delete hook.async;
+  hook.timeout = SYNC_TIMEOUT;
+  return true;
+}
+
 export async function installClaudeHooks(): Promise<{ scriptInstalled: boolean; settingsUpdated: boolean }> {
   const { readFile, writeFile, copyFile, chmod, mkdir } = await import("fs/promises");
 
@@ -52,15 +73,16 @@ export async function installClaudeHooks(): Promise<{ scriptInstalled: boolean;
     let needsWrite = false;
 
     for (const event of HOOK_EVENTS) {
-      const existing = hooks[event] as Array<{ hooks?: Array<{ command?: string }> }> | undefined;
-      const alreadyInstalled = existing?.some(
-        (entry) => entry.hooks?.some((h) => h.command === HOOK_SCRIPT_PATH)
-      );
-      if (!alreadyInstalled) {
+      const existing = hooks[event] as Array<{ hooks?: Array<Partial<HookCommand>> }> | undefined;
+      const ours = existing?.flatMap((entry) => entry.hooks ?? []).filter((h) => h.command === HOOK_SCRIPT_PATH) ?? [];
+      if (ours.length === 0) {
         if (!hooks[event]) hooks[event] = [];
         (hooks[event] as unknown[]).push(buildHookEntry(event));
         needsWrite = true;
       }
+      for (const hook of ours) {
+        if (upgradeHook(event, hook)) needsWrite = true;
+      }
     }
 
     if (needsWrite) {
@@ -81,16 +103,17 @@ export async function installClaudeHooks(): Promise<{ scriptInstalled: boolean;
     const supersetHooks = (supersetSettings.hooks || {}) as Record<string, unknown[]>;
     let needsSupersetWrite = false;
     for (const event of HOOK_EVENTS) {
-      const existing = supersetHooks[event] as Array<{ hooks?: Array<{ command?: string }> }> | undefined;
-      const alreadyInstalled = existing?.some(
-        (entry) => entry.hooks?.some((h) => h.command === HOOK_SCRIPT_PATH)
-      );
-      if (!alreadyInstalled) {
+      const existing = supersetHooks[event] as Array<{ hooks?: Array<Partial<HookCommand>> }> | undefined;
+      const ours = existing?.flatMap((entry) => entry.hooks ?? []).filter((h) => h.command === HOOK_SCRIPT_PATH) ?? [];
+      for (const hook of ours) {
+        if (upgradeHook(event, hook)) needsSupersetWrite = true;
+      }
+      if (ours.length === 0) {
         // Superset uses a flat hooks array per event — add our hook to each entry's hooks array
         if (Array.isArray(supersetHooks[event])) {
           for (const entry of supersetHooks[event] as Array<{ hooks?: unknown[] }>) {
             if (Array.isArray(entry.hooks)) {
-              entry.hooks.push({ type: "command", command: HOOK_SCRIPT_PATH, async: true, timeout: 5 });
+              entry.hooks.push(buildHook(event));
               needsSupersetWrite = true;
             }
           }
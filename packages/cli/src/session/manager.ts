@@ -111,6 +111,12 @@ export interface PendingRemoteControlPicker {
   options: RemoteControlPickerOption[];
 }
 
+export interface SessionLinkChange {
+  linked: boolean;
+  sessionId: string;
+  chatId: ChannelChatId;
+}
+
 export class SessionManager {
   private remotes: Map<string, RemoteSession> = new Map();
   // Map: channelChatId → sessionId (attached session)
@@ -133,9 +139,26 @@ export class SessionManager {
   private pendingRecentMessagesPolls: Map<string, PendingRecentMessagesPoll> = new Map();
   // Map: sessionId|chatId|userId → file mentions to prepend on next text input
   private pendingFileMentions: Map<string, string[]> = new Map();
+  // Map: sessionId → chat that receives prompts while the desktop user is away
+  private forwardChats: Map<string, ChannelChatId> = new Map();
+
+  // Told when a chat is connected to or disconnected from a session
+  private linkListener: ((change: SessionLinkChange) => void) | null = null;
 
   constructor(_settings: TgSettings) {}
 
+  onLinkChange(listener: ((change: SessionLinkChange) => void) | null): void {
+    this.linkListener = listener;
+  }
+
+  private emitLinkChange(change: SessionLinkChange): void {
+    try {
+      this.linkListener?.(change);
+    } catch {
+      // Listeners must not break attach/detach
+    }
+  }
+
   list(): SessionInfo[] {
     const remote = Array.from(this.remotes.values()).map((r) => ({
       id: r.id,
@@ -168,19 +191,34 @@ export class SessionManager {
   attach(chatId: ChannelChatId, sessionId: string): boolean {
     if (!sessionId.startsWith("r-")) return false;
     if (!this.remotes.has(sessionId)) return false;
+    const previous = this.attachments.get(chatId);
     for (const [existingChatId, existingSessionId] of this.attachments) {
       if (existingSessionId === sessionId) {
         this.attachments.delete(existingChatId);
+        if (existingChatId !== chatId) {
+          this.emitLinkChange({ linked: false, sessionId, chatId: existingChatId });
+        }
       }
     }
     this.removeChatFromAllGroupSubscriptions(chatId);
     this.attachments.set(chatId, sessionId);
+    if (previous && previous !== sessionId) {
+      this.emitLinkChange({ linked: false, sessionId: previous, chatId });
+    }
+    if (previous !== sessionId) {
+      this.emitLinkChange({ linked: true, sessionId, chatId });
+    }
     return true;
   }
 
   detach(chatId: ChannelChatId): boolean {
     this.removeChatFromAllGroupSubscriptions(chatId);
-    return this.attachments.delete(chatId);
+    const sessionId = this.attachments.get(chatId);
+    const removed = this.attachments.delete(chatId);
+    if (sessionId) {
+      this.emitLinkChange({ linked: false, sessionId, chatId });
+    }
+    return removed;
   }
 
   // Reverse lookup: find which chat a session is bound to
@@ -207,6 +245,32 @@ export class SessionManager {
     return null;
   }
 
+  // Every chat connected to a running remote session
+  listLinks(): Array<{ chatId: ChannelChatId; sessionId: string }> {
+    const links: Array<{ chatId: ChannelChatId; sessionId: string }> = [];
+    for (const [chatId, sessionId] of this.attachments) {
+      if (this.remotes.has(sessionId)) links.push({ chatId, sessionId });
+    }
+    return links;
+  }
+
+  setForwardChat(sessionId: string, chatId: ChannelChatId | null): boolean {
+    if (!this.remotes.has(sessionId)) return false;
+    if (chatId) this.forwardChats.set(sessionId, chatId);
+    else this.forwardChats.delete(sessionId);
+    return true;
+  }
+
+  getForwardChat(sessionId: string): ChannelChatId | null {
+    return this.forwardChats.get(sessionId) ?? null;
+  }
+
+  // Where prompts and permission requests go: the bound chat, or the
+  // away-mode forward chat when nothing is bound
+  getPromptChat(sessionId: string): ChannelChatId | null {
+    return this.getBoundChat(sessionId) ?? this.getForwardChat(sessionId);
+  }
+
   requestRemoteStop(id: string): boolean {
     const remote = this.remotes.get(id);
     if (!remote) return false;
@@ -304,10 +368,14 @@ export class SessionManager {
     const remote = this.remotes.get(id);
     if (remote) {
       for (const [chatId, sid] of this.attachments) {
-        if (sid === id) this.attachments.delete(chatId);
+        if (sid === id) {
+          this.attachments.delete(chatId);
+          this.emitLinkChange({ linked: false, sessionId: id, chatId });
+        }
       }
       this.remotes.delete(id);
       this.groupSubscriptions.delete(id);
+      this.forwardChats.delete(id);
       this.clearPendingQuestions(id);
       for (const [pollId, picker] of this.pendingFilePickers) {
         if (picker.sessionId === id) this.pendingFilePickers.delete(pollId);